@@ -0,0 +1,111 @@
+use crate::{ForkedEvm, ForkerError};
+use revm::{
+    db::AccountState,
+    primitives::{AccountInfo, Address, Bytes, KECCAK_EMPTY, U256},
+    DatabaseRef,
+};
+
+impl ForkedEvm {
+    /// Account info on the active fork, including local modifications.
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "trace", skip(self)))]
+    pub fn account_info(&self, address: Address) -> Result<Option<AccountInfo>, ForkerError> {
+        Ok(self.traced_read(|| self.executor.backend.basic_ref(address))?)
+    }
+
+    pub fn balance_of(&self, address: Address) -> Result<U256, ForkerError> {
+        Ok(self
+            .account_info(address)?
+            .map_or(U256::ZERO, |info| info.balance))
+    }
+
+    pub fn nonce_of(&self, address: Address) -> Result<u64, ForkerError> {
+        Ok(self.account_info(address)?.map_or(0, |info| info.nonce))
+    }
+
+    /// Runtime code at `address`, empty for accounts without code.
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "trace", skip(self)))]
+    pub fn code_at(&self, address: Address) -> Result<Bytes, ForkerError> {
+        let Some(info) = self.account_info(address)? else {
+            return Ok(Bytes::new());
+        };
+        match info.code {
+            Some(code) => Ok(code.original_bytes()),
+            None => Ok(self
+                .traced_read(|| self.executor.backend.code_by_hash_ref(info.code_hash))?
+                .original_bytes()),
+        }
+    }
+
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "trace", skip(self)))]
+    pub fn storage_at(&self, address: Address, slot: U256) -> Result<U256, ForkerError> {
+        Ok(self.traced_read(|| self.executor.backend.storage_ref(address, slot))?)
+    }
+
+    /// Discards local changes to the balance, nonce, code and storage of
+    /// `address` on the active fork, restoring its state at the fork block.
+    /// Other accounts keep their local changes.
+    pub fn reset_account(&mut self, address: Address) -> Result<(), ForkerError> {
+        let remote = self.fork_backend()?.clone();
+        let info = remote.basic_ref(address)?.unwrap_or_default();
+        let code = match info.code {
+            Some(code) => code.original_bytes(),
+            None if info.code_hash == KECCAK_EMPTY => Bytes::new(),
+            None => remote.code_by_hash_ref(info.code_hash)?.original_bytes(),
+        };
+        // every slot written locally is in the fork's cache, so resetting
+        // those restores the whole remote storage
+        let slots: Vec<U256> = self
+            .local_accounts()
+            .get(&address)
+            .map(|account| account.storage.keys().copied().collect())
+            .unwrap_or_default();
+        let storage = slots
+            .into_iter()
+            .map(|slot| Ok((slot, remote.storage_ref(address, slot)?)))
+            .collect::<Result<Vec<_>, ForkerError>>()?;
+        self.insert_account(address, info.balance, info.nonce, code, storage)?;
+        // a locally cleared storage reads uncached slots as zero, the reset
+        // account reads them from the fork again
+        if let Some(account) = self.local_accounts_mut()?.get_mut(&address) {
+            account.account_state = AccountState::None;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{testing::evm_with_code, ForkerError};
+    use revm::primitives::{hex, Address, Bytes, U256};
+
+    #[tokio::test]
+    async fn test_account_helpers() {
+        let target = Address::repeat_byte(0x11);
+        let forked_evm = evm_with_code(&[(target, "600160020160005260206000f3")]).await;
+        assert_eq!(
+            forked_evm.code_at(target).unwrap(),
+            Bytes::from(hex::decode("600160020160005260206000f3").unwrap())
+        );
+        assert_eq!(forked_evm.balance_of(target).unwrap(), U256::ZERO);
+        assert_eq!(forked_evm.nonce_of(target).unwrap(), 0);
+        assert_eq!(
+            forked_evm.storage_at(target, U256::ZERO).unwrap(),
+            U256::ZERO
+        );
+
+        let empty = Address::repeat_byte(0x22);
+        assert!(forked_evm.code_at(empty).unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_reset_account_requires_a_fork() {
+        let target = Address::repeat_byte(0x11);
+        let mut forked_evm = evm_with_code(&[(target, "00")]).await;
+        assert!(matches!(
+            forked_evm.reset_account(target),
+            Err(ForkerError::NoActiveFork)
+        ));
+        // nothing was reset
+        assert!(!forked_evm.code_at(target).unwrap().is_empty());
+    }
+}
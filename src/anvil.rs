@@ -0,0 +1,151 @@
+use crate::{ForkedEvm, ForkerError};
+use revm::{
+    db::AccountState,
+    primitives::{Address, BlockEnv, Bytes, U256},
+};
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, fs, path::Path};
+
+/// State in the json shape anvil uses for `anvil_dumpState`/`--dump-state`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SerializableState {
+    pub block: Option<BlockEnv>,
+    pub accounts: BTreeMap<Address, SerializableAccountRecord>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SerializableAccountRecord {
+    pub nonce: u64,
+    pub balance: U256,
+    pub code: Bytes,
+    pub storage: BTreeMap<U256, U256>,
+}
+
+impl ForkedEvm {
+    /// Dumps the locally modified accounts of the active fork as anvil state,
+    /// with their modified slots. State only read from the fork's RPC is
+    /// left out, without a fork every local account is dumped.
+    pub fn dump_anvil_state(&self) -> SerializableState {
+        let remote = self
+            .executor
+            .backend
+            .active_fork_db()
+            .map(|fork_db| fork_db.db.data());
+        let remote_accounts = remote.as_ref().map(|data| data.accounts.read());
+        let remote_storage = remote.as_ref().map(|data| data.storage.read());
+
+        let accounts = self
+            .local_accounts()
+            .iter()
+            .filter(|(_, account)| account.account_state != AccountState::NotExisting)
+            .filter_map(|(address, account)| {
+                let remote_info = remote_accounts
+                    .as_ref()
+                    .and_then(|accounts| accounts.get(address));
+                let remote_slots = remote_storage
+                    .as_ref()
+                    .and_then(|storage| storage.get(address));
+                let info = &account.info;
+                let info_modified = remote_info.map_or(true, |remote| {
+                    remote.balance != info.balance
+                        || remote.nonce != info.nonce
+                        || remote.code_hash != info.code_hash
+                });
+                let storage: BTreeMap<U256, U256> = account
+                    .storage
+                    .iter()
+                    .filter(|(slot, value)| {
+                        remote_slots.and_then(|slots| slots.get(*slot)) != Some(*value)
+                    })
+                    .map(|(slot, value)| (*slot, *value))
+                    .collect();
+                if !info_modified && storage.is_empty() {
+                    return None;
+                }
+                let code = info
+                    .code
+                    .as_ref()
+                    .map(|code| code.original_bytes())
+                    .unwrap_or_default();
+                let record = SerializableAccountRecord {
+                    nonce: info.nonce,
+                    balance: info.balance,
+                    code,
+                    storage,
+                };
+                Some((*address, record))
+            })
+            .collect();
+        SerializableState {
+            block: Some(self.executor.env.block.clone()),
+            accounts,
+        }
+    }
+
+    /// Applies an anvil state dump on top of the active fork. The dump's
+    /// block env, if any, replaces the current one.
+    pub fn load_anvil_state(&mut self, state: SerializableState) -> Result<(), ForkerError> {
+        for (address, record) in state.accounts {
+            self.insert_account(
+                address,
+                record.balance,
+                record.nonce,
+                record.code,
+                record.storage,
+            )?;
+        }
+        if let Some(block) = state.block {
+            self.executor.env.block = block;
+        }
+        Ok(())
+    }
+
+    pub fn export_anvil_state(&self, path: impl AsRef<Path>) -> Result<(), ForkerError> {
+        fs::write(path, serde_json::to_vec(&self.dump_anvil_state())?)?;
+        Ok(())
+    }
+
+    pub fn import_anvil_state(&mut self, path: impl AsRef<Path>) -> Result<(), ForkerError> {
+        let state: SerializableState = serde_json::from_slice(&fs::read(path)?)?;
+        self.load_anvil_state(state)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::evm_with_code;
+
+    #[tokio::test]
+    async fn test_anvil_state_round_trips() {
+        let account = Address::repeat_byte(0x11);
+        let record = SerializableAccountRecord {
+            nonce: 3,
+            balance: U256::from(100),
+            code: Bytes::from_static(&[0x00]),
+            storage: BTreeMap::from([(U256::from(1), U256::from(2))]),
+        };
+        let mut forked_evm = evm_with_code(&[]).await;
+        forked_evm
+            .load_anvil_state(SerializableState {
+                block: None,
+                accounts: BTreeMap::from([(account, record.clone())]),
+            })
+            .unwrap();
+
+        let dumped = forked_evm.dump_anvil_state();
+        let dumped_record = &dumped.accounts[&account];
+        assert_eq!(dumped_record.nonce, record.nonce);
+        assert_eq!(dumped_record.balance, record.balance);
+        assert_eq!(dumped_record.code, record.code);
+        assert_eq!(dumped_record.storage, record.storage);
+    }
+
+    #[tokio::test]
+    async fn test_dump_leaves_out_accounts_only_read() {
+        let forked_evm = evm_with_code(&[]).await;
+        let empty = Address::repeat_byte(0x22);
+        forked_evm.call(&[0u8; 20], empty.as_slice(), &[]).unwrap();
+        assert!(!forked_evm.dump_anvil_state().accounts.contains_key(&empty));
+    }
+}
@@ -0,0 +1,105 @@
+use crate::{CallOptions, ForkedEvm, ForkerError};
+use std::time::{Duration, Instant};
+
+/// Latency, gas and RPC usage of a read repeated by [ForkedEvm::bench_call].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BenchReport {
+    pub runs: usize,
+    pub min: Duration,
+    pub p50: Duration,
+    pub p90: Duration,
+    pub p99: Duration,
+    pub max: Duration,
+    pub mean: Duration,
+    /// Gas used by the first run. Reads against the same state use the
+    /// same gas every time, unless the first run was cold.
+    pub gas_used: u64,
+    /// Accounts and slots fetched from the fork's RPC by the first run,
+    /// which warms the cache for the rest.
+    pub first_run_rpc_fetches: usize,
+    /// Accounts and slots fetched from the fork's RPC across all runs.
+    pub rpc_fetches: usize,
+}
+
+impl ForkedEvm {
+    /// Runs the same read `runs` times against the current state, bypassing
+    /// memoized reads, e.g. to compare RPC providers or the effect of
+    /// prefetching on a cold fork.
+    pub fn bench_call(
+        &self,
+        runs: usize,
+        from_address: &[u8],
+        to_address: &[u8],
+        calldata: &[u8],
+    ) -> Result<BenchReport, ForkerError> {
+        if runs == 0 {
+            return Err(ForkerError::ZeroBenchRuns);
+        }
+        let fetched = || {
+            let (accounts, slots) = self.fetched_entries();
+            accounts + slots
+        };
+        let fetched_before = fetched();
+        let mut first_run_rpc_fetches = 0;
+        let mut gas_used = 0;
+        let mut durations = Vec::with_capacity(runs);
+        for run in 0..runs {
+            let started = Instant::now();
+            let result =
+                self.call_with_opts(from_address, to_address, calldata, &CallOptions::default())?;
+            durations.push(started.elapsed());
+            if run == 0 {
+                gas_used = result.gas_used;
+                first_run_rpc_fetches = fetched().saturating_sub(fetched_before);
+            }
+        }
+        let rpc_fetches = fetched().saturating_sub(fetched_before);
+
+        let mean = durations.iter().sum::<Duration>() / runs as u32;
+        durations.sort();
+        // nearest rank percentile
+        let percentile = |p: usize| durations[(runs * p).div_ceil(100).saturating_sub(1)];
+        Ok(BenchReport {
+            runs,
+            min: durations[0],
+            p50: percentile(50),
+            p90: percentile(90),
+            p99: percentile(99),
+            max: durations[runs - 1],
+            mean,
+            gas_used,
+            first_run_rpc_fetches,
+            rpc_fetches,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        testing::{evm_with_code, returning_code},
+        ForkerError,
+    };
+    use revm::primitives::Address;
+
+    #[tokio::test]
+    async fn test_bench_call_reports_every_run() {
+        let target = Address::repeat_byte(0x22);
+        let code = returning_code(&[1]);
+        let forked_evm = evm_with_code(&[(target, &code)]).await;
+        assert!(matches!(
+            forked_evm.bench_call(0, Address::ZERO.as_slice(), target.as_slice(), &[]),
+            Err(ForkerError::ZeroBenchRuns)
+        ));
+
+        let report = forked_evm
+            .bench_call(10, Address::ZERO.as_slice(), target.as_slice(), &[])
+            .unwrap();
+        assert_eq!(report.runs, 10);
+        assert!(report.min <= report.p50 && report.p50 <= report.p90);
+        assert!(report.p99 <= report.max);
+        assert!(report.gas_used > 0);
+        // without a fork nothing is fetched
+        assert_eq!(report.rpc_fetches, 0);
+    }
+}
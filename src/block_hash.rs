@@ -0,0 +1,89 @@
+use crate::{prefetch::fetch_parallel, ForkedEvm, ForkerError};
+use foundry_evm::fork::SharedBackend;
+use revm::{
+    primitives::{B256, U256},
+    DatabaseRef,
+};
+
+/// Blocks before the pinned one whose hashes are seeded when a fork is
+/// created, the window `BLOCKHASH` can see.
+const SEEDED_BLOCK_HASHES: u64 = 256;
+
+impl ForkedEvm {
+    /// Answers `blockhash(number)` with `hash` in all calls and writes, even
+    /// outside the 256 block window, e.g. for blocks past the fork's after
+    /// rolling forward, which the fork's RPC can't resolve.
+    pub fn set_block_hash(&mut self, number: u64, hash: B256) {
+        self.bump_state_version();
+        self.block_hashes.insert(U256::from(number), hash);
+    }
+
+    pub fn clear_block_hash(&mut self, number: u64) -> bool {
+        self.bump_state_version();
+        self.block_hashes.remove(&U256::from(number)).is_some()
+    }
+
+    /// Loads the hashes of the `BLOCKHASH` window of the active fork's
+    /// pinned block into its RPC cache and waits for them, e.g. before
+    /// going offline. New forks start the same loading in the background.
+    pub fn seed_block_hashes(&self) -> Result<(), ForkerError> {
+        let info = self.active_fork().ok_or(ForkerError::NoActiveFork)?;
+        let db = self.fork_backend()?;
+        fetch_block_hashes(db, info.pinned_block)
+    }
+}
+
+fn fetch_block_hashes(db: &SharedBackend, pinned_block: u64) -> Result<(), ForkerError> {
+    let numbers: Vec<U256> = (pinned_block.saturating_sub(SEEDED_BLOCK_HASHES)..pinned_block)
+        .map(U256::from)
+        .collect();
+    fetch_parallel(db, &numbers, |db, number| {
+        db.block_hash_ref(*number)?;
+        Ok(())
+    })
+}
+
+/// Seeds the block hashes of a new fork without holding up its creation.
+/// Hashes a read needs before they land are fetched by the read itself.
+#[cfg(feature = "native")]
+pub(crate) fn seed_in_background(db: SharedBackend, pinned_block: u64) {
+    std::thread::spawn(move || {
+        let seeded = fetch_block_hashes(&db, pinned_block);
+        #[cfg(feature = "tracing")]
+        if let Err(e) = &seeded {
+            tracing::warn!(error = %e, pinned_block, "seeding block hashes failed");
+        }
+        seeded
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{testing::evm_with_code, ForkerError};
+    use revm::primitives::{Address, B256};
+
+    #[tokio::test]
+    async fn test_blockhash_returns_seeded_hashes() {
+        let target = Address::repeat_byte(0x22);
+        // PUSH1 5 BLOCKHASH PUSH1 0 MSTORE PUSH1 32 PUSH1 0 RETURN
+        let mut forked_evm = evm_with_code(&[(target, "60054060005260206000f3")]).await;
+        forked_evm.memoize_reads(true);
+        let blockhash = |forked_evm: &crate::ForkedEvm| {
+            let result = forked_evm
+                .call(Address::ZERO.as_slice(), target.as_slice(), &[])
+                .unwrap();
+            B256::from_slice(&result.result)
+        };
+        assert_eq!(blockhash(&forked_evm), B256::ZERO);
+
+        let hash = B256::repeat_byte(0xab);
+        forked_evm.set_block_hash(5, hash);
+        assert_eq!(blockhash(&forked_evm), hash);
+        assert!(forked_evm.clear_block_hash(5));
+        assert_eq!(blockhash(&forked_evm), B256::ZERO);
+        assert!(matches!(
+            forked_evm.seed_block_hashes(),
+            Err(ForkerError::NoActiveFork)
+        ));
+    }
+}
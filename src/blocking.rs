@@ -0,0 +1,86 @@
+use crate::{ForkedEvm, ForkerError};
+use foundry_evm::executors::RawCallResult;
+use revm::primitives::U256;
+use std::time::Duration;
+
+impl ForkedEvm {
+    /// [ForkedEvm::call] on tokio's blocking pool, so heavy reads don't
+    /// stall the runtime. The call runs on a clone sharing this evm's state
+    /// and coverage. A timed out call keeps running in the background until
+    /// it finishes, its result is dropped.
+    pub async fn call_async(
+        &self,
+        from_address: Vec<u8>,
+        to_address: Vec<u8>,
+        calldata: Vec<u8>,
+        timeout: Option<Duration>,
+    ) -> Result<RawCallResult, ForkerError> {
+        let mut forked_evm = self.clone();
+        forked_evm.coverage = self.coverage.clone();
+        run_blocking(timeout, move || {
+            forked_evm.call(&from_address, &to_address, &calldata)
+        })
+        .await
+    }
+
+    /// [ForkedEvm::write] on tokio's blocking pool. The write runs on a
+    /// clone that replaces this evm once it completes, so a timed out write
+    /// leaves the state untouched. The clone copies the local state when it
+    /// writes, see [ForkedEvm::fork_clone].
+    pub async fn write_async(
+        &mut self,
+        from_address: Vec<u8>,
+        to_address: Vec<u8>,
+        calldata: Vec<u8>,
+        value: U256,
+        timeout: Option<Duration>,
+    ) -> Result<RawCallResult, ForkerError> {
+        let mut forked_evm = self.clone();
+        let (forked_evm, result) = run_blocking(timeout, move || {
+            let result = forked_evm.write(&from_address, &to_address, &calldata, value);
+            Ok((forked_evm, result))
+        })
+        .await?;
+        *self = forked_evm;
+        result
+    }
+}
+
+async fn run_blocking<T: Send + 'static>(
+    timeout: Option<Duration>,
+    f: impl FnOnce() -> Result<T, ForkerError> + Send + 'static,
+) -> Result<T, ForkerError> {
+    let task = tokio::task::spawn_blocking(f);
+    let joined = match timeout {
+        Some(timeout) => tokio::time::timeout(timeout, task)
+            .await
+            .map_err(|_| ForkerError::Timeout(timeout))?,
+        None => task.await,
+    };
+    joined?
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::testing::{evm_with_code, returning_code};
+    use revm::primitives::{Address, U256};
+
+    #[tokio::test]
+    async fn test_async_calls_and_writes() {
+        let sender = Address::repeat_byte(0x11);
+        let target = Address::repeat_byte(0x22);
+        let mut forked_evm = evm_with_code(&[(target, &returning_code(&[7; 32]))]).await;
+        let result = forked_evm
+            .call_async(sender.to_vec(), target.to_vec(), vec![], None)
+            .await
+            .unwrap();
+        assert_eq!(result.result.as_ref(), &[7; 32]);
+
+        let nonce = forked_evm.nonce_of(sender).unwrap();
+        forked_evm
+            .write_async(sender.to_vec(), target.to_vec(), vec![], U256::ZERO, None)
+            .await
+            .unwrap();
+        assert_eq!(forked_evm.nonce_of(sender).unwrap(), nonce + 1);
+    }
+}
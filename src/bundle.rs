@@ -0,0 +1,188 @@
+use crate::{ForkedEvm, ForkerError};
+use revm::primitives::{alloy_primitives::I256, Address, B256, U256};
+
+/// Per transaction outcome within a simulated bundle, in the spirit of
+/// `eth_callBundle` results.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BundleTxReport {
+    pub hash: B256,
+    pub reverted: bool,
+    pub gas_used: u64,
+    pub gas_price: U256,
+    /// Gas fees paid by the sender, `gas_used * gas_price`.
+    pub gas_fees: U256,
+    /// Change of the block coinbase's balance caused by this transaction,
+    /// negative when the coinbase itself pays out.
+    pub coinbase_diff: I256,
+    /// Part of `coinbase_diff` not explained by priority fees, i.e. direct
+    /// payments to the coinbase.
+    pub eth_sent_to_coinbase: I256,
+    /// Change of the beneficiary's balance caused by this transaction.
+    pub beneficiary_delta: I256,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BundleReport {
+    pub transactions: Vec<BundleTxReport>,
+    pub total_gas_used: u64,
+    pub total_gas_fees: U256,
+    pub coinbase_diff: I256,
+    pub eth_sent_to_coinbase: I256,
+    /// `coinbase_diff / total_gas_used`, the price the bundle effectively
+    /// pays, zero when the coinbase lost balance.
+    pub bundle_gas_price: U256,
+    pub beneficiary_delta: I256,
+}
+
+impl ForkedEvm {
+    /// Executes signed raw transactions in order on top of the current state
+    /// and reports gas, coinbase payments and balance changes of
+    /// `beneficiary`. The fork is reverted afterwards so nothing is committed.
+    pub fn simulate_bundle(
+        &mut self,
+        raw_transactions: &[Vec<u8>],
+        beneficiary: Address,
+    ) -> Result<BundleReport, ForkerError> {
+        // subscribers only see committed events, and the revert drops the
+        // bundle's local logs
+        #[cfg(feature = "native")]
+        let event_sender = self.event_sender.take();
+        let snapshot_id = self.snapshot();
+        let report = self.run_bundle(raw_transactions, beneficiary);
+        self.revert(snapshot_id);
+        #[cfg(feature = "native")]
+        {
+            self.event_sender = event_sender;
+        }
+        report
+    }
+
+    fn run_bundle(
+        &mut self,
+        raw_transactions: &[Vec<u8>],
+        beneficiary: Address,
+    ) -> Result<BundleReport, ForkerError> {
+        let coinbase = self.executor.env.block.coinbase;
+        let basefee = self.executor.env.block.basefee;
+        let mut transactions = Vec::with_capacity(raw_transactions.len());
+        for raw in raw_transactions {
+            let coinbase_before = self.balance_of(coinbase)?;
+            let beneficiary_before = self.balance_of(beneficiary)?;
+            let (hash, result) = self.send_raw_transaction(raw)?;
+            let coinbase_diff = balance_delta(coinbase_before, self.balance_of(coinbase)?);
+            let beneficiary_delta =
+                balance_delta(beneficiary_before, self.balance_of(beneficiary)?);
+
+            let gas_price = result.env.tx.gas_price;
+            let priority_fee = match result.env.tx.gas_priority_fee {
+                Some(priority_fee) => priority_fee.min(gas_price.saturating_sub(basefee)),
+                None => gas_price.saturating_sub(basefee),
+            };
+            let gas_used = U256::from(result.gas_used);
+            transactions.push(BundleTxReport {
+                hash,
+                reverted: result.reverted,
+                gas_used: result.gas_used,
+                gas_price,
+                gas_fees: gas_used * gas_price,
+                coinbase_diff,
+                eth_sent_to_coinbase: coinbase_diff
+                    .wrapping_sub(I256::from_raw(gas_used * priority_fee)),
+                beneficiary_delta,
+            });
+        }
+
+        let total_gas_used: u64 = transactions.iter().map(|tx| tx.gas_used).sum();
+        let sum = |delta: fn(&BundleTxReport) -> I256| {
+            transactions
+                .iter()
+                .fold(I256::ZERO, |total, tx| total.wrapping_add(delta(tx)))
+        };
+        let coinbase_diff = sum(|tx| tx.coinbase_diff);
+        let eth_sent_to_coinbase = sum(|tx| tx.eth_sent_to_coinbase);
+        let beneficiary_delta = sum(|tx| tx.beneficiary_delta);
+        let bundle_gas_price = if coinbase_diff.is_negative() {
+            U256::ZERO
+        } else {
+            coinbase_diff
+                .into_raw()
+                .checked_div(U256::from(total_gas_used))
+                .unwrap_or_default()
+        };
+        Ok(BundleReport {
+            total_gas_used,
+            total_gas_fees: transactions.iter().map(|tx| tx.gas_fees).sum(),
+            coinbase_diff,
+            eth_sent_to_coinbase,
+            bundle_gas_price,
+            beneficiary_delta,
+            transactions,
+        })
+    }
+}
+
+/// Signed change from `before` to `after`.
+fn balance_delta(before: U256, after: U256) -> I256 {
+    I256::from_raw(after).wrapping_sub(I256::from_raw(before))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::evm_with_code;
+    use ethers::{
+        signers::{LocalWallet, Signer},
+        types::{transaction::eip2718::TypedTransaction, TransactionRequest, H160},
+    };
+    use revm::primitives::Bytes;
+
+    // the first default anvil account
+    const PRIVATE_KEY: &str = "ac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80";
+
+    fn signed_transfer(wallet: &LocalWallet, nonce: u64, to: Address, value: u64) -> Vec<u8> {
+        let tx: TypedTransaction = TransactionRequest::new()
+            .to(H160::from(to.0 .0))
+            .value(value)
+            .gas(21_000)
+            .gas_price(0)
+            .nonce(nonce)
+            .chain_id(1)
+            .into();
+        let signature = wallet.sign_transaction_sync(&tx).unwrap();
+        tx.rlp_signed(&signature).to_vec()
+    }
+
+    #[tokio::test]
+    async fn test_bundle_reports_signed_coinbase_diffs() {
+        let wallet = PRIVATE_KEY.parse::<LocalWallet>().unwrap();
+        let sender = Address::from(wallet.address().0);
+        let beneficiary = Address::repeat_byte(0x22);
+        let mut forked_evm = evm_with_code(&[]).await;
+        forked_evm
+            .insert_account(sender, U256::from(100), 0, Bytes::new(), [])
+            .unwrap();
+
+        // a direct payment to the coinbase
+        let coinbase = Address::repeat_byte(0x33);
+        forked_evm.executor.env.block.coinbase = coinbase;
+        let report = forked_evm
+            .simulate_bundle(&[signed_transfer(&wallet, 0, coinbase, 5)], beneficiary)
+            .unwrap();
+        assert_eq!(report.coinbase_diff, I256::try_from(5i64).unwrap());
+        assert_eq!(report.eth_sent_to_coinbase, I256::try_from(5i64).unwrap());
+        assert_eq!(report.total_gas_used, 21_000);
+
+        // the coinbase itself paying the beneficiary
+        forked_evm.executor.env.block.coinbase = sender;
+        let report = forked_evm
+            .simulate_bundle(&[signed_transfer(&wallet, 0, beneficiary, 7)], beneficiary)
+            .unwrap();
+        assert_eq!(report.coinbase_diff, I256::try_from(-7i64).unwrap());
+        assert_eq!(report.beneficiary_delta, I256::try_from(7i64).unwrap());
+        assert_eq!(report.bundle_gas_price, U256::ZERO);
+
+        // nothing was committed
+        assert_eq!(forked_evm.balance_of(sender).unwrap(), U256::from(100));
+        assert_eq!(forked_evm.nonce_of(sender).unwrap(), 0);
+    }
+}
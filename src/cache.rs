@@ -0,0 +1,262 @@
+use crate::{ForkedEvm, ForkerError};
+use foundry_evm::backend::Backend;
+use revm::primitives::{AccountInfo, Address, Env, U256};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// Where forks pinned to a block keep the state they fetch between runs.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CacheConfig {
+    /// Whether pinned forks read and write an on-disk cache at all.
+    pub enabled: bool,
+    /// Directory to keep the cache in instead of foundry's default
+    /// `~/.foundry/cache/rpc`, laid out as `<chain id>/<block>/storage.json`.
+    /// Unlike the default cache it is only written by
+    /// [ForkedEvm::flush_cache].
+    pub dir: Option<PathBuf>,
+}
+
+impl Default for CacheConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            dir: None,
+        }
+    }
+}
+
+impl CacheConfig {
+    /// Whether foundry's own cache, in its default location, is used.
+    pub(crate) fn default_location(&self) -> bool {
+        self.enabled && self.dir.is_none()
+    }
+
+    fn cache_file(&self, chain_id: u64, block: u64) -> Option<PathBuf> {
+        let dir = self.dir.as_ref().filter(|_| self.enabled)?;
+        Some(
+            dir.join(chain_id.to_string())
+                .join(block.to_string())
+                .join("storage.json"),
+        )
+    }
+}
+
+/// Portable snapshot of everything the active fork fetched from its RPC,
+/// together with the env the fork was pinned to.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ForkCacheDump {
+    pub env: Env,
+    pub accounts: BTreeMap<Address, AccountInfo>,
+    pub storage: BTreeMap<Address, BTreeMap<U256, U256>>,
+}
+
+impl ForkedEvm {
+    /// Collects the state fetched so far by the active fork's backend.
+    pub fn dump_cache(&self) -> Result<ForkCacheDump, ForkerError> {
+        let Some(fork_db) = self.executor.backend.active_fork_db() else {
+            return Err(ForkerError::NoActiveFork);
+        };
+        Ok(ForkCacheDump {
+            env: self.executor.env.clone(),
+            accounts: fork_db.db.accounts().into_iter().collect(),
+            storage: fork_db
+                .db
+                .storage()
+                .into_iter()
+                .map(|(address, slots)| (address, slots.into_iter().collect()))
+                .collect(),
+        })
+    }
+
+    /// Writes the active fork's fetched state to `path` as json.
+    pub fn export_cache(&self, path: impl AsRef<Path>) -> Result<(), ForkerError> {
+        fs::write(path, serde_json::to_vec(&self.dump_cache()?)?)?;
+        Ok(())
+    }
+
+    pub fn cache_config(&self) -> &CacheConfig {
+        &self.rpc_config.cache
+    }
+
+    /// Sets the on-disk cache of forks created from here on, part of
+    /// [ForkedEvm::rpc_config].
+    pub fn set_cache_config(&mut self, cache: CacheConfig) {
+        self.rpc_config.cache = cache;
+    }
+
+    /// Writes the active fork's fetched state to the relocated cache, see
+    /// [CacheConfig::dir]. Does nothing for forks following the chain tip
+    /// or without a cache dir.
+    pub fn flush_cache(&self) -> Result<(), ForkerError> {
+        let Some(path) = self.active_cache_file() else {
+            return Ok(());
+        };
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        self.export_cache(path)
+    }
+
+    /// Seeds the active fork's RPC cache from the relocated cache, if it
+    /// has an entry for the fork.
+    pub(crate) fn load_relocated_cache(&self) -> Result<(), ForkerError> {
+        let Some(path) = self.active_cache_file().filter(|path| path.exists()) else {
+            return Ok(());
+        };
+        let dump: ForkCacheDump = serde_json::from_slice(&fs::read(path)?)?;
+        let data = self.fork_backend()?.data();
+        data.accounts.write().extend(dump.accounts);
+        let mut storage = data.storage.write();
+        for (address, slots) in dump.storage {
+            storage.entry(address).or_default().extend(slots);
+        }
+        Ok(())
+    }
+
+    fn active_cache_file(&self) -> Option<PathBuf> {
+        let info = self.active_fork()?;
+        info.block_number?;
+        info.rpc_config
+            .cache
+            .cache_file(info.env.cfg.chain_id, info.pinned_block)
+    }
+
+    /// Deletes the cached state of `chain_id` at `block`, or of all its
+    /// blocks, from the configured cache dir and foundry's default one.
+    pub fn purge_cache(&self, chain_id: u64, block: Option<u64>) -> Result<(), ForkerError> {
+        let mut chain_dirs = vec![];
+        if let Some(dir) = &self.rpc_config.cache.dir {
+            chain_dirs.push(dir.join(chain_id.to_string()));
+        }
+        if let Some(home) = std::env::var_os("HOME") {
+            let rpc_cache = PathBuf::from(home).join(".foundry/cache/rpc");
+            // foundry names the directories of known chains by name
+            if let Ok(chain) = ethers::types::Chain::try_from(chain_id) {
+                chain_dirs.push(rpc_cache.join(chain.to_string()));
+            }
+            chain_dirs.push(rpc_cache.join(chain_id.to_string()));
+        }
+        for chain_dir in chain_dirs {
+            let dir = match block {
+                Some(block) => chain_dir.join(block.to_string()),
+                None => chain_dir,
+            };
+            if dir.exists() {
+                fs::remove_dir_all(dir)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Builds an evm purely from a cache dump, without any fork url. State
+    /// missing from the dump reads as empty instead of being fetched.
+    pub async fn from_cache_dump(
+        dump: ForkCacheDump,
+        gas_limit: Option<u64>,
+    ) -> Result<ForkedEvm, ForkerError> {
+        let mut backend = Backend::spawn(None).await;
+        for (address, info) in dump.accounts {
+            backend.insert_account_info(address, info);
+        }
+        for (address, slots) in dump.storage {
+            for (slot, value) in slots {
+                backend.insert_account_storage(address, slot, value)?;
+            }
+        }
+        Ok(Self::new_with_shared_backend(backend, dump.env, gas_limit))
+    }
+
+    /// Reads a dump written by [ForkedEvm::export_cache] and builds an evm from it.
+    pub async fn import_cache(
+        path: impl AsRef<Path>,
+        gas_limit: Option<u64>,
+    ) -> Result<ForkedEvm, ForkerError> {
+        let dump: ForkCacheDump = serde_json::from_slice(&fs::read(path)?)?;
+        Self::from_cache_dump(dump, gas_limit).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::evm_with_code;
+    use revm::DatabaseRef;
+
+    #[tokio::test]
+    async fn test_dump_cache_requires_fork() {
+        let forked_evm = evm_with_code(&[]).await;
+        assert!(forked_evm.dump_cache().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_import_round_trips_dump() {
+        let account = Address::repeat_byte(0x11);
+        let dump = ForkCacheDump {
+            env: Env::default(),
+            accounts: BTreeMap::from([(
+                account,
+                AccountInfo {
+                    balance: U256::from(5),
+                    nonce: 2,
+                    ..Default::default()
+                },
+            )]),
+            storage: BTreeMap::from([(account, BTreeMap::from([(U256::from(1), U256::from(9))]))]),
+        };
+        let path = std::env::temp_dir().join(format!("forker-dump-{}.json", std::process::id()));
+        fs::write(&path, serde_json::to_vec(&dump).unwrap()).unwrap();
+
+        let forked_evm = ForkedEvm::import_cache(&path, None).await.unwrap();
+        fs::remove_file(&path).unwrap();
+
+        let backend = &forked_evm.executor.backend;
+        let info = backend.basic_ref(account).unwrap().unwrap();
+        assert_eq!(info.balance, U256::from(5));
+        assert_eq!(info.nonce, 2);
+        assert_eq!(
+            backend.storage_ref(account, U256::from(1)).unwrap(),
+            U256::from(9)
+        );
+        assert_eq!(
+            backend.storage_ref(account, U256::from(2)).unwrap(),
+            U256::ZERO
+        );
+    }
+
+    #[tokio::test]
+    async fn test_import_rejects_malformed_dump() {
+        let path = std::env::temp_dir().join(format!("forker-bad-{}.json", std::process::id()));
+        fs::write(&path, b"{").unwrap();
+        let result = ForkedEvm::import_cache(&path, None).await;
+        fs::remove_file(&path).unwrap();
+        assert!(result.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_purge_cache_removes_relocated_entries() {
+        let dir = std::env::temp_dir().join(format!("forker-cache-{}", std::process::id()));
+        let mut forked_evm = evm_with_code(&[]).await;
+        forked_evm.set_cache_config(CacheConfig {
+            enabled: true,
+            dir: Some(dir.clone()),
+        });
+        // a chain id no provider uses, so the default cache is left alone
+        let chain_id = 999_999_999;
+        for block in [5, 6] {
+            let block_dir = dir.join(chain_id.to_string()).join(block.to_string());
+            fs::create_dir_all(&block_dir).unwrap();
+            fs::write(block_dir.join("storage.json"), b"{}").unwrap();
+        }
+
+        forked_evm.purge_cache(chain_id, Some(5)).unwrap();
+        assert!(!dir.join("999999999/5").exists());
+        assert!(dir.join("999999999/6").exists());
+        forked_evm.purge_cache(chain_id, None).unwrap();
+        assert!(!dir.join("999999999").exists());
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
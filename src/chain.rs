@@ -0,0 +1,192 @@
+use crate::{ForkedEvm, ForkerError};
+use alloy_providers::provider::TempProvider;
+use alloy_rpc_types::{Block, BlockId, BlockNumberOrTag, BlockTransactions, Transaction};
+use foundry_common::provider::alloy::ProviderBuilder;
+use foundry_evm::{
+    backend::DatabaseExt,
+    executors::RawCallResult,
+    fork::{ForkId, SharedBackend},
+};
+use revm::{
+    primitives::{CreateScheme, Env, SpecId, TransactTo, B256, U256},
+    JournaledState,
+};
+
+/// Fork created by [ForkedEvm::add_pending_fork].
+#[derive(Debug)]
+pub struct PendingFork {
+    /// Id of the branch, which is never reused by other forks.
+    pub fork_id: ForkId,
+    /// Pending transactions committed on top of the parent block.
+    pub applied: Vec<B256>,
+    /// Pending transactions the executor failed to apply, with the reason.
+    pub skipped: Vec<(B256, ForkerError)>,
+}
+
+impl ForkedEvm {
+    /// Fetches a full block of the active fork's chain through the fork's RPC.
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all))]
+    pub fn get_block(&self, block: impl Into<BlockId>) -> Result<Block, ForkerError> {
+        Ok(self.fork_backend()?.get_full_block(block)?)
+    }
+
+    /// Fetches a transaction of the active fork's chain through the fork's RPC.
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip(self)))]
+    pub fn get_transaction(&self, hash: B256) -> Result<Transaction, ForkerError> {
+        Ok(self.fork_backend()?.get_transaction(hash)?)
+    }
+
+    /// RPC backed database of the active fork.
+    pub(crate) fn fork_backend(&self) -> Result<&SharedBackend, ForkerError> {
+        self.executor
+            .backend
+            .active_fork_db()
+            .map(|fork_db| &fork_db.db)
+            .ok_or(ForkerError::NoActiveFork)
+    }
+}
+
+impl ForkedEvm {
+    /// Builds a tx env reproducing an rpc transaction on top of the current
+    /// block env. The nonce is left unchecked so transactions queued behind
+    /// other pending ones can still be simulated.
+    pub(crate) fn env_from_rpc_tx(&self, tx: &Transaction) -> Env {
+        let mut env = self.executor.env.clone();
+        env.tx.caller = tx.from;
+        env.tx.transact_to = match tx.to {
+            Some(to) => TransactTo::Call(to),
+            None => TransactTo::Create(CreateScheme::Create),
+        };
+        env.tx.data = tx.input.clone();
+        env.tx.value = tx.value;
+        env.tx.gas_limit = tx.gas.saturating_to();
+        env.tx.nonce = None;
+        env.tx.chain_id = tx.chain_id.map(|chain_id| chain_id.to());
+        if let Some(gas_price) = tx.max_fee_per_gas.or(tx.gas_price) {
+            env.tx.gas_price = U256::from(gas_price);
+        }
+        env.tx.gas_priority_fee = tx.max_priority_fee_per_gas.map(U256::from);
+        env
+    }
+
+    /// Fetches a mempool transaction through the fork's RPC and runs it on
+    /// top of the current fork state without committing it.
+    pub fn simulate_pending_tx(&self, hash: B256) -> Result<RawCallResult, ForkerError> {
+        let tx = self.get_transaction(hash)?;
+        if tx.block_number.is_some() {
+            return Err(ForkerError::TransactionMined(hash));
+        }
+        self.call_with_env(self.env_from_rpc_tx(&tx))
+    }
+
+    /// Forks `fork_url` at the `pending` tag: creates a new fork of the
+    /// pending block's parent, takes the pending block's header as the block
+    /// env and commits the transactions the node has put in it, so
+    /// simulations run against mempool state. Pending transactions that fail
+    /// to apply are skipped, like a builder would, and reported in the
+    /// result. The new fork is selected.
+    pub async fn add_pending_fork(&mut self, fork_url: &str) -> Result<PendingFork, ForkerError> {
+        #[cfg(feature = "native")]
+        let fork_url = &self.serving_url(fork_url).await?;
+        let provider = ProviderBuilder::new(fork_url)
+            .build()
+            .map_err(|e| ForkerError::rpc(fork_url, e))?;
+        let pending = provider
+            .get_block_by_number(BlockNumberOrTag::Pending, true)
+            .await
+            .map_err(|e| ForkerError::rpc(fork_url, e))?
+            .ok_or_else(|| ForkerError::rpc(fork_url, "node returned no pending block"))?;
+        let number: u64 = pending
+            .header
+            .number
+            .ok_or_else(|| ForkerError::rpc(fork_url, "pending block has no number"))?
+            .saturating_to();
+        let parent = number
+            .checked_sub(1)
+            .ok_or(ForkerError::NoParentBlock(number))?;
+
+        // always a new fork, so the pending transactions never land in a
+        // fork that add_or_select hands out for the parent block
+        let fork_opts = Self::create_fork_opts(fork_url, Some(parent), &self.rpc_config).await?;
+        self.executor.env = fork_opts.env.clone();
+        let mut journaled_state = JournaledState::new(SpecId::LATEST, vec![]);
+        let executor = &mut *self.executor;
+        let local_id = executor
+            .backend
+            .create_select_fork(fork_opts, &mut executor.env, &mut journaled_state)
+            .map_err(|e| ForkerError::rpc(fork_url, e))?;
+        let fork_id = ForkId(format!(
+            "{}@pending-{local_id}",
+            ForkId::new(fork_url, Some(parent)).0
+        ));
+        self.track_fork(fork_id.clone(), fork_url, Some(parent), local_id);
+
+        self.apply_block_header(&pending.header);
+
+        let mut applied = vec![];
+        let mut skipped = vec![];
+        if let BlockTransactions::Full(transactions) = &pending.transactions {
+            for tx in transactions {
+                match self.commit_tx(self.env_from_rpc_tx(tx), Some(tx.hash)) {
+                    Ok(_) => applied.push(tx.hash),
+                    Err(e) => skipped.push((tx.hash, e)),
+                }
+            }
+        }
+        if let Some(info) = self.forks.get_mut(&fork_id) {
+            info.env = self.executor.env.clone();
+        }
+        self.enforce_memory_budget()?;
+        Ok(PendingFork {
+            fork_id,
+            applied,
+            skipped,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{testing::evm_with_code, ForkerError};
+    use alloy_rpc_types::Transaction;
+    use revm::primitives::{Address, Bytes, TransactTo, B256, U256};
+
+    #[tokio::test]
+    async fn test_chain_queries_require_fork() {
+        let forked_evm = evm_with_code(&[]).await;
+        assert!(matches!(
+            forked_evm.get_block(1u64),
+            Err(ForkerError::NoActiveFork)
+        ));
+        assert!(matches!(
+            forked_evm.get_transaction(B256::ZERO),
+            Err(ForkerError::NoActiveFork)
+        ));
+        assert!(matches!(
+            forked_evm.simulate_pending_tx(B256::ZERO),
+            Err(ForkerError::NoActiveFork)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_env_from_rpc_tx_skips_the_nonce() {
+        let forked_evm = evm_with_code(&[]).await;
+        let to = Address::repeat_byte(0x11);
+        let tx = Transaction {
+            from: Address::repeat_byte(0x22),
+            to: Some(to),
+            input: Bytes::from_static(&[0x12, 0x34]),
+            value: U256::from(7),
+            gas: U256::from(50_000),
+            ..Default::default()
+        };
+
+        let env = forked_evm.env_from_rpc_tx(&tx);
+        assert_eq!(env.tx.caller, tx.from);
+        assert!(matches!(env.tx.transact_to, TransactTo::Call(address) if address == to));
+        assert_eq!(env.tx.data, tx.input);
+        assert_eq!(env.tx.value, U256::from(7));
+        assert_eq!(env.tx.gas_limit, 50_000);
+        assert_eq!(env.tx.nonce, None);
+    }
+}
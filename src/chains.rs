@@ -0,0 +1,192 @@
+use crate::{ForkedEvm, ForkerError};
+
+/// Connection details for a chain that can be forked by name or id.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChainInfo {
+    pub name: String,
+    pub chain_id: u64,
+    pub rpc_url: String,
+    pub explorer_url: String,
+}
+
+impl ChainInfo {
+    pub fn new(name: &str, chain_id: u64, rpc_url: &str, explorer_url: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            chain_id,
+            rpc_url: rpc_url.to_string(),
+            explorer_url: explorer_url.to_string(),
+        }
+    }
+
+    /// Env var that overrides this chain's rpc url, e.g.
+    /// `FORKER_RPC_URL_POLYGON`.
+    pub fn rpc_env_var(&self) -> String {
+        format!(
+            "FORKER_RPC_URL_{}",
+            self.name.to_uppercase().replace('-', "_")
+        )
+    }
+
+    /// The rpc url to fork from, preferring the env var override.
+    pub fn resolved_rpc_url(&self) -> String {
+        std::env::var(self.rpc_env_var()).unwrap_or_else(|_| self.rpc_url.clone())
+    }
+}
+
+/// Known chains, pre-populated with public endpoints and extendable by users.
+#[derive(Debug, Clone)]
+pub struct ChainRegistry {
+    chains: Vec<ChainInfo>,
+}
+
+impl Default for ChainRegistry {
+    fn default() -> Self {
+        Self {
+            chains: vec![
+                ChainInfo::new(
+                    "ethereum",
+                    1,
+                    "https://eth.llamarpc.com",
+                    "https://etherscan.io",
+                ),
+                ChainInfo::new(
+                    "sepolia",
+                    11155111,
+                    "https://rpc.sepolia.org",
+                    "https://sepolia.etherscan.io",
+                ),
+                ChainInfo::new(
+                    "polygon",
+                    137,
+                    "https://polygon-rpc.com",
+                    "https://polygonscan.com",
+                ),
+                ChainInfo::new(
+                    "bsc",
+                    56,
+                    "https://bsc-dataseed.bnbchain.org",
+                    "https://bscscan.com",
+                ),
+                ChainInfo::new(
+                    "arbitrum",
+                    42161,
+                    "https://arb1.arbitrum.io/rpc",
+                    "https://arbiscan.io",
+                ),
+                ChainInfo::new(
+                    "optimism",
+                    10,
+                    "https://mainnet.optimism.io",
+                    "https://optimistic.etherscan.io",
+                ),
+                ChainInfo::new(
+                    "base",
+                    8453,
+                    "https://mainnet.base.org",
+                    "https://basescan.org",
+                ),
+                ChainInfo::new(
+                    "avalanche",
+                    43114,
+                    "https://api.avax.network/ext/bc/C/rpc",
+                    "https://snowtrace.io",
+                ),
+                ChainInfo::new(
+                    "gnosis",
+                    100,
+                    "https://rpc.gnosischain.com",
+                    "https://gnosisscan.io",
+                ),
+                ChainInfo::new(
+                    "linea",
+                    59144,
+                    "https://rpc.linea.build",
+                    "https://lineascan.build",
+                ),
+                ChainInfo::new(
+                    "flare",
+                    14,
+                    "https://flare-api.flare.network/ext/C/rpc",
+                    "https://flare-explorer.flare.network",
+                ),
+            ],
+        }
+    }
+}
+
+impl ChainRegistry {
+    /// Adds a chain, replacing any known chain with the same name or id.
+    pub fn register(&mut self, chain: ChainInfo) {
+        self.chains
+            .retain(|known| known.name != chain.name && known.chain_id != chain.chain_id);
+        self.chains.push(chain);
+    }
+
+    /// Looks a chain up by case insensitive name or by decimal chain id.
+    pub fn get(&self, name_or_id: &str) -> Option<&ChainInfo> {
+        match name_or_id.parse::<u64>() {
+            Ok(chain_id) => self.by_id(chain_id),
+            Err(_) => self
+                .chains
+                .iter()
+                .find(|chain| chain.name.eq_ignore_ascii_case(name_or_id)),
+        }
+    }
+
+    pub fn by_id(&self, chain_id: u64) -> Option<&ChainInfo> {
+        self.chains.iter().find(|chain| chain.chain_id == chain_id)
+    }
+
+    pub fn chains(&self) -> &[ChainInfo] {
+        &self.chains
+    }
+}
+
+impl ForkedEvm {
+    /// Forks a chain of the default [ChainRegistry] by name or id.
+    pub async fn for_chain(
+        name_or_id: &str,
+        fork_block_number: Option<u64>,
+    ) -> Result<ForkedEvm, ForkerError> {
+        Self::for_chain_with_registry(&ChainRegistry::default(), name_or_id, fork_block_number)
+            .await
+    }
+
+    pub async fn for_chain_with_registry(
+        registry: &ChainRegistry,
+        name_or_id: &str,
+        fork_block_number: Option<u64>,
+    ) -> Result<ForkedEvm, ForkerError> {
+        let chain = registry
+            .get(name_or_id)
+            .ok_or_else(|| ForkerError::UnknownChain(name_or_id.to_string()))?;
+        Self::new(&chain.resolved_rpc_url(), fork_block_number, None, None).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_registry_lookup() {
+        let mut registry = ChainRegistry::default();
+        assert_eq!(registry.get("Polygon").unwrap().chain_id, 137);
+        assert_eq!(registry.get("8453").unwrap().name, "base");
+        assert!(registry.get("unknown").is_none());
+
+        registry.register(ChainInfo::new("my-chain", 137, "http://localhost:8545", ""));
+        assert!(registry.get("polygon").is_none());
+        let chain = registry.get("137").unwrap();
+        assert_eq!(chain.name, "my-chain");
+        assert_eq!(chain.rpc_env_var(), "FORKER_RPC_URL_MY_CHAIN");
+    }
+
+    #[tokio::test]
+    async fn test_unknown_chains_are_rejected() {
+        let result =
+            ForkedEvm::for_chain_with_registry(&ChainRegistry::default(), "unknown", None).await;
+        assert!(matches!(result, Err(ForkerError::UnknownChain(name)) if name == "unknown"));
+    }
+}
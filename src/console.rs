@@ -0,0 +1,60 @@
+use foundry_evm::{decode::decode_console_logs, executors::RawCallResult};
+
+/// `console.log` and DSTest `log_*` output of a call or write, decoded to
+/// strings in emission order. Also available as
+/// [crate::ForkCallResult::console_logs].
+pub fn console_logs(result: &RawCallResult) -> Vec<String> {
+    decode_console_logs(&result.logs)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{testing::evm_with_code, CallOptions};
+    use alloy_sol_types::{sol, SolCall};
+    use revm::primitives::{hex, Address, U256};
+
+    sol! {
+        function log(string message);
+    }
+
+    /// Runtime code calling the hardhat console with `payload`.
+    fn console_calling_code(payload: &[u8]) -> String {
+        let len = u8::try_from(payload.len()).unwrap();
+        // CODECOPY the payload appended to the code, then CALL the console
+        let mut code = vec![0x60, len, 0x60, 41, 0x60, 0x00, 0x39];
+        code.extend([
+            0x60, 0x00, 0x60, 0x00, 0x60, len, 0x60, 0x00, 0x60, 0x00, 0x73,
+        ]);
+        code.extend(hex::decode("000000000000000000636F6e736F6c652e6c6f67").unwrap());
+        code.extend([0x5a, 0xf1, 0x00]);
+        code.extend_from_slice(payload);
+        hex::encode(code)
+    }
+
+    #[tokio::test]
+    async fn test_console_logs_are_on_the_result() {
+        let logger = Address::repeat_byte(0x11);
+        let payload = logCall {
+            message: "hello fork".to_string(),
+        }
+        .abi_encode();
+        let mut forked_evm = evm_with_code(&[(logger, &console_calling_code(&payload))]).await;
+
+        let result = forked_evm
+            .call_detailed(&[0u8; 20], logger.as_slice(), &[], &CallOptions::default())
+            .unwrap();
+        assert!(!result.reverted);
+        assert_eq!(result.console_logs, vec!["hello fork".to_string()]);
+
+        let result = forked_evm
+            .write_detailed(
+                &[0u8; 20],
+                logger.as_slice(),
+                &[],
+                U256::ZERO,
+                &CallOptions::default(),
+            )
+            .unwrap();
+        assert_eq!(result.console_logs, vec!["hello fork".to_string()]);
+    }
+}
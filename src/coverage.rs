@@ -0,0 +1,196 @@
+use crate::{ForkedEvm, ForkerError};
+#[cfg(feature = "native")]
+use foundry_compilers::sourcemap;
+use foundry_evm::coverage::{HitMap, HitMaps};
+use revm::primitives::Address;
+#[cfg(feature = "native")]
+use revm::{primitives::Bytes, DatabaseRef};
+use std::collections::BTreeMap;
+
+/// Compiler output needed to map a deployed contract's pcs back to source.
+#[derive(Debug, Clone)]
+pub struct CoverageArtifact {
+    /// Address the contract is deployed at on the fork.
+    pub address: Address,
+    /// Deployed (runtime) source map as emitted by solc.
+    pub source_map: String,
+    /// Source files keyed by their solc source index, as `(path, content)`.
+    pub sources: BTreeMap<u32, (String, String)>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct LineHits {
+    pub path: String,
+    pub line: usize,
+    pub hits: u64,
+}
+
+impl ForkedEvm {
+    /// Starts collecting executed pcs for every subsequent `call`.
+    pub fn enable_coverage(&mut self) {
+        self.executor.inspector.collect_coverage(true);
+        self.coverage
+            .lock()
+            .unwrap()
+            .get_or_insert_with(HitMaps::default);
+    }
+
+    pub fn disable_coverage(&mut self) {
+        self.executor.inspector.collect_coverage(false);
+        *self.coverage.lock().unwrap() = None;
+    }
+
+    /// Raw pc hit counts accumulated so far, keyed by code hash.
+    pub fn coverage_hits(&self) -> Option<HitMaps> {
+        self.coverage.lock().unwrap().clone()
+    }
+
+    pub(crate) fn record_coverage(&self, hit_maps: Option<HitMaps>) {
+        let mut coverage = self.coverage.lock().unwrap();
+        let (Some(coverage), Some(hit_maps)) = (coverage.as_mut(), hit_maps) else {
+            return;
+        };
+        for (code_hash, hit_map) in hit_maps.0 {
+            let entry = coverage.0.entry(code_hash).or_insert_with(|| HitMap {
+                bytecode: hit_map.bytecode.clone(),
+                hits: BTreeMap::new(),
+            });
+            for (pc, hits) in hit_map.hits {
+                *entry.hits.entry(pc).or_default() += hits;
+            }
+        }
+    }
+
+    /// Maps accumulated hits for the given artifacts back to source lines.
+    #[cfg(feature = "native")]
+    pub fn coverage_report(
+        &self,
+        artifacts: &[CoverageArtifact],
+    ) -> Result<Vec<LineHits>, ForkerError> {
+        let coverage = self.coverage.lock().unwrap();
+        let Some(coverage) = coverage.as_ref() else {
+            return Err(ForkerError::CoverageDisabled);
+        };
+        let mut lines: BTreeMap<(String, usize), u64> = BTreeMap::new();
+        for artifact in artifacts {
+            let source_map = sourcemap::parse(&artifact.source_map)
+                .map_err(|e| ForkerError::InvalidSourceMap(e.to_string()))?;
+            let Some(account) = self.executor.backend.basic_ref(artifact.address)? else {
+                continue;
+            };
+            let Some(hit_map) = coverage.0.get(&account.code_hash) else {
+                continue;
+            };
+            let instructions = pc_to_instruction(&hit_map.bytecode);
+            for (pc, hits) in &hit_map.hits {
+                let Some(element) = instructions.get(pc).and_then(|ic| source_map.get(*ic)) else {
+                    continue;
+                };
+                let Some((path, content)) = element.index.and_then(|i| artifact.sources.get(&i))
+                else {
+                    continue;
+                };
+                let line = line_of(content, element.offset);
+                *lines.entry((path.clone(), line)).or_default() += hits;
+            }
+        }
+        Ok(lines
+            .into_iter()
+            .map(|((path, line), hits)| LineHits { path, line, hits })
+            .collect())
+    }
+}
+
+/// Maps each opcode's pc to its instruction index, which is what source maps
+/// are indexed by.
+#[cfg(feature = "native")]
+fn pc_to_instruction(bytecode: &Bytes) -> BTreeMap<usize, usize> {
+    let mut map = BTreeMap::new();
+    let mut pc = 0;
+    let mut ic = 0;
+    while pc < bytecode.len() {
+        map.insert(pc, ic);
+        let op = bytecode[pc];
+        pc += 1;
+        if (0x60..=0x7f).contains(&op) {
+            pc += (op - 0x5f) as usize;
+        }
+        ic += 1;
+    }
+    map
+}
+
+#[cfg(feature = "native")]
+fn line_of(content: &str, offset: usize) -> usize {
+    let end = offset.min(content.len());
+    content.as_bytes()[..end]
+        .iter()
+        .filter(|b| **b == b'\n')
+        .count()
+        + 1
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::evm_with_code;
+
+    // returns 1 + 2 as a single word
+    const ADD_CODE: &str = "600160020160005260206000f3";
+
+    #[tokio::test]
+    async fn test_coverage_accumulates_across_calls() {
+        let target = Address::repeat_byte(0x11);
+        let mut forked_evm = evm_with_code(&[(target, ADD_CODE)]).await;
+        forked_evm.enable_coverage();
+
+        forked_evm.call(&[0u8; 20], target.as_slice(), &[]).unwrap();
+        forked_evm.call(&[0u8; 20], target.as_slice(), &[]).unwrap();
+
+        let hit_maps = forked_evm.coverage_hits().unwrap();
+        assert_eq!(hit_maps.0.len(), 1);
+        let hit_map = hit_maps.0.values().next().unwrap();
+        assert_eq!(hit_map.hits.len(), 8);
+        assert!(hit_map.hits.values().all(|hits| *hits == 2));
+    }
+
+    #[cfg(feature = "native")]
+    #[tokio::test]
+    async fn test_coverage_report_maps_pcs_to_lines() {
+        let target = Address::repeat_byte(0x11);
+        let mut forked_evm = evm_with_code(&[(target, ADD_CODE)]).await;
+        forked_evm.enable_coverage();
+        forked_evm.call(&[0u8; 20], target.as_slice(), &[]).unwrap();
+
+        // first three instructions on line 1, the rest on line 2
+        let artifact = CoverageArtifact {
+            address: target,
+            source_map: "0:1:0;;;2:1:0;;;;".to_string(),
+            sources: BTreeMap::from([(0, ("Add.sol".to_string(), "a\nb\n".to_string()))]),
+        };
+        let report = forked_evm.coverage_report(&[artifact]).unwrap();
+        assert_eq!(
+            report,
+            vec![
+                LineHits {
+                    path: "Add.sol".to_string(),
+                    line: 1,
+                    hits: 3
+                },
+                LineHits {
+                    path: "Add.sol".to_string(),
+                    line: 2,
+                    hits: 5
+                },
+            ]
+        );
+    }
+
+    #[cfg(feature = "native")]
+    #[tokio::test]
+    async fn test_coverage_report_requires_enable() {
+        let forked_evm = evm_with_code(&[]).await;
+        assert!(forked_evm.coverage_report(&[]).is_err());
+        assert!(forked_evm.coverage_hits().is_none());
+    }
+}
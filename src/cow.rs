@@ -0,0 +1,38 @@
+use foundry_evm::executors::Executor;
+use std::{
+    ops::{Deref, DerefMut},
+    sync::Arc,
+};
+
+/// Executor shared between clones of an evm until one of them changes it,
+/// when that clone takes a copy of its own. Reads go through `Deref`, so
+/// only clones that write ever pay for copying the local state.
+#[derive(Clone)]
+pub struct CowExecutor(Arc<Executor>);
+
+impl CowExecutor {
+    /// Whether another clone still shares this executor.
+    pub fn is_shared(&self) -> bool {
+        Arc::strong_count(&self.0) > 1
+    }
+}
+
+impl From<Executor> for CowExecutor {
+    fn from(executor: Executor) -> Self {
+        Self(Arc::new(executor))
+    }
+}
+
+impl Deref for CowExecutor {
+    type Target = Executor;
+
+    fn deref(&self) -> &Executor {
+        &self.0
+    }
+}
+
+impl DerefMut for CowExecutor {
+    fn deref_mut(&mut self) -> &mut Executor {
+        Arc::make_mut(&mut self.0)
+    }
+}
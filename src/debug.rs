@@ -0,0 +1,233 @@
+use crate::{ForkedEvm, ForkerError};
+use revm::{
+    interpreter::{CallInputs, Gas, InstructionResult, Interpreter},
+    primitives::{Address, Bytes, ExecutionResult, U256},
+    Database, EVMData, Inspector,
+};
+use std::collections::HashMap;
+
+/// Granularity at which a [DebugSession] hands control back to the caller.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StepMode {
+    /// Pause once at the start of every call frame.
+    Frame,
+    /// Pause before every executed opcode.
+    Opcode,
+}
+
+/// What the caller wants the session to do after inspecting a step.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DebugAction {
+    Continue,
+    Abort,
+}
+
+/// Snapshot of the interpreter handed to the caller at each pause.
+#[derive(Debug, Clone)]
+pub struct DebugStep {
+    pub depth: u64,
+    pub address: Address,
+    pub pc: usize,
+    pub opcode: u8,
+    pub stack: Vec<U256>,
+    pub memory: Vec<u8>,
+    /// Storage slots of `address` touched so far in this execution.
+    pub storage: HashMap<U256, U256>,
+}
+
+#[derive(Debug, Clone)]
+pub struct DebugOutcome {
+    pub result: ExecutionResult,
+    pub aborted: bool,
+    pub steps: usize,
+}
+
+/// Executes a call without committing it, pausing at each frame or opcode
+/// so the caller can inspect the machine state and continue or abort.
+pub struct DebugSession<F> {
+    mode: StepMode,
+    on_step: F,
+    aborted: bool,
+    steps: usize,
+}
+
+impl<F> DebugSession<F>
+where
+    F: FnMut(&DebugStep) -> DebugAction,
+{
+    pub fn new(mode: StepMode, on_step: F) -> Self {
+        Self {
+            mode,
+            on_step,
+            aborted: false,
+            steps: 0,
+        }
+    }
+
+    pub fn run(
+        mut self,
+        forked_evm: &ForkedEvm,
+        from_address: &[u8],
+        to_address: &[u8],
+        calldata: &[u8],
+    ) -> Result<DebugOutcome, ForkerError> {
+        let mut env = forked_evm.build_env(from_address, to_address, calldata)?;
+        let mut db = foundry_evm::backend::CowBackend::new(&forked_evm.executor.backend);
+        let result = db
+            .inspect_ref(&mut env, &mut self)
+            .map_err(ForkerError::executor)?
+            .result;
+        Ok(DebugOutcome {
+            result,
+            aborted: self.aborted,
+            steps: self.steps,
+        })
+    }
+
+    fn pause<DB: Database>(&mut self, interp: &mut Interpreter<'_>, data: &mut EVMData<'_, DB>) {
+        if self.aborted {
+            interp.instruction_result = InstructionResult::Revert;
+            return;
+        }
+        let address = interp.contract.address;
+        let storage = data
+            .journaled_state
+            .state
+            .get(&address)
+            .map(|account| {
+                account
+                    .storage
+                    .iter()
+                    .map(|(slot, value)| (*slot, value.present_value()))
+                    .collect()
+            })
+            .unwrap_or_default();
+        let step = DebugStep {
+            depth: data.journaled_state.depth(),
+            address,
+            pc: interp.program_counter(),
+            opcode: interp.current_opcode(),
+            stack: interp.stack().data().clone(),
+            memory: interp.shared_memory.context_memory().to_vec(),
+            storage,
+        };
+        self.steps += 1;
+        if (self.on_step)(&step) == DebugAction::Abort {
+            self.aborted = true;
+            interp.instruction_result = InstructionResult::Revert;
+        }
+    }
+}
+
+impl<F, DB> Inspector<DB> for DebugSession<F>
+where
+    F: FnMut(&DebugStep) -> DebugAction,
+    DB: Database,
+{
+    fn initialize_interp(&mut self, interp: &mut Interpreter<'_>, data: &mut EVMData<'_, DB>) {
+        if self.mode == StepMode::Frame {
+            self.pause(interp, data);
+        }
+    }
+
+    fn step(&mut self, interp: &mut Interpreter<'_>, data: &mut EVMData<'_, DB>) {
+        if self.mode == StepMode::Opcode {
+            self.pause(interp, data);
+        }
+    }
+
+    fn call(
+        &mut self,
+        _data: &mut EVMData<'_, DB>,
+        inputs: &mut CallInputs,
+    ) -> (InstructionResult, Gas, Bytes) {
+        if self.aborted {
+            return (
+                InstructionResult::Revert,
+                Gas::new(inputs.gas_limit),
+                Bytes::new(),
+            );
+        }
+        (
+            InstructionResult::Continue,
+            Gas::new(inputs.gas_limit),
+            Bytes::new(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::evm_with_code;
+    use revm::primitives::Output;
+
+    // returns 1 + 2 as a single word
+    const ADD_CODE: &str = "600160020160005260206000f3";
+
+    #[tokio::test]
+    async fn test_opcode_mode_pauses_at_every_opcode() {
+        let target = Address::repeat_byte(0x11);
+        let forked_evm = evm_with_code(&[(target, ADD_CODE)]).await;
+
+        let mut opcodes = vec![];
+        let outcome = DebugSession::new(StepMode::Opcode, |step: &DebugStep| {
+            opcodes.push(step.opcode);
+            DebugAction::Continue
+        })
+        .run(&forked_evm, &[0u8; 20], target.as_slice(), &[])
+        .unwrap();
+
+        assert!(!outcome.aborted);
+        assert_eq!(outcome.steps, 8);
+        assert_eq!(
+            opcodes,
+            vec![0x60, 0x60, 0x01, 0x60, 0x52, 0x60, 0x60, 0xf3]
+        );
+        match outcome.result {
+            ExecutionResult::Success {
+                output: Output::Call(output),
+                ..
+            } => assert_eq!(U256::from_be_slice(&output), U256::from(3)),
+            other => panic!("unexpected result {other:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_frame_mode_pauses_once_per_frame() {
+        let target = Address::repeat_byte(0x11);
+        let forked_evm = evm_with_code(&[(target, ADD_CODE)]).await;
+
+        let outcome = DebugSession::new(StepMode::Frame, |step: &DebugStep| {
+            assert_eq!(step.address, target);
+            assert_eq!(step.pc, 0);
+            DebugAction::Continue
+        })
+        .run(&forked_evm, &[0u8; 20], target.as_slice(), &[])
+        .unwrap();
+
+        assert_eq!(outcome.steps, 1);
+        assert!(outcome.result.is_success());
+    }
+
+    #[tokio::test]
+    async fn test_abort_reverts_the_call() {
+        let target = Address::repeat_byte(0x11);
+        let forked_evm = evm_with_code(&[(target, ADD_CODE)]).await;
+
+        let outcome = DebugSession::new(StepMode::Opcode, |step: &DebugStep| {
+            if step.opcode == 0x01 {
+                assert_eq!(step.stack, vec![U256::from(1), U256::from(2)]);
+                DebugAction::Abort
+            } else {
+                DebugAction::Continue
+            }
+        })
+        .run(&forked_evm, &[0u8; 20], target.as_slice(), &[])
+        .unwrap();
+
+        assert!(outcome.aborted);
+        assert_eq!(outcome.steps, 3);
+        assert!(!outcome.result.is_success());
+    }
+}
@@ -0,0 +1,158 @@
+use crate::{ForkedEvm, ForkerError};
+use alloy_dyn_abi::{DynSolValue, FunctionExt, JsonAbiExt, Specifier};
+use alloy_json_abi::{Function, JsonAbi};
+
+impl ForkedEvm {
+    /// Reads `function` of a contract whose json abi is only known at
+    /// runtime, encoding `args` and decoding the returns dynamically.
+    /// Overloads are told apart by which one accepts `args`.
+    pub fn call_dynamic(
+        &self,
+        from_address: &[u8],
+        to_address: &[u8],
+        abi_json: &str,
+        function: &str,
+        args: &[DynSolValue],
+    ) -> Result<Vec<DynSolValue>, ForkerError> {
+        let abi: JsonAbi = serde_json::from_str(abi_json)?;
+        let overloads = abi
+            .function(function)
+            .ok_or_else(|| ForkerError::UnknownFunction(function.to_string()))?;
+        let (function, calldata) = overloads
+            .iter()
+            .find_map(|overload| Some((overload, overload.abi_encode_input(args).ok()?)))
+            .ok_or_else(|| ForkerError::NoMatchingOverload(function.to_string()))?;
+        self.call_function(from_address, to_address, function, &calldata)
+    }
+
+    /// Runs already encoded `calldata` of `function` and decodes its returns.
+    pub(crate) fn call_function(
+        &self,
+        from_address: &[u8],
+        to_address: &[u8],
+        function: &Function,
+        calldata: &[u8],
+    ) -> Result<Vec<DynSolValue>, ForkerError> {
+        let result = self.call(from_address, to_address, calldata)?;
+        if result.reverted {
+            return Err(ForkerError::reverted(&result));
+        }
+        Ok(function.abi_decode_output(&result.result, true)?)
+    }
+}
+
+impl ForkedEvm {
+    /// Reads a function given by its human readable signature, e.g.
+    /// `balanceOf(address) returns (uint256)`, with `args` in their human
+    /// readable form like cast takes them. Returns are decoded when the
+    /// signature declares them and empty otherwise.
+    pub fn call_sig(
+        &self,
+        from_address: &[u8],
+        to_address: &[u8],
+        signature: &str,
+        args: &[&str],
+    ) -> Result<Vec<DynSolValue>, ForkerError> {
+        let function = parse_signature(signature)?;
+        let calldata = encode_args(&function, args)?;
+        self.call_function(from_address, to_address, &function, &calldata)
+    }
+}
+
+pub(crate) fn parse_signature(signature: &str) -> Result<Function, ForkerError> {
+    Function::parse(signature).map_err(|_| ForkerError::InvalidSignature(signature.to_string()))
+}
+
+/// Abi encodes a call to `function` from human readable arguments.
+pub(crate) fn encode_args(
+    function: &Function,
+    args: &[impl AsRef<str>],
+) -> Result<Vec<u8>, ForkerError> {
+    if function.inputs.len() != args.len() {
+        return Err(ForkerError::ArgumentCount {
+            signature: function.signature(),
+            expected: function.inputs.len(),
+            got: args.len(),
+        });
+    }
+    let values = function
+        .inputs
+        .iter()
+        .zip(args)
+        .map(|(param, arg)| {
+            let ty = param.resolve()?;
+            ty.coerce_str(arg.as_ref()).map_err(ForkerError::from)
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(function.abi_encode_input(&values)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        testing::{evm_with_code, returning_code},
+        ForkerError,
+    };
+    use alloy_dyn_abi::DynSolValue;
+    use revm::primitives::{Address, U256};
+
+    const ABI: &str = r#"[{
+        "type": "function",
+        "name": "get",
+        "inputs": [{"name": "key", "type": "uint256"}],
+        "outputs": [{"name": "", "type": "uint256"}],
+        "stateMutability": "view"
+    }]"#;
+
+    #[tokio::test]
+    async fn test_call_dynamic_encodes_and_decodes() {
+        let target = Address::repeat_byte(0x22);
+        let code = returning_code(&U256::from(42).to_be_bytes::<32>());
+        let forked_evm = evm_with_code(&[(target, &code)]).await;
+        let call = |function: &str, args: &[DynSolValue]| {
+            forked_evm.call_dynamic(&[0u8; 20], target.as_slice(), ABI, function, args)
+        };
+
+        let returns = call("get", &[DynSolValue::Uint(U256::from(1), 256)]).unwrap();
+        assert_eq!(returns, vec![DynSolValue::Uint(U256::from(42), 256)]);
+        assert!(matches!(
+            call("set", &[]),
+            Err(ForkerError::UnknownFunction(_))
+        ));
+        assert!(matches!(
+            call("get", &[DynSolValue::Bool(true)]),
+            Err(ForkerError::NoMatchingOverload(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_call_sig_coerces_string_arguments() {
+        let target = Address::repeat_byte(0x22);
+        let code = returning_code(&U256::from(42).to_be_bytes::<32>());
+        let forked_evm = evm_with_code(&[(target, &code)]).await;
+        let call = |signature: &str, args: &[&str]| {
+            forked_evm.call_sig(&[0u8; 20], target.as_slice(), signature, args)
+        };
+
+        assert_eq!(
+            call("get(uint256) returns (uint256)", &["1"]).unwrap(),
+            vec![DynSolValue::Uint(U256::from(42), 256)]
+        );
+        assert!(matches!(
+            call("get(uint256", &["1"]),
+            Err(ForkerError::InvalidSignature(_))
+        ));
+        assert!(matches!(
+            call("get(uint256)", &[]),
+            Err(ForkerError::ArgumentCount {
+                expected: 1,
+                got: 0,
+                ..
+            })
+        ));
+        assert!(matches!(
+            call("get(uint256)", &["nope"]),
+            Err(ForkerError::DynAbi(_))
+        ));
+    }
+}
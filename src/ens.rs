@@ -0,0 +1,113 @@
+use crate::{ForkedEvm, ForkerError};
+use alloy_sol_types::sol;
+use revm::primitives::{address, hex, keccak256, Address, B256};
+
+/// ENS registry, deployed at the same address on mainnet and its testnets.
+pub const ENS_REGISTRY_ADDRESS: Address = address!("00000000000C2E074eC69A0dFb2997BA6C7d2e1e");
+
+sol! {
+    interface IEnsRegistry {
+        function resolver(bytes32 node) external view returns (address);
+    }
+
+    interface IEnsResolver {
+        function addr(bytes32 node) external view returns (address);
+        function name(bytes32 node) external view returns (string memory);
+    }
+}
+
+/// EIP-137 namehash of an ENS name.
+pub fn namehash(name: &str) -> B256 {
+    let mut node = B256::ZERO;
+    if name.is_empty() {
+        return node;
+    }
+    for label in name.rsplit('.') {
+        let label_hash = keccak256(label.to_lowercase().as_bytes());
+        node = keccak256([node.as_slice(), label_hash.as_slice()].concat());
+    }
+    node
+}
+
+impl ForkedEvm {
+    /// Resolves an ENS name through the forked ENS registry, returning `None`
+    /// if the name has no resolver or no address set.
+    pub fn resolve_ens(&self, name: &str) -> Result<Option<Address>, ForkerError> {
+        let node = namehash(name);
+        let Some(resolver) = self.ens_resolver(node)? else {
+            return Ok(None);
+        };
+        let resolved = self
+            .alloy_read(
+                Address::ZERO.as_slice(),
+                resolver.as_slice(),
+                IEnsResolver::addrCall { node },
+            )?
+            ._0;
+        Ok((resolved != Address::ZERO).then_some(resolved))
+    }
+
+    /// Looks up the primary ENS name of `address` through the reverse
+    /// registrar, only returning it if it resolves back to `address`.
+    pub fn lookup_address(&self, address: Address) -> Result<Option<String>, ForkerError> {
+        let node = namehash(&format!("{}.addr.reverse", hex::encode(address)));
+        let Some(resolver) = self.ens_resolver(node)? else {
+            return Ok(None);
+        };
+        let name = self
+            .alloy_read(
+                Address::ZERO.as_slice(),
+                resolver.as_slice(),
+                IEnsResolver::nameCall { node },
+            )?
+            ._0;
+        if name.is_empty() || self.resolve_ens(&name)? != Some(address) {
+            return Ok(None);
+        }
+        Ok(Some(name))
+    }
+
+    fn ens_resolver(&self, node: B256) -> Result<Option<Address>, ForkerError> {
+        let resolver = self
+            .alloy_read(
+                Address::ZERO.as_slice(),
+                ENS_REGISTRY_ADDRESS.as_slice(),
+                IEnsRegistry::resolverCall { node },
+            )?
+            ._0;
+        Ok((resolver != Address::ZERO).then_some(resolver))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{evm_with_code, returning_code};
+    use revm::primitives::b256;
+
+    #[test]
+    fn test_namehash() {
+        assert_eq!(namehash(""), B256::ZERO);
+        assert_eq!(
+            namehash("eth"),
+            b256!("93cdeb708b7545dc668eb9280176169d1c33cfd8ed6f04690a0bcc88a93fc4ae")
+        );
+        assert_eq!(
+            namehash("Foo.eth"),
+            b256!("de9b09fd7c5f901e23a3f19fecc54828e9c848539801e86591bd9801b019f84f")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_names_without_resolver() {
+        let registry = returning_code(&[0u8; 32]);
+        let forked_evm = evm_with_code(&[(ENS_REGISTRY_ADDRESS, &registry)]).await;
+        assert_eq!(forked_evm.resolve_ens("vitalik.eth").unwrap(), None);
+        assert_eq!(
+            forked_evm
+                .lookup_address(Address::repeat_byte(0x11))
+                .unwrap(),
+            None
+        );
+    }
+}
@@ -0,0 +1,197 @@
+use crate::{ForkedEvm, ForkerError};
+use alloy_sol_types::{sol, SolCall, SolError};
+use revm::primitives::{address, Address, Bytes, U256};
+
+/// EntryPoint v0.6 deployment, at the same address on every supported chain.
+pub const ENTRY_POINT_V06_ADDRESS: Address = address!("5FF137D4b0FDCD49DcA30c7CF57E578a026d2789");
+
+sol! {
+    struct UserOperation {
+        address sender;
+        uint256 nonce;
+        bytes initCode;
+        bytes callData;
+        uint256 callGasLimit;
+        uint256 verificationGasLimit;
+        uint256 preVerificationGas;
+        uint256 maxFeePerGas;
+        uint256 maxPriorityFeePerGas;
+        bytes paymasterAndData;
+        bytes signature;
+    }
+
+    struct ReturnInfo {
+        uint256 preOpGas;
+        uint256 prefund;
+        bool sigFailed;
+        uint48 validAfter;
+        uint48 validUntil;
+        bytes paymasterContext;
+    }
+
+    struct StakeInfo {
+        uint256 stake;
+        uint256 unstakeDelay;
+    }
+
+    interface IEntryPoint {
+        error FailedOp(uint256 opIndex, string reason);
+        error ValidationResult(ReturnInfo returnInfo, StakeInfo senderInfo, StakeInfo factoryInfo, StakeInfo paymasterInfo);
+        error ExecutionResult(uint256 preOpGas, uint256 paid, uint48 validAfter, uint48 validUntil, bool targetSuccess, bytes targetResult);
+
+        function simulateValidation(UserOperation calldata userOp) external;
+        function simulateHandleOp(UserOperation calldata op, address target, bytes calldata targetCallData) external;
+    }
+}
+
+/// Decoded `ValidationResult` of `simulateValidation`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UserOpValidation {
+    pub pre_op_gas: U256,
+    pub prefund: U256,
+    pub sig_failed: bool,
+    pub valid_after: u64,
+    pub valid_until: u64,
+    pub paymaster_context: Bytes,
+    pub sender_stake: (U256, U256),
+    pub factory_stake: (U256, U256),
+    pub paymaster_stake: (U256, U256),
+}
+
+/// Decoded `ExecutionResult` of `simulateHandleOp`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UserOpExecution {
+    pub pre_op_gas: U256,
+    pub paid: U256,
+    pub valid_after: u64,
+    pub valid_until: u64,
+    pub target_success: bool,
+    pub target_result: Bytes,
+}
+
+impl ForkedEvm {
+    /// Runs `simulateValidation` for `user_op` on the given EntryPoint.
+    /// Validation failures reported through `FailedOp` are returned as
+    /// [ForkerError::UserOpFailed] with the EntryPoint's reason.
+    pub fn simulate_user_op_validation(
+        &self,
+        entry_point: Address,
+        user_op: UserOperation,
+    ) -> Result<UserOpValidation, ForkerError> {
+        let calldata = IEntryPoint::simulateValidationCall { userOp: user_op }.abi_encode();
+        let data = self.simulate_user_op(entry_point, &calldata)?;
+        let result = IEntryPoint::ValidationResult::abi_decode(&data, true)?;
+        Ok(UserOpValidation {
+            pre_op_gas: result.returnInfo.preOpGas,
+            prefund: result.returnInfo.prefund,
+            sig_failed: result.returnInfo.sigFailed,
+            valid_after: result.returnInfo.validAfter,
+            valid_until: result.returnInfo.validUntil,
+            paymaster_context: result.returnInfo.paymasterContext,
+            sender_stake: (result.senderInfo.stake, result.senderInfo.unstakeDelay),
+            factory_stake: (result.factoryInfo.stake, result.factoryInfo.unstakeDelay),
+            paymaster_stake: (
+                result.paymasterInfo.stake,
+                result.paymasterInfo.unstakeDelay,
+            ),
+        })
+    }
+
+    /// Runs `simulateHandleOp` for `user_op` on the given EntryPoint,
+    /// optionally calling `target` with `target_calldata` afterwards.
+    pub fn simulate_user_op_execution(
+        &self,
+        entry_point: Address,
+        user_op: UserOperation,
+        target: Address,
+        target_calldata: Bytes,
+    ) -> Result<UserOpExecution, ForkerError> {
+        let calldata = IEntryPoint::simulateHandleOpCall {
+            op: user_op,
+            target,
+            targetCallData: target_calldata,
+        }
+        .abi_encode();
+        let data = self.simulate_user_op(entry_point, &calldata)?;
+        let result = IEntryPoint::ExecutionResult::abi_decode(&data, true)?;
+        Ok(UserOpExecution {
+            pre_op_gas: result.preOpGas,
+            paid: result.paid,
+            valid_after: result.validAfter,
+            valid_until: result.validUntil,
+            target_success: result.targetSuccess,
+            target_result: result.targetResult,
+        })
+    }
+
+    /// EntryPoint simulations always revert, returning their result as the
+    /// revert data. Returns that data unless it is a `FailedOp`.
+    fn simulate_user_op(
+        &self,
+        entry_point: Address,
+        calldata: &[u8],
+    ) -> Result<Bytes, ForkerError> {
+        let result = self.call(Address::ZERO.as_slice(), entry_point.as_slice(), calldata)?;
+        if !result.reverted {
+            return Err(ForkerError::SimulationDidNotRevert(result.result));
+        }
+        if let Ok(failed) = IEntryPoint::FailedOp::abi_decode(&result.result, true) {
+            return Err(ForkerError::UserOpFailed {
+                op_index: failed.opIndex,
+                reason: failed.reason,
+            });
+        }
+        Ok(result.result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{evm_with_code, returning_code, reverting_code};
+
+    fn user_op() -> UserOperation {
+        UserOperation {
+            sender: Address::repeat_byte(0x21),
+            nonce: U256::ZERO,
+            initCode: Bytes::new(),
+            callData: Bytes::new(),
+            callGasLimit: U256::ZERO,
+            verificationGasLimit: U256::ZERO,
+            preVerificationGas: U256::ZERO,
+            maxFeePerGas: U256::ZERO,
+            maxPriorityFeePerGas: U256::ZERO,
+            paymasterAndData: Bytes::new(),
+            signature: Bytes::new(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_failed_ops_are_reported() {
+        let failing = Address::repeat_byte(0x11);
+        let returning = Address::repeat_byte(0x12);
+        let failed_op = IEntryPoint::FailedOp {
+            opIndex: U256::from(2),
+            reason: "AA21 didn't pay prefund".to_string(),
+        }
+        .abi_encode();
+        let forked_evm = evm_with_code(&[
+            (failing, &reverting_code(&failed_op)),
+            (returning, &returning_code(&[])),
+        ])
+        .await;
+
+        let result = forked_evm.simulate_user_op_validation(failing, user_op());
+        match result {
+            Err(ForkerError::UserOpFailed { op_index, reason }) => {
+                assert_eq!(op_index, U256::from(2));
+                assert_eq!(reason, "AA21 didn't pay prefund");
+            }
+            other => panic!("unexpected result {other:?}"),
+        }
+        assert!(matches!(
+            forked_evm.simulate_user_op_validation(returning, user_op()),
+            Err(ForkerError::SimulationDidNotRevert(_))
+        ));
+    }
+}
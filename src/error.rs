@@ -0,0 +1,288 @@
+use crate::rain::ParseError;
+use foundry_evm::{backend::DatabaseError, executors::RawCallResult, fork::ForkId};
+use revm::{
+    interpreter::InstructionResult,
+    primitives::{Address, Bytes, B256, U256},
+};
+
+/// Underlying error of a failed rpc, fork or executor operation.
+pub type BoxError = Box<dyn std::error::Error + Send + Sync>;
+
+/// Errors returned by [crate::ForkedEvm] and its helpers.
+#[derive(Debug, thiserror::Error)]
+pub enum ForkerError {
+    /// Creating a fork of, or fetching its env from, `url` failed.
+    #[error("rpc error for {url}: {source}")]
+    RpcError {
+        url: String,
+        #[source]
+        source: BoxError,
+    },
+    #[error("failed to select fork {local_id}: {source}")]
+    ForkSelectError {
+        local_id: U256,
+        #[source]
+        source: BoxError,
+    },
+    #[error("no active fork")]
+    NoActiveFork,
+    /// The fork id was not created through this evm.
+    #[error("unknown fork {0:?}")]
+    UnknownFork(ForkId),
+    /// The pending block is the genesis block, so there is no parent to fork.
+    #[error("pending block {0} has no parent to fork")]
+    NoParentBlock(u64),
+    #[error("block step must be positive")]
+    ZeroBlockStep,
+    #[error("bench needs at least one run")]
+    ZeroBenchRuns,
+    #[error("unknown chain: {0}")]
+    UnknownChain(String),
+    /// Relayed messages kept triggering further messages. The deliveries
+    /// made are committed and returned here.
+    #[error("messages still pending after {rounds} relay rounds")]
+    RelayLimitReached {
+        rounds: usize,
+        deliveries: Vec<crate::multichain::Delivery>,
+    },
+    /// Deposit transactions only exist on OP stack chains.
+    #[error("chain {0} has no deposit transactions")]
+    DepositsUnsupported(u64),
+    #[error("transaction has no recipient")]
+    MissingRecipient,
+    #[error("invalid address, expected 20 bytes but got {got_len}")]
+    InvalidAddress { got_len: usize },
+    #[error(
+        "execution reverted ({exit_reason:?}, {gas_used} gas): {}",
+        reason.as_deref().unwrap_or("no reason")
+    )]
+    ExecutionReverted {
+        /// Raw revert data returned by the call.
+        data: Bytes,
+        /// `Error(string)` or `Panic(uint256)` reason decoded from `data`.
+        reason: Option<String>,
+        gas_used: u64,
+        exit_reason: InstructionResult,
+    },
+    #[error("multicall inner call reverted: {}", reason.as_deref().unwrap_or("no reason"))]
+    MulticallReverted { data: Bytes, reason: Option<String> },
+    #[error("deployment failed: {0:?}")]
+    DeploymentFailed(InstructionResult),
+    #[error("erc20 call to {token} returned false")]
+    Erc20ReturnedFalse { token: Address },
+    #[error("no signer registered for {0}")]
+    MissingSigner(Address),
+    #[error("invalid private key: {0}")]
+    InvalidPrivateKey(String),
+    #[error("signing failed: {0}")]
+    SigningFailed(String),
+    #[error("transaction {0} is already mined")]
+    TransactionMined(B256),
+    #[error("invalid transaction: {0}")]
+    InvalidTransaction(String),
+    #[error("transaction is signed for chain {got}, expected {expected}")]
+    ChainIdMismatch { expected: u64, got: u64 },
+    #[error("invalid nonce {got}, expected {expected}")]
+    NonceMismatch { expected: u64, got: u64 },
+    /// `FailedOp` reported by an ERC-4337 EntryPoint simulation.
+    #[error("user operation {op_index} failed: {reason}")]
+    UserOpFailed { op_index: U256, reason: String },
+    /// An EntryPoint simulation returned instead of reverting with its result.
+    #[error("entry point simulation did not revert with a result")]
+    SimulationDidNotRevert(Bytes),
+    #[error("abi decode error: {0}")]
+    AbiDecode(#[from] alloy_sol_types::Error),
+    #[error("dynamic abi error: {0}")]
+    DynAbi(#[from] alloy_dyn_abi::Error),
+    #[error(transparent)]
+    Expectation(#[from] crate::expect::ExpectationError),
+    #[error("storage layout error: {0}")]
+    StorageLayout(String),
+    /// A dynamic array or bytes length read from storage is beyond what
+    /// decoding it would reasonably allocate, e.g. an uninitialized layout.
+    #[error("storage length {len} at slot {slot} exceeds {max}")]
+    StorageLengthTooLarge { slot: U256, len: U256, max: usize },
+    #[error("execution timed out after {0:?}")]
+    Timeout(std::time::Duration),
+    /// The executor failed to run a transaction, as opposed to the
+    /// transaction reverting.
+    #[error("executor error: {0}")]
+    ExecutorError(#[source] BoxError),
+    #[error("coverage is not enabled")]
+    CoverageDisabled,
+    #[error("invalid source map: {0}")]
+    InvalidSourceMap(String),
+    #[error("invalid artifact: {0}")]
+    InvalidArtifact(String),
+    /// A function signature that doesn't parse as a name and a tuple of
+    /// parameter types.
+    #[error("invalid function signature {0}")]
+    InvalidSignature(String),
+    #[error("abi has no function {0}")]
+    UnknownFunction(String),
+    #[error("no overload of {0} accepts the arguments")]
+    NoMatchingOverload(String),
+    #[error("{signature} takes {expected} arguments but got {got}")]
+    ArgumentCount {
+        signature: String,
+        expected: usize,
+        got: usize,
+    },
+    #[error("no fuzz strategy for {0}")]
+    UnsupportedFuzzType(String),
+    /// The fuzz runner gave up before running all cases, e.g. because too
+    /// many inputs were rejected.
+    #[error("fuzz run aborted: {0}")]
+    FuzzAborted(String),
+    /// State a session can't hold, e.g. signer keys or precompile closures.
+    #[error("{0} can't be saved in a session")]
+    SessionUnsupported(&'static str),
+    #[error("worker thread panicked")]
+    WorkerPanicked,
+    #[error("fork needs at least one rpc url")]
+    NoFallbackRpcs,
+    #[error("unsupported rpc url {0}, expected http(s), ws(s) or ipc")]
+    InvalidRpcUrl(String),
+    /// The header's value is left out, as it usually is a credential.
+    #[error("invalid rpc header {0}")]
+    InvalidHeader(String),
+    /// State the forks' caches don't hold was needed while offline, see
+    /// [crate::ForkedEvm::set_offline].
+    #[error("state missing while offline: {0}")]
+    MissingState(#[source] BoxError),
+    #[error(transparent)]
+    Database(DatabaseError),
+    #[error(transparent)]
+    Parse(#[from] ParseError),
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
+    #[error(transparent)]
+    Yaml(#[from] serde_yaml::Error),
+    #[cfg(feature = "native")]
+    #[error(transparent)]
+    Server(#[from] hyper::Error),
+    /// A task running blocking work panicked or was cancelled.
+    #[cfg(feature = "native")]
+    #[error("execution task failed: {0}")]
+    Join(#[from] tokio::task::JoinError),
+}
+
+impl ForkerError {
+    /// Builds [ForkerError::ExecutionReverted] from a reverted call result,
+    /// decoding `Error(string)` and `Panic(uint256)` reasons when present.
+    pub fn reverted(result: &RawCallResult) -> ForkerError {
+        ForkerError::ExecutionReverted {
+            data: result.result.clone(),
+            reason: alloy_sol_types::decode_revert_reason(&result.result),
+            gas_used: result.gas_used,
+            exit_reason: result.exit_reason,
+        }
+    }
+
+    pub(crate) fn rpc(url: &str, source: impl Into<BoxError>) -> ForkerError {
+        ForkerError::RpcError {
+            url: url.to_string(),
+            source: source.into(),
+        }
+    }
+
+    pub(crate) fn fork_select(local_id: U256, source: impl Into<BoxError>) -> ForkerError {
+        ForkerError::ForkSelectError {
+            local_id,
+            source: source.into(),
+        }
+    }
+
+    pub(crate) fn executor(source: impl Into<BoxError>) -> ForkerError {
+        let source = source.into();
+        #[cfg(feature = "native")]
+        if crate::offline::refused_offline(source.as_ref()) {
+            return ForkerError::MissingState(source);
+        }
+        ForkerError::ExecutorError(source)
+    }
+}
+
+impl From<DatabaseError> for ForkerError {
+    fn from(error: DatabaseError) -> Self {
+        #[cfg(feature = "native")]
+        if crate::offline::refused_offline(&error) {
+            return ForkerError::MissingState(Box::new(error));
+        }
+        ForkerError::Database(error)
+    }
+}
+
+/// Checks that `address` is exactly 20 bytes.
+pub(crate) fn check_address(address: &[u8]) -> Result<(), ForkerError> {
+    if address.len() != 20 {
+        return Err(ForkerError::InvalidAddress {
+            got_len: address.len(),
+        });
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy_sol_types::{Revert, SolError};
+
+    fn reverted_result(data: Bytes) -> RawCallResult {
+        RawCallResult {
+            result: data,
+            reverted: true,
+            gas_used: 21_000,
+            exit_reason: InstructionResult::Revert,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_reverted_decodes_error_string() {
+        let data = Bytes::from(
+            Revert {
+                reason: "nope".into(),
+            }
+            .abi_encode(),
+        );
+        match ForkerError::reverted(&reverted_result(data.clone())) {
+            ForkerError::ExecutionReverted {
+                data: reverted,
+                reason,
+                gas_used,
+                exit_reason,
+            } => {
+                assert_eq!(reverted, data);
+                assert_eq!(reason.as_deref(), Some("nope"));
+                assert_eq!(gas_used, 21_000);
+                assert_eq!(exit_reason, InstructionResult::Revert);
+            }
+            other => panic!("unexpected error {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_reverted_without_reason() {
+        let error = ForkerError::reverted(&reverted_result(Bytes::from_static(&[0xde, 0xad])));
+        assert!(matches!(
+            error,
+            ForkerError::ExecutionReverted { reason: None, .. }
+        ));
+        assert_eq!(
+            error.to_string(),
+            "execution reverted (Revert, 21000 gas): no reason"
+        );
+    }
+
+    #[test]
+    fn test_check_address_reports_length() {
+        assert!(check_address(&[0u8; 20]).is_ok());
+        assert!(matches!(
+            check_address(&[0u8; 19]),
+            Err(ForkerError::InvalidAddress { got_len: 19 })
+        ));
+    }
+}
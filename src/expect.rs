@@ -0,0 +1,210 @@
+use crate::{ForkCallResult, ForkedEvm, ForkerError};
+use alloy_sol_types::{SolError, SolEvent};
+use foundry_evm::executors::RawCallResult;
+use revm::primitives::{hex, Address, Bytes, Log, B256};
+
+/// Difference between what a call was expected to do and what it did.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum ExpectationError {
+    #[error("expected revert with {expected} but the call succeeded")]
+    DidNotRevert { expected: String },
+    #[error("expected revert with {expected} but it reverted with {got}")]
+    WrongRevert {
+        expected: String,
+        /// Decoded reason of the actual revert, or its raw data in hex.
+        got: String,
+        data: Bytes,
+    },
+    #[error(
+        "expected event {expected} was not emitted, {} logs with the same signature: {candidates:?}",
+        candidates.len()
+    )]
+    EventNotEmitted {
+        expected: String,
+        /// Logs with the expected signature that differ in topics or data.
+        candidates: Vec<String>,
+    },
+}
+
+/// What [ForkedEvm::expect_revert] can check for a revert. Raw calls and
+/// writes report reverts in their result rather than as an error.
+pub trait CallOutcome {
+    /// The raw result if the call ran and reverted.
+    fn reverted_result(&self) -> Option<&RawCallResult>;
+}
+
+impl CallOutcome for RawCallResult {
+    fn reverted_result(&self) -> Option<&RawCallResult> {
+        self.reverted.then_some(self)
+    }
+}
+
+impl CallOutcome for ForkCallResult {
+    fn reverted_result(&self) -> Option<&RawCallResult> {
+        self.raw.reverted_result()
+    }
+}
+
+/// The result of [ForkedEvm::alloy_write].
+impl<R> CallOutcome for (RawCallResult, R) {
+    fn reverted_result(&self) -> Option<&RawCallResult> {
+        self.0.reverted_result()
+    }
+}
+
+/// The result of [ForkedEvm::deploy], which errors on reverts.
+impl CallOutcome for Address {
+    fn reverted_result(&self) -> Option<&RawCallResult> {
+        None
+    }
+}
+
+/// For calls that already turn reverts into errors, e.g.
+/// `forked_evm.alloy_read(..).map(|_| ())`.
+impl CallOutcome for () {
+    fn reverted_result(&self) -> Option<&RawCallResult> {
+        None
+    }
+}
+
+impl ForkedEvm {
+    /// Runs `f` and checks it reverted with the error `E`, returning the
+    /// decoded error. Use [alloy_sol_types::Revert] for `require` messages.
+    pub fn expect_revert<E: SolError, T: CallOutcome>(
+        &mut self,
+        f: impl FnOnce(&mut Self) -> Result<T, ForkerError>,
+    ) -> Result<E, ForkerError> {
+        let outcome = f(self).and_then(|outcome| match outcome.reverted_result() {
+            Some(result) => Err(ForkerError::reverted(result)),
+            None => Ok(outcome),
+        });
+        match outcome {
+            Ok(_) => Err(ExpectationError::DidNotRevert {
+                expected: E::SIGNATURE.to_string(),
+            }
+            .into()),
+            Err(ForkerError::ExecutionReverted { data, reason, .. }) => E::abi_decode(&data, true)
+                .map_err(|_| {
+                    ExpectationError::WrongRevert {
+                        expected: E::SIGNATURE.to_string(),
+                        got: reason.unwrap_or_else(|| hex::encode_prefixed(&data)),
+                        data,
+                    }
+                    .into()
+                }),
+            Err(e) => Err(e),
+        }
+    }
+}
+
+/// Checks `logs` contain `expected` with exactly its topics and data, e.g.
+/// the logs of a write's result.
+pub fn expect_emit<E: SolEvent>(logs: &[Log], expected: &E) -> Result<(), ForkerError> {
+    let topics: Vec<B256> = expected
+        .encode_topics()
+        .into_iter()
+        .map(|topic| topic.0)
+        .collect();
+    let data = expected.encode_data();
+    if logs
+        .iter()
+        .any(|log| log.topics == topics && log.data.as_ref() == data.as_slice())
+    {
+        return Ok(());
+    }
+    let candidates = logs
+        .iter()
+        .filter(|log| E::ANONYMOUS || log.topics.first() == Some(&E::SIGNATURE_HASH))
+        .map(describe_log)
+        .collect();
+    Err(ExpectationError::EventNotEmitted {
+        expected: format!(
+            "{} topics {topics:?} data {}",
+            E::SIGNATURE,
+            hex::encode_prefixed(&data)
+        ),
+        candidates,
+    }
+    .into())
+}
+
+fn describe_log(log: &Log) -> String {
+    format!(
+        "{} topics {:?} data {}",
+        log.address,
+        log.topics,
+        hex::encode_prefixed(&log.data)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{evm_with_code, reverting_code};
+    use alloy_sol_types::{sol, Revert};
+    use revm::primitives::{keccak256, U256};
+
+    sol! {
+        event Ping();
+        error Unauthorized();
+    }
+
+    #[tokio::test]
+    async fn test_expect_revert_checks_reverted_results() {
+        let reverting = Address::repeat_byte(0x11);
+        let adding = Address::repeat_byte(0x12);
+        let data = Revert {
+            reason: "nope".to_string(),
+        }
+        .abi_encode();
+        let mut forked_evm = evm_with_code(&[
+            (reverting, &reverting_code(&data)),
+            (adding, "600160020160005260206000f3"),
+        ])
+        .await;
+
+        // write reports the revert in its result, not as an error
+        let revert = forked_evm
+            .expect_revert::<Revert, _>(|evm| {
+                evm.write(&[0u8; 20], reverting.as_slice(), &[], U256::ZERO)
+            })
+            .unwrap();
+        assert_eq!(revert.reason, "nope");
+
+        assert!(matches!(
+            forked_evm.expect_revert::<Unauthorized, _>(|evm| {
+                evm.write(&[0u8; 20], reverting.as_slice(), &[], U256::ZERO)
+            }),
+            Err(ForkerError::Expectation(
+                ExpectationError::WrongRevert { .. }
+            ))
+        ));
+        assert!(matches!(
+            forked_evm.expect_revert::<Revert, _>(|evm| {
+                evm.write(&[0u8; 20], adding.as_slice(), &[], U256::ZERO)
+            }),
+            Err(ForkerError::Expectation(
+                ExpectationError::DidNotRevert { .. }
+            ))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_expect_emit_matches_topics() {
+        let emitter = Address::repeat_byte(0x11);
+        // PUSH32 keccak("Ping()") PUSH1 0 PUSH1 0 LOG1 STOP
+        let code = format!("7f{}60006000a100", hex::encode(keccak256("Ping()")));
+        let mut forked_evm = evm_with_code(&[(emitter, &code)]).await;
+        let result = forked_evm
+            .write(&[0u8; 20], emitter.as_slice(), &[], U256::ZERO)
+            .unwrap();
+
+        expect_emit(&result.logs, &Ping {}).unwrap();
+        assert!(matches!(
+            expect_emit(&[], &Ping {}),
+            Err(ForkerError::Expectation(
+                ExpectationError::EventNotEmitted { .. }
+            ))
+        ));
+    }
+}
@@ -0,0 +1,299 @@
+use crate::{
+    offline::offline_response,
+    transport::{RelayPolicy, Upstream, UpstreamClient},
+    ForkedEvm, ForkerError,
+};
+use async_trait::async_trait;
+use ethers::providers::{
+    HttpClientError, HttpRateLimitRetryPolicy, JsonRpcClient, JsonRpcError, ProviderError,
+    RetryPolicy, RpcError,
+};
+use revm::primitives::Env;
+use serde::{de::DeserializeOwned, Serialize};
+use std::{
+    fmt::Debug,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+/// Client sending each request to the RPC that last answered, moving on to
+/// the next one on transport errors and rate limiting. Other errors, e.g. a
+/// revert, are the chain's answer and returned as they are.
+#[derive(Debug)]
+pub(crate) struct FallbackClient {
+    clients: Vec<UpstreamClient>,
+    preferred: Arc<AtomicUsize>,
+    timeout: Option<Duration>,
+    policy: RelayPolicy,
+}
+
+/// Error of a [FallbackClient] request.
+#[derive(Debug, thiserror::Error)]
+pub(crate) enum FallbackError {
+    #[error(transparent)]
+    Rpc(#[from] ProviderError),
+    #[error("no rpcs to fall back to")]
+    NoRpcs,
+    #[error("rpc request timed out after {0:?}")]
+    Timeout(Duration),
+    /// Refused while offline, answered with [crate::offline::OFFLINE_CODE].
+    #[error("{}", .0.message)]
+    Offline(JsonRpcError),
+}
+
+impl RpcError for FallbackError {
+    fn as_error_response(&self) -> Option<&JsonRpcError> {
+        match self {
+            FallbackError::Rpc(e) => e.as_error_response(),
+            FallbackError::Offline(e) => Some(e),
+            FallbackError::NoRpcs | FallbackError::Timeout(_) => None,
+        }
+    }
+
+    fn as_serde_error(&self) -> Option<&serde_json::Error> {
+        match self {
+            FallbackError::Rpc(e) => e.as_serde_error(),
+            FallbackError::NoRpcs | FallbackError::Timeout(_) | FallbackError::Offline(_) => None,
+        }
+    }
+}
+
+impl From<FallbackError> for ProviderError {
+    fn from(e: FallbackError) -> Self {
+        match e {
+            FallbackError::Rpc(e) => e,
+            e @ (FallbackError::NoRpcs | FallbackError::Timeout(_) | FallbackError::Offline(_)) => {
+                ProviderError::JsonRpcClientError(Box::new(e))
+            }
+        }
+    }
+}
+
+impl FallbackClient {
+    /// Connects to every RPC of `upstream`. Ws and ipc clients run on the
+    /// runtime this is called on, as do the timers of request timeouts.
+    pub(crate) async fn connect(
+        upstream: &Upstream,
+        preferred: Arc<AtomicUsize>,
+        policy: RelayPolicy,
+    ) -> Result<Self, ForkerError> {
+        let mut clients = Vec::with_capacity(upstream.urls.len());
+        for url in &upstream.urls {
+            clients.push(UpstreamClient::connect(url, upstream).await?);
+        }
+        Ok(Self {
+            clients,
+            preferred,
+            timeout: upstream.timeout,
+            policy,
+        })
+    }
+}
+
+#[async_trait]
+impl JsonRpcClient for FallbackClient {
+    type Error = FallbackError;
+
+    async fn request<T, R>(&self, method: &str, params: T) -> Result<R, Self::Error>
+    where
+        T: Debug + Serialize + Send + Sync,
+        R: DeserializeOwned + Send,
+    {
+        if self.policy.offline.load(Ordering::Relaxed) {
+            return Err(FallbackError::Offline(offline_response()));
+        }
+        let preferred = self.preferred.load(Ordering::Relaxed);
+        let mut last_error = None;
+        for i in 0..self.clients.len() {
+            let index = (preferred + i) % self.clients.len();
+            // waiting for the budget doesn't count against the timeout
+            if let Some(limiter) = self.policy.limiters.get(index) {
+                limiter.acquire().await;
+            }
+            let request = async {
+                self.clients[index]
+                    .request(method, &params)
+                    .await
+                    .map_err(FallbackError::Rpc)
+            };
+            // a stalled rpc fails over like one that's down
+            let response = match self.timeout {
+                Some(timeout) => tokio::time::timeout(timeout, request)
+                    .await
+                    .unwrap_or(Err(FallbackError::Timeout(timeout))),
+                None => request.await,
+            };
+            match response {
+                Ok(result) => {
+                    self.preferred.store(index, Ordering::Relaxed);
+                    return Ok(result);
+                }
+                Err(e) if should_fail_over(&e) => {
+                    #[cfg(feature = "tracing")]
+                    tracing::warn!(error = %e, "rpc failed, trying the next one");
+                    last_error = Some(e);
+                }
+                Err(e) => return Err(e),
+            }
+        }
+        Err(last_error.unwrap_or(FallbackError::NoRpcs))
+    }
+}
+
+fn should_fail_over(error: &FallbackError) -> bool {
+    match error.as_error_response() {
+        // the rpc answered, which is only worth retrying elsewhere when it
+        // rate limits, judged the way ethers retries http requests
+        Some(response) => {
+            HttpRateLimitRetryPolicy.should_retry(&HttpClientError::JsonRpcError(response.clone()))
+        }
+        None => true,
+    }
+}
+
+impl ForkedEvm {
+    /// Like [ForkedEvm::add_or_select] with an ordered list of RPCs for the
+    /// same chain. The fork is served through a relay that sends requests to
+    /// the first RPC that answers and transparently moves on to the next one
+    /// on transport errors and rate limiting, so the fork, its id and local
+    /// state never change when a provider fails. See
+    /// [ForkedEvm::serving_rpc] for the RPC currently serving it.
+    pub async fn add_or_select_with_fallbacks(
+        &mut self,
+        fork_urls: &[&str],
+        fork_block_number: Option<u64>,
+        env: Option<Env>,
+    ) -> Result<(), ForkerError> {
+        if fork_urls.is_empty() {
+            return Err(ForkerError::NoFallbackRpcs);
+        }
+        let upstream =
+            self.configured_upstream(fork_urls.iter().map(|url| url.to_string()).collect());
+        let relay = self.relay_for(upstream).await?;
+        self.add_or_select(&relay.url, fork_block_number, env).await
+    }
+
+    /// RPC that last served data of the active fork, the fork's own url
+    /// unless it fails over between several.
+    pub fn serving_rpc(&self) -> Option<&str> {
+        let info = self.active_fork()?;
+        match &info.relay {
+            Some(relay) => relay.serving_url(),
+            None => Some(&info.url),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{FallbackClient, FallbackError};
+    use crate::{
+        testing::{evm_with_code, fixed_relay},
+        transport::Upstream,
+        ForkerError,
+    };
+    use ethers::providers::JsonRpcClient;
+    use serde_json::json;
+    use std::{
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        },
+        time::Duration,
+    };
+
+    #[tokio::test]
+    async fn test_fallback_client_skips_failing_rpcs() {
+        let rate_limited = fixed_relay(Err(429)).await;
+        let answering = fixed_relay(Ok(json!("0x1"))).await;
+        let upstream = Upstream {
+            urls: vec![
+                // nothing listens on port 1
+                "http://127.0.0.1:1/".to_string(),
+                rate_limited.url.clone(),
+                answering.url.clone(),
+            ],
+            ..Default::default()
+        };
+        let preferred = Arc::new(AtomicUsize::new(0));
+        let client = FallbackClient::connect(&upstream, preferred.clone(), Default::default())
+            .await
+            .unwrap();
+
+        let chain_id: String = client.request("eth_chainId", ()).await.unwrap();
+        assert_eq!(chain_id, "0x1");
+        assert_eq!(preferred.load(Ordering::Relaxed), 2);
+
+        // errors that aren't the transport's are the chain's answer
+        let reverting = fixed_relay(Err(3)).await;
+        let upstream = Upstream {
+            urls: vec![reverting.url.clone(), answering.url.clone()],
+            ..Default::default()
+        };
+        let client = FallbackClient::connect(&upstream, Default::default(), Default::default())
+            .await
+            .unwrap();
+        assert!(client.request::<_, String>("eth_call", ()).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_fallback_client_times_out_stalled_rpcs() {
+        // accepts connections but never answers
+        let stalled = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let answering = fixed_relay(Ok(json!("0x1"))).await;
+        let timeout = Duration::from_millis(200);
+        let upstream = Upstream {
+            urls: vec![
+                format!("http://{}/", stalled.local_addr().unwrap()),
+                answering.url.clone(),
+            ],
+            timeout: Some(timeout),
+            ..Default::default()
+        };
+        let preferred = Arc::new(AtomicUsize::new(0));
+        let client = FallbackClient::connect(&upstream, preferred.clone(), Default::default())
+            .await
+            .unwrap();
+        let chain_id: String = client.request("eth_chainId", ()).await.unwrap();
+        assert_eq!(chain_id, "0x1");
+        assert_eq!(preferred.load(Ordering::Relaxed), 1);
+
+        let upstream = Upstream {
+            urls: vec![upstream.urls[0].clone()],
+            ..upstream
+        };
+        let client = FallbackClient::connect(&upstream, Default::default(), Default::default())
+            .await
+            .unwrap();
+        assert!(matches!(
+            client.request::<_, String>("eth_chainId", ()).await,
+            Err(FallbackError::Timeout(t)) if t == timeout
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_fallbacks_need_an_rpc() {
+        let mut forked_evm = evm_with_code(&[]).await;
+        assert!(matches!(
+            forked_evm
+                .add_or_select_with_fallbacks(&[], None, None)
+                .await,
+            Err(ForkerError::NoFallbackRpcs)
+        ));
+        assert!(matches!(
+            FallbackClient::connect(
+                &Upstream {
+                    urls: vec!["ftp://rpc.invalid".to_string()],
+                    ..Default::default()
+                },
+                Default::default(),
+                Default::default()
+            )
+            .await,
+            Err(ForkerError::InvalidRpcUrl(_))
+        ));
+    }
+}
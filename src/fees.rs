@@ -0,0 +1,120 @@
+use crate::{ForkedEvm, ForkerError};
+use alloy_rpc_types::BlockTransactions;
+use revm::primitives::{Env, U256};
+
+/// Blocks sampled by [ForkedEvm::suggest_fees], ending at the pinned block.
+const FEE_HISTORY_BLOCKS: u64 = 5;
+
+/// EIP-1559 fee suggestion for a transaction in the block after the fork's.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FeeSuggestion {
+    pub base_fee: U256,
+    pub max_fee_per_gas: U256,
+    pub max_priority_fee_per_gas: U256,
+}
+
+impl FeeSuggestion {
+    pub(crate) fn apply(&self, env: &mut Env) {
+        env.tx.gas_price = self.max_fee_per_gas;
+        env.tx.gas_priority_fee = Some(self.max_priority_fee_per_gas);
+    }
+}
+
+impl ForkedEvm {
+    /// Suggests fees from the priority fees paid in the fork's recent
+    /// blocks, leaving room for the base fee to double like most wallets.
+    pub fn suggest_fees(&self) -> Result<FeeSuggestion, ForkerError> {
+        let latest: u64 = self.executor.env.block.number.saturating_to();
+        let mut block_medians = vec![];
+        let mut base_fee = self.executor.env.block.basefee;
+        for block_number in latest.saturating_sub(FEE_HISTORY_BLOCKS - 1)..=latest {
+            let block = self.get_block(block_number)?;
+            let block_base_fee = block.header.base_fee_per_gas.unwrap_or_default();
+            if block_number == latest {
+                base_fee = block_base_fee;
+            }
+            let BlockTransactions::Full(transactions) = block.transactions else {
+                continue;
+            };
+            let mut priority_fees: Vec<U256> = transactions
+                .iter()
+                .filter_map(|tx| {
+                    let max_fee = tx.max_fee_per_gas.or(tx.gas_price)?;
+                    let priority_fee = max_fee.saturating_sub(block_base_fee);
+                    Some(
+                        tx.max_priority_fee_per_gas
+                            .map_or(priority_fee, |max_priority| max_priority.min(priority_fee)),
+                    )
+                })
+                .collect();
+            if priority_fees.is_empty() {
+                continue;
+            }
+            priority_fees.sort();
+            block_medians.push(priority_fees[priority_fees.len() / 2]);
+        }
+        block_medians.sort();
+        let max_priority_fee_per_gas = block_medians
+            .get(block_medians.len() / 2)
+            .copied()
+            .unwrap_or_default();
+        Ok(FeeSuggestion {
+            base_fee,
+            max_fee_per_gas: base_fee * U256::from(2) + max_priority_fee_per_gas,
+            max_priority_fee_per_gas,
+        })
+    }
+
+    /// Fees applied to writes that don't set a gas price through
+    /// [crate::CallOptions], e.g. from [ForkedEvm::suggest_fees], so writes
+    /// pay what an includable transaction would.
+    pub fn set_auto_fees(&mut self, fees: Option<FeeSuggestion>) {
+        self.auto_fees = fees;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::FeeSuggestion;
+    use crate::{testing::evm_with_code, CallOptions, ForkerError};
+    use revm::primitives::{AccountInfo, Address, U256};
+
+    #[tokio::test]
+    async fn test_auto_fees_apply_to_writes_without_a_gas_price() {
+        let sender = Address::repeat_byte(0x11);
+        let target = Address::repeat_byte(0x22);
+        let mut forked_evm = evm_with_code(&[(target, "00")]).await;
+        assert!(matches!(
+            forked_evm.suggest_fees(),
+            Err(ForkerError::NoActiveFork)
+        ));
+        forked_evm.executor.backend.insert_account_info(
+            sender,
+            AccountInfo {
+                balance: U256::from(10).pow(U256::from(18)),
+                ..Default::default()
+            },
+        );
+        forked_evm.set_auto_fees(Some(FeeSuggestion {
+            base_fee: U256::ZERO,
+            max_fee_per_gas: U256::from(7),
+            max_priority_fee_per_gas: U256::from(2),
+        }));
+
+        let mut opts = CallOptions {
+            gas_limit: Some(100_000),
+            ..Default::default()
+        };
+        let result = forked_evm
+            .write_with_opts(sender.as_slice(), target.as_slice(), &[], U256::ZERO, &opts)
+            .unwrap();
+        assert_eq!(result.env.tx.gas_price, U256::from(7));
+        assert_eq!(result.env.tx.gas_priority_fee, Some(U256::from(2)));
+
+        opts.gas_price = Some(U256::from(3));
+        let result = forked_evm
+            .write_with_opts(sender.as_slice(), target.as_slice(), &[], U256::ZERO, &opts)
+            .unwrap();
+        assert_eq!(result.env.tx.gas_price, U256::from(3));
+    }
+}
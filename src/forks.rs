@@ -0,0 +1,921 @@
+use crate::{cache::CacheConfig, overrides::ModifiedAccount, ForkedEvm, ForkerError};
+use alloy_rpc_types::{BlockNumberOrTag, Header};
+use alloy_sol_types::SolCall;
+use foundry_common::provider::alloy::{ProviderBuilder, RetryProvider};
+use foundry_config::Config;
+use foundry_evm::{
+    backend::DatabaseExt,
+    executors::RawCallResult,
+    fork::{BlockchainDb, BlockchainDbMeta, CreateFork, ForkId, SharedBackend},
+    opts::EvmOpts,
+};
+use revm::{
+    db::{AccountState, CacheDB},
+    primitives::{Address, Env, SpecId, U256},
+    JournaledState,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::Arc,
+    time::Duration,
+};
+
+/// Bookkeeping for a fork created through [ForkedEvm::add_or_select].
+#[derive(Clone)]
+pub struct ForkInfo {
+    pub url: String,
+    pub block_number: Option<u64>,
+    /// Block the fork actually resolved to, which for forks created without
+    /// a block number is the chain tip at creation or last refresh.
+    pub pinned_block: u64,
+    pub local_id: U256,
+    pub env: Env,
+    /// Hardfork set explicitly for the fork, `None` while it is detected
+    /// from its chain and block on every roll.
+    pub spec_id: Option<SpecId>,
+    /// How the fork's backend talks to its RPC, see [ForkedEvm::set_rpc_config].
+    pub rpc_config: RpcConfig,
+    /// Relay the fork's RPC traffic goes through, e.g. to fail over between
+    /// several upstream RPCs.
+    #[cfg(feature = "native")]
+    pub(crate) relay: Option<crate::transport::RpcRelay>,
+    pub(crate) db: Option<SharedBackend>,
+    pub(crate) last_used: u64,
+    /// Set while the fork's backend is dropped under the memory budget, it is
+    /// rebuilt from the disk cache when the fork is selected again.
+    pub(crate) evicted: bool,
+}
+
+/// Approximate in-memory footprint of one fork's fetched RPC state.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ForkMemoryUsage {
+    pub url: String,
+    pub block_number: Option<u64>,
+    pub active: bool,
+    pub accounts: usize,
+    pub slots: usize,
+    pub bytes: usize,
+}
+
+/// How a fork's backend talks to its RPC, set on the evm for forks created
+/// afterwards.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RpcConfig {
+    /// Deadline of each request, after which it fails, or moves on to the
+    /// next fallback RPC, instead of stalling the call. Forks with a timeout
+    /// are served through a relay, so it needs the `native` feature.
+    pub timeout: Option<Duration>,
+    /// Retries of failed or rate limited requests, foundry's default when
+    /// unset.
+    pub max_retries: Option<u32>,
+    /// Wait before the first retry, growing exponentially on further ones.
+    pub initial_backoff: Option<Duration>,
+    /// Compute units per second the provider allows, which the backend's
+    /// retries budget for when it rate limits.
+    pub compute_units_per_second: Option<u64>,
+    /// Client side cap on requests per second to the RPC, one budget shared
+    /// by all forks of the same url, e.g. to keep bulk prefetches from
+    /// getting an api key banned. Forks with a cap are served through a
+    /// relay, so it needs the `native` feature.
+    pub requests_per_second: Option<u32>,
+    /// See [ForkedEvm::set_cache_config].
+    #[serde(default)]
+    pub cache: CacheConfig,
+}
+
+impl RpcConfig {
+    fn backoff_millis(&self) -> Option<u64> {
+        self.initial_backoff
+            .map(|backoff| u64::try_from(backoff.as_millis()).unwrap_or(u64::MAX))
+    }
+}
+
+impl ForkedEvm {
+    // fork urls often embed api keys, so spans carry blocks and local ids only
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "debug", skip_all, fields(block = ?fork_block_number))
+    )]
+    pub(crate) async fn create_fork_opts(
+        fork_url: &str,
+        fork_block_number: Option<u64>,
+        rpc_config: &RpcConfig,
+    ) -> Result<CreateFork, ForkerError> {
+        let evm_opts = EvmOpts {
+            fork_url: Some(fork_url.to_string()),
+            fork_block_number,
+            fork_retries: rpc_config.max_retries,
+            fork_retry_backoff: rpc_config.backoff_millis(),
+            compute_units_per_second: rpc_config.compute_units_per_second,
+            env: foundry_evm::opts::Env {
+                chain_id: None,
+                code_size_limit: None,
+                // gas_price: Some(100),
+                gas_limit: u64::MAX,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        Ok(CreateFork {
+            url: fork_url.to_string(),
+            enable_caching: rpc_config.cache.default_location(),
+            env: evm_opts
+                .fork_evm_env(fork_url)
+                .await
+                .map_err(|e| ForkerError::rpc(fork_url, e))?
+                .0,
+            evm_opts,
+        })
+    }
+
+    /// Selects the fork for `fork_url` at `fork_block_number`, creating it if
+    /// it doesn't exist yet. Local state of previously selected forks is kept.
+    /// Besides http(s), `fork_url` can be a ws(s) or `ipc://` url or a path
+    /// to an `.ipc` socket, which are served through a local relay whose url
+    /// stands in for `fork_url` in [ForkedEvm::forks].
+    pub async fn add_or_select(
+        &mut self,
+        fork_url: &str,
+        fork_block_number: Option<u64>,
+        env: Option<Env>,
+    ) -> Result<(), ForkerError> {
+        self.add_or_select_with_spec(fork_url, fork_block_number, env, None)
+            .await
+    }
+
+    /// Like [ForkedEvm::add_or_select] with the fork's hardfork set to
+    /// `spec_id`, or detected from its chain and block when creating it
+    /// without one.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "debug", skip_all, fields(block = ?fork_block_number))
+    )]
+    pub async fn add_or_select_with_spec(
+        &mut self,
+        fork_url: &str,
+        fork_block_number: Option<u64>,
+        env: Option<Env>,
+        spec_id: Option<SpecId>,
+    ) -> Result<(), ForkerError> {
+        #[cfg(feature = "native")]
+        let fork_url = &self.serving_url(fork_url).await?;
+        let fork_id = ForkId::new(fork_url, fork_block_number);
+        if let Some(local_id) = self.forks.get(&fork_id).map(|info| info.local_id) {
+            self.select_local_fork(local_id)?;
+            if let Some(env) = env {
+                self.executor.env = env;
+            }
+            if let Some(spec_id) = spec_id {
+                self.set_spec_id(spec_id)?;
+            }
+        } else {
+            let fork_opts =
+                Self::create_fork_opts(fork_url, fork_block_number, &self.rpc_config).await?;
+            self.executor.env = env.unwrap_or(fork_opts.env.clone());
+            let mut journaled_state = JournaledState::new(SpecId::LATEST, vec![]);
+            let executor = &mut *self.executor;
+            let local_id = executor
+                .backend
+                .create_select_fork(fork_opts, &mut executor.env, &mut journaled_state)
+                .map_err(|e| ForkerError::rpc(fork_url, e))?;
+            self.executor.env.cfg.spec_id = spec_id.unwrap_or_else(|| self.detect_spec_id());
+            self.track_fork(fork_id.clone(), fork_url, fork_block_number, local_id);
+            self.load_relocated_cache()?;
+            self.touch_fork(&fork_id);
+            if let Some(info) = self.forks.get_mut(&fork_id) {
+                info.spec_id = spec_id;
+            }
+        }
+        self.enforce_memory_budget()
+    }
+
+    /// Selects a fork known to this evm, e.g. one listed by
+    /// [ForkedEvm::forks] or returned by [ForkedEvm::fork_from_current].
+    pub fn select(&mut self, fork_id: &ForkId) -> Result<(), ForkerError> {
+        let local_id = self
+            .forks
+            .get(fork_id)
+            .map(|info| info.local_id)
+            .ok_or_else(|| ForkerError::UnknownFork(fork_id.clone()))?;
+        self.select_local_fork(local_id)?;
+        self.enforce_memory_budget()
+    }
+
+    /// Branches the active fork: creates and selects a new fork at the same
+    /// block seeded with the active fork's locally modified state, so both
+    /// can diverge independently. Returns the branch's id to select it by.
+    pub async fn fork_from_current(&mut self) -> Result<ForkId, ForkerError> {
+        let (url, pinned_block, rpc_config) = self
+            .active_fork()
+            .map(|info| (info.url.clone(), info.pinned_block, info.rpc_config.clone()))
+            .ok_or(ForkerError::NoActiveFork)?;
+        let local_writes = self.modified_accounts();
+
+        let fork_opts = Self::create_fork_opts(&url, Some(pinned_block), &rpc_config).await?;
+        let mut env = self.executor.env.clone();
+        let mut journaled_state = JournaledState::new(SpecId::LATEST, vec![]);
+        let local_id = self
+            .executor
+            .backend
+            .create_select_fork(fork_opts, &mut env, &mut journaled_state)
+            .map_err(|e| ForkerError::rpc(&url, e))?;
+        self.executor.env = env;
+        self.apply_modified_accounts(local_writes)?;
+
+        // the backend keys forks by url and block, branches of the same
+        // block need their own key here
+        let branch = self
+            .forks
+            .values()
+            .filter(|info| info.url == url && info.pinned_block == pinned_block)
+            .count();
+        let fork_id = ForkId(format!(
+            "{}#branch-{branch}",
+            ForkId::new(&url, Some(pinned_block)).0
+        ));
+        self.track_fork(fork_id.clone(), &url, Some(pinned_block), local_id);
+        self.touch_fork(&fork_id);
+        self.enforce_memory_budget()?;
+        Ok(fork_id)
+    }
+
+    /// Runs a read against the active fork's chain as of `block_number`,
+    /// creating a fork pinned to that block on first use and reusing it
+    /// afterwards. The previously active fork is selected again before
+    /// returning.
+    pub async fn call_at_block(
+        &mut self,
+        block_number: u64,
+        from_address: &[u8],
+        to_address: &[u8],
+        calldata: &[u8],
+    ) -> Result<RawCallResult, ForkerError> {
+        let (url, previous) = self
+            .active_fork()
+            .map(|info| (info.url.clone(), info.local_id))
+            .ok_or(ForkerError::NoActiveFork)?;
+        self.add_or_select(&url, Some(block_number), None).await?;
+        let result = self.call(from_address, to_address, calldata);
+        // by local id, as other forks may share the url and block
+        self.select_local_fork(previous)?;
+        result
+    }
+
+    /// Runs the same read on every known fork, e.g. to compare an oracle
+    /// across chains. Failures are reported per fork, and the previously
+    /// active fork is selected again before returning.
+    pub fn call_all_forks(
+        &mut self,
+        from_address: &[u8],
+        to_address: &[u8],
+        calldata: &[u8],
+    ) -> Result<HashMap<ForkId, Result<RawCallResult, ForkerError>>, ForkerError> {
+        let previous = self.executor.backend.active_fork_id();
+        let forks: Vec<_> = self
+            .forks
+            .iter()
+            .map(|(fork_id, info)| (fork_id.clone(), info.local_id))
+            .collect();
+        let mut results = HashMap::with_capacity(forks.len());
+        for (fork_id, local_id) in forks {
+            let result = self
+                .select_local_fork(local_id)
+                .and_then(|_| self.call(from_address, to_address, calldata));
+            results.insert(fork_id, result);
+        }
+        if let Some(previous) = previous {
+            self.select_local_fork(previous)?;
+        }
+        Ok(results)
+    }
+
+    /// Typed [ForkedEvm::call_all_forks], decoding each fork's return with
+    /// the call's abi.
+    pub fn alloy_call_all_forks<C: SolCall>(
+        &mut self,
+        from_address: &[u8],
+        to_address: &[u8],
+        call: C,
+    ) -> Result<HashMap<ForkId, Result<C::Return, ForkerError>>, ForkerError> {
+        Ok(self
+            .call_all_forks(from_address, to_address, &call.abi_encode())?
+            .into_iter()
+            .map(|(fork_id, result)| {
+                let typed_return = result.and_then(|result| {
+                    if result.reverted {
+                        return Err(ForkerError::reverted(&result));
+                    }
+                    Ok(C::abi_decode_returns(&result.result, true)?)
+                });
+                (fork_id, typed_return)
+            })
+            .collect())
+    }
+
+    /// Selects an already created fork by its local id, rebuilding its
+    /// backend if it was evicted.
+    pub(crate) fn select_local_fork(&mut self, local_id: U256) -> Result<(), ForkerError> {
+        self.bump_state_version();
+        #[cfg(feature = "tracing")]
+        tracing::debug!(%local_id, "selecting fork");
+        let mut journaled_state = JournaledState::new(SpecId::LATEST, vec![]);
+        let executor = &mut *self.executor;
+        executor
+            .backend
+            .select_fork(local_id, &mut executor.env, &mut journaled_state)
+            .map_err(|e| ForkerError::fork_select(local_id, e))?;
+        let Some((fork_id, info)) = self
+            .forks
+            .iter()
+            .find(|(_, info)| info.local_id == local_id)
+        else {
+            return Ok(());
+        };
+        let (fork_id, evicted) = (fork_id.clone(), info.evicted);
+        self.executor.env.cfg.spec_id = info.env.cfg.spec_id;
+        if evicted {
+            self.restore_evicted_fork(&fork_id)?;
+        }
+        self.touch_fork(&fork_id);
+        Ok(())
+    }
+
+    /// Block the active fork is pinned to.
+    pub fn pinned_block(&self) -> Option<u64> {
+        self.active_fork().map(|info| info.pinned_block)
+    }
+
+    /// Re-pins the active fork to the chain's current tip. Local writes are
+    /// discarded by the roll unless `preserve_local_writes` is set, in which
+    /// case the locally modified fields and slots are re-applied on top of
+    /// the new tip. State that was only read is fetched at the new tip.
+    pub fn refresh_to_latest(&mut self, preserve_local_writes: bool) -> Result<u64, ForkerError> {
+        let url = self
+            .active_fork()
+            .map(|info| info.url.clone())
+            .ok_or(ForkerError::NoActiveFork)?;
+        let latest = self
+            .get_block(BlockNumberOrTag::Latest)?
+            .header
+            .number
+            .ok_or_else(|| ForkerError::rpc(&url, "latest block has no number"))?
+            .to();
+        self.roll_to_block(latest, preserve_local_writes)?;
+        Ok(latest)
+    }
+
+    /// Re-pins the active fork to `block_number` and takes the block env from
+    /// its header, with the same handling of local writes as
+    /// [ForkedEvm::refresh_to_latest].
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip(self)))]
+    pub fn roll_to_block(
+        &mut self,
+        block_number: u64,
+        preserve_local_writes: bool,
+    ) -> Result<(), ForkerError> {
+        let (local_id, url) = self
+            .active_fork()
+            .map(|info| (info.local_id, info.url.clone()))
+            .ok_or(ForkerError::NoActiveFork)?;
+        self.bump_state_version();
+        // only what was written locally, accounts and slots that were just
+        // read come from the new block
+        let local_writes = if preserve_local_writes {
+            self.modified_accounts()
+        } else {
+            Default::default()
+        };
+
+        let mut journaled_state = JournaledState::new(SpecId::LATEST, vec![]);
+        let executor = &mut *self.executor;
+        executor
+            .backend
+            .roll_fork(
+                Some(local_id),
+                U256::from(block_number),
+                &mut executor.env,
+                &mut journaled_state,
+            )
+            .map_err(|e| ForkerError::rpc(&url, e))?;
+        let header = self.get_block(block_number)?.header;
+        self.apply_block_header(&header);
+
+        self.apply_modified_accounts(local_writes)?;
+
+        let env = self.executor.env.clone();
+        let db = self
+            .executor
+            .backend
+            .active_fork_db()
+            .map(|fork_db| fork_db.db.clone());
+        if let Some(info) = self
+            .forks
+            .values_mut()
+            .find(|info| info.local_id == local_id)
+        {
+            info.pinned_block = block_number;
+            info.env = env;
+            info.db = db;
+        }
+        Ok(())
+    }
+
+    /// Takes the block env from `header`, as the block the fork sits at.
+    pub(crate) fn apply_block_header(&mut self, header: &Header) {
+        let block = &mut self.executor.env.block;
+        if let Some(number) = header.number {
+            block.number = number;
+        }
+        block.timestamp = header.timestamp;
+        block.coinbase = header.miner;
+        block.gas_limit = header.gas_limit;
+        block.difficulty = header.difficulty;
+        block.prevrandao = header.mix_hash;
+        if let Some(basefee) = header.base_fee_per_gas {
+            block.basefee = basefee;
+        }
+        self.executor.env.cfg.spec_id = self
+            .active_fork()
+            .and_then(|info| info.spec_id)
+            .unwrap_or_else(|| self.detect_spec_id());
+    }
+
+    /// Applies `accounts` as returned by [ForkedEvm::modified_accounts] on
+    /// top of the active fork's current state, leaving unchanged fields and
+    /// slots to the fork.
+    pub(crate) fn apply_modified_accounts(
+        &mut self,
+        accounts: BTreeMap<Address, ModifiedAccount>,
+    ) -> Result<(), ForkerError> {
+        for (address, account) in accounts {
+            let balance = match account.balance {
+                Some(balance) => balance,
+                None => self.balance_of(address)?,
+            };
+            let nonce = match account.nonce {
+                Some(nonce) => nonce,
+                None => self.nonce_of(address)?,
+            };
+            let code = match account.code {
+                Some(code) => code,
+                None => self.code_at(address)?,
+            };
+            self.insert_account(address, balance, nonce, code, account.slots)?;
+            if account.storage_cleared {
+                if let Some(fork) = self.executor.backend.active_fork_mut() {
+                    if let Some(db_account) = fork.db.accounts.get_mut(&address) {
+                        db_account.account_state = AccountState::StorageCleared;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Provider for requests the fork backend can't make, e.g. `eth_call`
+    /// at the pinned block, talking to the active fork's RPC the way its
+    /// backend does.
+    #[cfg(feature = "native")]
+    pub(crate) fn fork_provider(&self) -> Result<RetryProvider, ForkerError> {
+        let info = self.active_fork().ok_or(ForkerError::NoActiveFork)?;
+        build_provider(&info.url, &info.rpc_config)
+    }
+
+    /// Independent copy of this evm, e.g. to explore scenarios from a common
+    /// base in parallel threads. The copy shares the local state with this
+    /// evm until either side writes, when the writer copies it, and keeps
+    /// sharing the forks' RPC caches and memoized reads. Event subscriptions
+    /// stay with this evm.
+    pub fn fork_clone(&self) -> ForkedEvm {
+        self.clone()
+    }
+
+    /// Discards every local change to the active fork, restoring the state
+    /// and block env it was pinned at. Remote state already fetched stays
+    /// cached, so nothing is downloaded again.
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip(self)))]
+    pub fn reset_fork(&mut self) -> Result<(), ForkerError> {
+        let info = self.active_fork().ok_or(ForkerError::NoActiveFork)?;
+        let (local_id, env) = (info.local_id, info.env.clone());
+        // a fresh local layer over the same rpc cache, so accounts created
+        // and storage cleared locally are gone too
+        let fork = self
+            .executor
+            .backend
+            .active_fork_mut()
+            .ok_or(ForkerError::NoActiveFork)?;
+        fork.db = CacheDB::new(fork.db.db.clone());
+        self.bump_state_version();
+        self.local_logs
+            .retain(|local_log| local_log.fork_id != Some(local_id));
+        self.executor.env = env;
+        Ok(())
+    }
+
+    /// Bookkeeping of the currently selected fork.
+    pub fn active_fork(&self) -> Option<&ForkInfo> {
+        let active = self.executor.backend.active_fork_id()?;
+        self.forks.values().find(|info| info.local_id == active)
+    }
+
+    pub(crate) fn active_fork_mut(&mut self) -> Option<&mut ForkInfo> {
+        let active = self.executor.backend.active_fork_id()?;
+        self.forks.values_mut().find(|info| info.local_id == active)
+    }
+
+    /// Forks known to this evm, keyed by url and block.
+    pub fn forks(&self) -> impl Iterator<Item = (&ForkId, &ForkInfo)> {
+        self.forks.iter()
+    }
+
+    pub(crate) fn track_fork(
+        &mut self,
+        fork_id: ForkId,
+        fork_url: &str,
+        fork_block_number: Option<u64>,
+        local_id: U256,
+    ) {
+        let db = self
+            .executor
+            .backend
+            .active_fork_db()
+            .map(|fork_db| fork_db.db.clone());
+        let pinned_block: u64 = self.executor.env.block.number.to();
+        #[cfg(feature = "native")]
+        if let Some(db) = &db {
+            crate::block_hash::seed_in_background(db.clone(), pinned_block);
+        }
+        // forks of a relay url, e.g. branches and other blocks of a relayed
+        // fork, are served by that relay
+        #[cfg(feature = "native")]
+        let relay = self
+            .rpc_relays
+            .values()
+            .find(|relay| relay.url == fork_url)
+            .cloned();
+        self.forks.insert(
+            fork_id,
+            ForkInfo {
+                url: fork_url.to_string(),
+                block_number: fork_block_number,
+                pinned_block,
+                local_id,
+                env: self.executor.env.clone(),
+                spec_id: None,
+                rpc_config: self.rpc_config.clone(),
+                #[cfg(feature = "native")]
+                relay,
+                db,
+                last_used: 0,
+                evicted: false,
+            },
+        );
+        #[cfg(feature = "tracing")]
+        tracing::debug!(
+            %local_id,
+            pinned_block = %self.executor.env.block.number,
+            forks = self.forks.len(),
+            "created fork"
+        );
+        self.report_fork_count();
+    }
+
+    fn touch_fork(&mut self, fork_id: &ForkId) {
+        self.fork_clock += 1;
+        if let Some(info) = self.forks.get_mut(fork_id) {
+            info.last_used = self.fork_clock;
+        }
+    }
+
+    /// Caps the memory held by the forks' fetched RPC state. When exceeded,
+    /// the least recently selected inactive forks are flushed to the disk
+    /// cache and their backends dropped. An evicted fork is rebuilt from the
+    /// disk cache the next time it is selected, its local state is kept.
+    pub fn set_memory_budget(&mut self, bytes: Option<usize>) -> Result<(), ForkerError> {
+        self.memory_budget = bytes;
+        self.enforce_memory_budget()
+    }
+
+    pub fn rpc_config(&self) -> &RpcConfig {
+        &self.rpc_config
+    }
+
+    /// Sets how forks created from here on talk to their RPC, including
+    /// branches of existing forks. Existing forks keep their settings.
+    pub fn set_rpc_config(&mut self, rpc_config: RpcConfig) {
+        self.rpc_config = rpc_config;
+    }
+
+    pub fn memory_usage(&self) -> Vec<ForkMemoryUsage> {
+        let active = self.executor.backend.active_fork_id();
+        self.forks
+            .values()
+            .map(|info| {
+                let (accounts, slots, bytes) = info.db.as_ref().map(db_usage).unwrap_or_default();
+                ForkMemoryUsage {
+                    url: info.url.clone(),
+                    block_number: info.block_number,
+                    active: Some(info.local_id) == active,
+                    accounts,
+                    slots,
+                    bytes,
+                }
+            })
+            .collect()
+    }
+
+    pub(crate) fn enforce_memory_budget(&mut self) -> Result<(), ForkerError> {
+        let Some(budget) = self.memory_budget else {
+            return Ok(());
+        };
+        let mut total: usize = self.memory_usage().iter().map(|usage| usage.bytes).sum();
+        if total <= budget {
+            return Ok(());
+        }
+        let active = self.executor.backend.active_fork_id();
+        let mut inactive: Vec<_> = self
+            .forks
+            .iter()
+            .filter(|(_, info)| Some(info.local_id) != active && info.db.is_some())
+            .map(|(fork_id, info)| (info.last_used, fork_id.clone()))
+            .collect();
+        inactive.sort_by_key(|(last_used, _)| *last_used);
+        for (_, fork_id) in inactive {
+            if total <= budget {
+                break;
+            }
+            total = total.saturating_sub(self.evict_fork(&fork_id)?);
+        }
+        Ok(())
+    }
+
+    /// Flushes an inactive fork's fetched state to the disk cache and swaps
+    /// its backend for an empty one, so the fetched state is released
+    /// without touching the maps other handles share. Returns the estimated
+    /// bytes released.
+    fn evict_fork(&mut self, fork_id: &ForkId) -> Result<usize, ForkerError> {
+        let Some(info) = self.forks.get(fork_id) else {
+            return Ok(0);
+        };
+        let Some(db) = info.db.as_ref() else {
+            return Ok(0);
+        };
+        let released = db_usage(db).2;
+        db.flush_cache();
+        let local_id = info.local_id;
+        let empty = spawn_fork_backend(info, false)?;
+        self.replace_fork_backend(local_id, empty)?;
+        if let Some(info) = self.forks.get_mut(fork_id) {
+            info.db = None;
+            info.evicted = true;
+        }
+        Ok(released)
+    }
+
+    /// Rebuilds the backend of the active, previously evicted fork from the
+    /// disk cache.
+    fn restore_evicted_fork(&mut self, fork_id: &ForkId) -> Result<(), ForkerError> {
+        let Some(info) = self.forks.get(fork_id) else {
+            return Ok(());
+        };
+        let backend = spawn_fork_backend(info, true)?;
+        let local_id = info.local_id;
+        self.replace_fork_backend(local_id, backend.clone())?;
+        if let Some(info) = self.forks.get_mut(fork_id) {
+            info.db = Some(backend);
+            info.evicted = false;
+        }
+        Ok(())
+    }
+
+    /// Swaps the rpc backend under the local layer of fork `local_id`, which
+    /// is selected for the swap if needed and the previous selection
+    /// restored afterwards.
+    pub(crate) fn replace_fork_backend(
+        &mut self,
+        local_id: U256,
+        backend: SharedBackend,
+    ) -> Result<(), ForkerError> {
+        let previous = self.executor.backend.active_fork_id();
+        let env = self.executor.env.clone();
+        let mut journaled_state = JournaledState::new(SpecId::LATEST, vec![]);
+        if previous != Some(local_id) {
+            let executor = &mut *self.executor;
+            executor
+                .backend
+                .select_fork(local_id, &mut executor.env, &mut journaled_state)
+                .map_err(|e| ForkerError::fork_select(local_id, e))?;
+        }
+        if let Some(fork) = self.executor.backend.active_fork_mut() {
+            fork.db.db = backend;
+        }
+        if let Some(previous) = previous.filter(|previous| *previous != local_id) {
+            let executor = &mut *self.executor;
+            executor
+                .backend
+                .select_fork(previous, &mut executor.env, &mut journaled_state)
+                .map_err(|e| ForkerError::fork_select(previous, e))?;
+        }
+        self.executor.env = env;
+        Ok(())
+    }
+}
+
+/// Spawns a fresh rpc backend for a tracked fork, loading what the fork
+/// flushed to the disk cache when `from_disk_cache` is set. Forks without
+/// foundry's cache, see [crate::cache::CacheConfig], start empty and
+/// refetch.
+pub(crate) fn spawn_fork_backend(
+    info: &ForkInfo,
+    from_disk_cache: bool,
+) -> Result<SharedBackend, ForkerError> {
+    let cache_path = if from_disk_cache && info.rpc_config.cache.default_location() {
+        u64::try_from(info.env.block.number)
+            .ok()
+            .and_then(|block| Config::foundry_block_cache_file(info.env.cfg.chain_id, block))
+    } else {
+        None
+    };
+    let db = BlockchainDb::new(
+        BlockchainDbMeta::new(info.env.clone(), info.url.clone()),
+        cache_path,
+    );
+    let provider = build_provider(&info.url, &info.rpc_config)?;
+    Ok(SharedBackend::spawn_backend_thread(
+        Arc::new(provider),
+        db,
+        info.block_number.map(Into::into),
+    ))
+}
+
+/// Provider talking to `url` the way fork backends do.
+fn build_provider(url: &str, rpc_config: &RpcConfig) -> Result<RetryProvider, ForkerError> {
+    ProviderBuilder::new(url)
+        .maybe_max_retry(rpc_config.max_retries)
+        .maybe_initial_backoff(rpc_config.backoff_millis())
+        .compute_units_per_second_opt(rpc_config.compute_units_per_second)
+        .build()
+        .map_err(|e| ForkerError::rpc(url, e))
+}
+
+/// Estimated (accounts, slots, bytes) held in memory by a fork's backend.
+fn db_usage(db: &SharedBackend) -> (usize, usize, usize) {
+    let data = db.data();
+    let accounts = data.accounts.read();
+    let storage = data.storage.read();
+    let slots: usize = storage.values().map(|slots| slots.len()).sum();
+    let code: usize = accounts
+        .values()
+        .map(|info| info.code.as_ref().map_or(0, |code| code.len()))
+        .sum();
+    let bytes = accounts.len() * std::mem::size_of::<revm::primitives::AccountInfo>()
+        + slots * 2 * std::mem::size_of::<U256>()
+        + code;
+    (accounts.len(), slots, bytes)
+}
+
+/// Seeds `to` with the state `from` fetched, e.g. for a backend of the same
+/// fork served by another RPC.
+pub(crate) fn copy_fetched_state(from: &SharedBackend, to: &SharedBackend) {
+    let (from, to) = (from.data(), to.data());
+    to.accounts.write().extend(from.accounts.read().clone());
+    to.storage.write().extend(from.storage.read().clone());
+    to.block_hashes
+        .write()
+        .extend(from.block_hashes.read().clone());
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{overrides::ModifiedAccount, testing::evm_with_code, ForkerError};
+    use alloy_rpc_types::Header;
+    use foundry_evm::fork::ForkId;
+    use revm::primitives::{Address, Bytes, SpecId, U256};
+    use std::collections::BTreeMap;
+
+    #[tokio::test]
+    async fn test_memory_budget_without_forks() {
+        let mut forked_evm = evm_with_code(&[]).await;
+        assert_eq!(forked_evm.forks().count(), 0);
+        assert!(forked_evm.memory_usage().is_empty());
+        forked_evm.set_memory_budget(Some(0)).unwrap();
+        forked_evm.set_memory_budget(None).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_call_at_block_requires_fork() {
+        let mut forked_evm = evm_with_code(&[]).await;
+        assert!(forked_evm.active_fork().is_none());
+        let result = forked_evm
+            .call_at_block(1, &[0u8; 20], &[0u8; 20], &[])
+            .await;
+        assert!(matches!(result, Err(ForkerError::NoActiveFork)));
+    }
+
+    #[tokio::test]
+    async fn test_call_all_forks_without_forks() {
+        let mut forked_evm = evm_with_code(&[]).await;
+        let results = forked_evm
+            .call_all_forks(&[0u8; 20], &[0u8; 20], &[])
+            .unwrap();
+        assert!(results.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_branching_requires_known_fork() {
+        let mut forked_evm = evm_with_code(&[]).await;
+        let fork_id = ForkId::new("http://localhost:8545", Some(1));
+        assert!(matches!(
+            forked_evm.select(&fork_id),
+            Err(ForkerError::UnknownFork(unknown)) if unknown == fork_id
+        ));
+        assert!(matches!(
+            forked_evm.fork_from_current().await,
+            Err(ForkerError::NoActiveFork)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_block_header_sets_the_spec() {
+        let mut forked_evm = evm_with_code(&[]).await;
+        forked_evm.executor.env.cfg.chain_id = 1;
+        let header = Header {
+            number: Some(U256::from(17_034_870)),
+            gas_limit: U256::from(30_000_000),
+            ..Default::default()
+        };
+        forked_evm.apply_block_header(&header);
+        assert_eq!(forked_evm.spec_id(), SpecId::SHANGHAI);
+        assert_eq!(forked_evm.executor.env.block.gas_limit, header.gas_limit);
+
+        let header = Header {
+            number: Some(U256::from(19_426_587)),
+            ..header
+        };
+        forked_evm.apply_block_header(&header);
+        assert_eq!(forked_evm.spec_id(), SpecId::CANCUN);
+    }
+
+    #[tokio::test]
+    async fn test_refresh_to_latest_requires_fork() {
+        let mut forked_evm = evm_with_code(&[]).await;
+        assert!(forked_evm.pinned_block().is_none());
+        assert!(matches!(
+            forked_evm.refresh_to_latest(true),
+            Err(ForkerError::NoActiveFork)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_reset_fork_requires_fork() {
+        let target = Address::repeat_byte(0x11);
+        let mut forked_evm = evm_with_code(&[(target, "00")]).await;
+        assert!(matches!(
+            forked_evm.reset_fork(),
+            Err(ForkerError::NoActiveFork)
+        ));
+        assert!(!forked_evm.code_at(target).unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_apply_modified_accounts_keeps_unchanged_fields() {
+        let address = Address::repeat_byte(0x11);
+        let mut forked_evm = evm_with_code(&[(address, "00")]).await;
+        forked_evm
+            .insert_account(address, U256::from(5), 1, Bytes::from_static(&[0x00]), [])
+            .unwrap();
+
+        let modified = ModifiedAccount {
+            nonce: Some(7),
+            slots: BTreeMap::from([(U256::from(1), U256::from(2))]),
+            ..Default::default()
+        };
+        forked_evm
+            .apply_modified_accounts(BTreeMap::from([(address, modified)]))
+            .unwrap();
+        assert_eq!(forked_evm.balance_of(address).unwrap(), U256::from(5));
+        assert_eq!(forked_evm.nonce_of(address).unwrap(), 7);
+        assert_eq!(forked_evm.code_at(address).unwrap().as_ref(), &[0x00]);
+        assert_eq!(
+            forked_evm.storage_at(address, U256::from(1)).unwrap(),
+            U256::from(2)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_fork_clone_copies_local_state_on_write() {
+        let address = Address::repeat_byte(0x11);
+        let forked_evm = evm_with_code(&[(address, "00")]).await;
+        let mut copy = forked_evm.fork_clone();
+        assert!(forked_evm.executor.is_shared());
+
+        copy.insert_account(address, U256::from(5), 1, Bytes::new(), [])
+            .unwrap();
+        assert!(!forked_evm.executor.is_shared());
+        assert_eq!(copy.balance_of(address).unwrap(), U256::from(5));
+        assert_eq!(forked_evm.balance_of(address).unwrap(), U256::ZERO);
+        assert!(!forked_evm.code_at(address).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,189 @@
+use crate::{ForkedEvm, ForkerError};
+use alloy_dyn_abi::{DynSolType, DynSolValue};
+use alloy_sol_types::SolCall;
+use proptest::{
+    prelude::*,
+    test_runner::{Config, TestCaseError, TestError, TestRunner},
+};
+use revm::primitives::{alloy_primitives::I256, Address, B256, U256};
+use std::cell::RefCell;
+
+/// Smallest input found to fail a fuzz test, with the failure.
+#[derive(Debug, Clone)]
+pub struct FuzzFailure<T> {
+    pub input: T,
+    pub reason: String,
+}
+
+pub fn any_address() -> impl Strategy<Value = Address> {
+    any::<[u8; 20]>().prop_map(Address::from)
+}
+
+pub fn any_u256() -> impl Strategy<Value = U256> {
+    any::<[u8; 32]>().prop_map(U256::from_be_bytes)
+}
+
+/// Amounts in `0..=max`.
+pub fn amount_up_to(max: U256) -> impl Strategy<Value = U256> {
+    any_u256().prop_map(move |amount| match max.checked_add(U256::from(1)) {
+        Some(bound) => amount % bound,
+        None => amount,
+    })
+}
+
+/// Random values of a solidity type.
+pub fn value_strategy(ty: &DynSolType) -> Result<BoxedStrategy<DynSolValue>, ForkerError> {
+    let strategy = match ty {
+        DynSolType::Address => any_address().prop_map(DynSolValue::Address).boxed(),
+        DynSolType::Bool => any::<bool>().prop_map(DynSolValue::Bool).boxed(),
+        DynSolType::Uint(bits) => {
+            let bits = *bits;
+            any_u256()
+                .prop_map(move |value| DynSolValue::Uint(mask(value, bits), bits))
+                .boxed()
+        }
+        DynSolType::Int(bits) => {
+            let bits = *bits;
+            any_u256()
+                .prop_map(move |value| {
+                    let mut value = mask(value, bits);
+                    if bits < 256 && value.bit(bits - 1) {
+                        value |= U256::MAX << bits;
+                    }
+                    DynSolValue::Int(I256::from_raw(value), bits)
+                })
+                .boxed()
+        }
+        DynSolType::FixedBytes(len) => {
+            let len = *len;
+            any::<[u8; 32]>()
+                .prop_map(move |mut bytes| {
+                    bytes[len..].fill(0);
+                    DynSolValue::FixedBytes(B256::from(bytes), len)
+                })
+                .boxed()
+        }
+        DynSolType::Bytes => proptest::collection::vec(any::<u8>(), 0..64)
+            .prop_map(DynSolValue::Bytes)
+            .boxed(),
+        DynSolType::String => ".{0,32}".prop_map(DynSolValue::String).boxed(),
+        DynSolType::Array(inner) => proptest::collection::vec(value_strategy(inner)?, 0..4)
+            .prop_map(DynSolValue::Array)
+            .boxed(),
+        DynSolType::FixedArray(inner, len) => {
+            proptest::collection::vec(value_strategy(inner)?, *len)
+                .prop_map(DynSolValue::FixedArray)
+                .boxed()
+        }
+        DynSolType::Tuple(types) => types
+            .iter()
+            .map(value_strategy)
+            .collect::<Result<Vec<_>, _>>()?
+            .prop_map(DynSolValue::Tuple)
+            .boxed(),
+        ty => return Err(ForkerError::UnsupportedFuzzType(ty.to_string())),
+    };
+    Ok(strategy)
+}
+
+/// Random inputs of a typed call, generated from its signature.
+pub fn sol_call_strategy<C: SolCall + std::fmt::Debug + 'static>(
+) -> Result<BoxedStrategy<C>, ForkerError> {
+    let params = &C::SIGNATURE[C::SIGNATURE.find('(').unwrap_or_default()..];
+    let ty = DynSolType::parse(params)
+        .map_err(|_| ForkerError::InvalidSignature(C::SIGNATURE.to_string()))?;
+    Ok(value_strategy(&ty)?
+        .prop_filter_map("inputs must decode as the call", |value| {
+            C::abi_decode_raw(&value.abi_encode_params(), true).ok()
+        })
+        .boxed())
+}
+
+impl ForkedEvm {
+    /// Runs `test` on `cases` random inputs, each against a snapshot of
+    /// the current state that is reverted afterwards. A failing input is
+    /// shrunk to the smallest one that still fails and returned, `None`
+    /// if every case passed.
+    pub fn fuzz<S: Strategy>(
+        &mut self,
+        cases: u32,
+        strategy: S,
+        test: impl Fn(&mut ForkedEvm, S::Value) -> Result<(), ForkerError>,
+    ) -> Result<Option<FuzzFailure<S::Value>>, ForkerError> {
+        let mut runner = TestRunner::new(Config {
+            cases,
+            ..Config::default()
+        });
+        let forked_evm = RefCell::new(self);
+        let outcome = runner.run(&strategy, |input| {
+            let mut forked_evm = forked_evm.borrow_mut();
+            let snapshot_id = forked_evm.snapshot();
+            let result = test(&mut forked_evm, input);
+            forked_evm.revert(snapshot_id);
+            result.map_err(|e| TestCaseError::fail(e.to_string()))
+        });
+        match outcome {
+            Ok(()) => Ok(None),
+            Err(TestError::Fail(reason, input)) => Ok(Some(FuzzFailure {
+                input,
+                reason: reason.to_string(),
+            })),
+            Err(TestError::Abort(reason)) => Err(ForkerError::FuzzAborted(reason.to_string())),
+        }
+    }
+}
+
+fn mask(value: U256, bits: usize) -> U256 {
+    if bits >= 256 {
+        value
+    } else {
+        value & ((U256::from(1) << bits) - U256::from(1))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{amount_up_to, value_strategy};
+    use crate::{testing::evm_with_code, ForkerError};
+    use alloy_dyn_abi::DynSolType;
+    use proptest::strategy::Strategy;
+    use revm::primitives::U256;
+
+    #[test]
+    fn test_value_strategy_rejects_unsupported_types() {
+        assert!(value_strategy(&DynSolType::parse("(uint8[],bytes32)").unwrap()).is_ok());
+        assert!(matches!(
+            value_strategy(&DynSolType::Function),
+            Err(ForkerError::UnsupportedFuzzType(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_fuzz_reports_a_failing_input() {
+        let mut forked_evm = evm_with_code(&[]).await;
+        let failure = forked_evm
+            .fuzz(256, amount_up_to(U256::from(1000)), |_, amount| {
+                if amount > U256::from(500) {
+                    return Err(ForkerError::InvalidTransaction(amount.to_string()));
+                }
+                Ok(())
+            })
+            .unwrap()
+            .unwrap();
+        assert!(failure.input > U256::from(500));
+        assert_eq!(
+            failure.reason,
+            format!("invalid transaction: {}", failure.input)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_fuzz_aborts_when_every_input_is_rejected() {
+        let mut forked_evm = evm_with_code(&[]).await;
+        let strategy = amount_up_to(U256::from(10)).prop_filter("never", |_| false);
+        assert!(matches!(
+            forked_evm.fuzz(16, strategy, |_, _| Ok(())),
+            Err(ForkerError::FuzzAborted(_))
+        ));
+    }
+}
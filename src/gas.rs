@@ -0,0 +1,112 @@
+use crate::{ForkCallResult, ForkedEvm, ForkerError};
+use foundry_evm::executors::RawCallResult;
+use revm::primitives::{Env, U256};
+
+/// Gas and wei a transaction pays, as a wallet or explorer would show it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GasBreakdown {
+    /// Gas charged after refunds.
+    pub used: u64,
+    /// Gas refunded, e.g. for clearing storage.
+    pub refunded: u64,
+    /// Wei paid for execution plus any L1 data fee.
+    pub effective_cost_wei: U256,
+    /// L1 data fee on L2s, `None` on chains without one.
+    pub l1_fee: Option<U256>,
+}
+
+impl GasBreakdown {
+    /// Execution cost of `result` at its effective gas price, without an
+    /// L1 data fee.
+    pub(crate) fn execution(result: &RawCallResult) -> Self {
+        Self {
+            used: result.gas_used,
+            refunded: result.gas_refunded,
+            effective_cost_wei: U256::from(result.gas_used) * effective_gas_price(&result.env),
+            l1_fee: None,
+        }
+    }
+}
+
+impl ForkedEvm {
+    /// Gas accounting of the transaction of `result`, priced at its
+    /// effective gas price, or as the L2 charges it, with the L1 data fee
+    /// on L2s.
+    pub fn gas_breakdown(&self, result: &RawCallResult) -> Result<GasBreakdown, ForkerError> {
+        let execution = GasBreakdown::execution(result);
+        Ok(match self.l2_fees(result)? {
+            Some(fees) => GasBreakdown {
+                effective_cost_wei: fees.total(),
+                l1_fee: Some(fees.l1_fee),
+                ..execution
+            },
+            None => execution,
+        })
+    }
+
+    /// Fills the gas breakdown of `result`, L1 data fee included.
+    pub(crate) fn with_gas(
+        &self,
+        mut result: ForkCallResult,
+    ) -> Result<ForkCallResult, ForkerError> {
+        result.gas = self.gas_breakdown(&result.raw)?;
+        Ok(result)
+    }
+}
+
+/// Gas price a tx pays per unit of gas, capping EIP-1559 fees at the base
+/// fee plus priority fee.
+pub(crate) fn effective_gas_price(env: &Env) -> U256 {
+    match env.tx.gas_priority_fee {
+        Some(priority_fee) => env.tx.gas_price.min(env.block.basefee + priority_fee),
+        None => env.tx.gas_price,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::effective_gas_price;
+    use crate::{testing::evm_with_code, CallOptions};
+    use revm::primitives::{Address, Env, U256};
+
+    #[test]
+    fn test_effective_gas_price_caps_at_basefee_plus_tip() {
+        let mut env = Env::default();
+        env.block.basefee = U256::from(10);
+        env.tx.gas_price = U256::from(30);
+        assert_eq!(effective_gas_price(&env), U256::from(30));
+        env.tx.gas_priority_fee = Some(U256::from(5));
+        assert_eq!(effective_gas_price(&env), U256::from(15));
+    }
+
+    #[tokio::test]
+    async fn test_detailed_results_carry_the_gas_breakdown() {
+        let sender = Address::repeat_byte(0x11);
+        let target = Address::repeat_byte(0x22);
+        // stores 1 at slot 0
+        let mut forked_evm = evm_with_code(&[(target, "6001600055")]).await;
+        forked_evm
+            .insert_account(
+                sender,
+                U256::from(10).pow(U256::from(18)),
+                0,
+                Default::default(),
+                [],
+            )
+            .unwrap();
+        let opts = CallOptions {
+            gas_price: Some(U256::from(2)),
+            ..Default::default()
+        };
+        let result = forked_evm
+            .write_detailed(sender.as_slice(), target.as_slice(), &[], U256::ZERO, &opts)
+            .unwrap();
+        assert!(result.gas.used > 21_000);
+        assert_eq!(result.gas.used, result.gas_used);
+        assert_eq!(
+            result.gas.effective_cost_wei,
+            U256::from(result.gas.used * 2)
+        );
+        assert_eq!(result.gas.l1_fee, None);
+    }
+}
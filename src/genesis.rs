@@ -0,0 +1,193 @@
+use crate::{ForkedEvm, ForkerError};
+use foundry_evm::backend::Backend;
+use revm::primitives::{alloy_primitives::U64, Address, Bytes, Env, B256, U256};
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, fs, path::Path};
+
+/// Chain id of [ForkedEvm::new_local] evms, the one anvil and hardhat use.
+pub const LOCAL_CHAIN_ID: u64 = 31337;
+
+/// The parts of a geth-style genesis file relevant to seeding evm state.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Genesis {
+    #[serde(default)]
+    pub config: GenesisConfig,
+    #[serde(default)]
+    pub timestamp: Option<U64>,
+    #[serde(default, rename = "gasLimit")]
+    pub gas_limit: Option<U64>,
+    #[serde(default)]
+    pub coinbase: Option<Address>,
+    pub alloc: BTreeMap<Address, GenesisAccount>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct GenesisConfig {
+    #[serde(default, rename = "chainId")]
+    pub chain_id: Option<u64>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct GenesisAccount {
+    #[serde(default)]
+    pub balance: U256,
+    #[serde(default)]
+    pub nonce: Option<U64>,
+    #[serde(default)]
+    pub code: Option<Bytes>,
+    #[serde(default)]
+    pub storage: Option<BTreeMap<B256, B256>>,
+}
+
+impl Genesis {
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Genesis, ForkerError> {
+        Ok(serde_json::from_slice(&fs::read(path)?)?)
+    }
+}
+
+impl ForkedEvm {
+    /// Builds an evm with no fork whose state is exactly the genesis alloc.
+    pub async fn from_genesis(
+        genesis: Genesis,
+        gas_limit: Option<u64>,
+    ) -> Result<ForkedEvm, ForkerError> {
+        let mut env = Env::default();
+        if let Some(chain_id) = genesis.config.chain_id {
+            env.cfg.chain_id = chain_id;
+        }
+        if let Some(timestamp) = genesis.timestamp {
+            env.block.timestamp = U256::from(timestamp);
+        }
+        if let Some(block_gas_limit) = genesis.gas_limit {
+            env.block.gas_limit = U256::from(block_gas_limit);
+        }
+        if let Some(coinbase) = genesis.coinbase {
+            env.block.coinbase = coinbase;
+        }
+        let backend = Backend::spawn(None).await;
+        let mut forked_evm = Self::new_with_shared_backend(backend, env, gas_limit);
+        forked_evm.apply_genesis(genesis)?;
+        Ok(forked_evm)
+    }
+
+    /// Builds an evm with no fork and no RPC at all, holding only
+    /// `accounts`, for unit tests against the same call, write and cheat
+    /// api. Everything else reads as empty, and the chain starts at
+    /// [LOCAL_CHAIN_ID] with block zero at timestamp one.
+    pub async fn new_local(
+        accounts: BTreeMap<Address, GenesisAccount>,
+    ) -> Result<ForkedEvm, ForkerError> {
+        Self::from_genesis(
+            Genesis {
+                config: GenesisConfig {
+                    chain_id: Some(LOCAL_CHAIN_ID),
+                },
+                timestamp: Some(U64::from(1)),
+                alloc: accounts,
+                ..Default::default()
+            },
+            None,
+        )
+        .await
+    }
+
+    /// Layers the genesis alloc on top of the active fork, overwriting any
+    /// account it mentions.
+    pub fn apply_genesis(&mut self, genesis: Genesis) -> Result<(), ForkerError> {
+        for (address, account) in genesis.alloc {
+            let storage = account
+                .storage
+                .unwrap_or_default()
+                .into_iter()
+                .map(|(slot, value)| (U256::from_be_bytes(slot.0), U256::from_be_bytes(value.0)));
+            self.insert_account(
+                address,
+                account.balance,
+                account.nonce.map_or(0, |nonce| nonce.to()),
+                account.code.unwrap_or_default(),
+                storage,
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use revm::DatabaseRef;
+
+    #[tokio::test]
+    async fn test_from_genesis_seeds_alloc_and_env() {
+        let genesis: Genesis = serde_json::from_value(serde_json::json!({
+            "config": { "chainId": 1337 },
+            "timestamp": "0x10",
+            "gasLimit": "0x1c9c380",
+            "alloc": {
+                "0x1111111111111111111111111111111111111111": {
+                    "balance": "0x64",
+                    "nonce": "0x2",
+                    "code": "0x00",
+                    "storage": {
+                        "0x0000000000000000000000000000000000000000000000000000000000000001":
+                            "0x0000000000000000000000000000000000000000000000000000000000000007"
+                    }
+                }
+            }
+        }))
+        .unwrap();
+        let forked_evm = ForkedEvm::from_genesis(genesis, None).await.unwrap();
+
+        let env = &forked_evm.executor.env;
+        assert_eq!(env.cfg.chain_id, 1337);
+        assert_eq!(env.block.timestamp, U256::from(0x10));
+        assert_eq!(env.block.gas_limit, U256::from(30_000_000));
+
+        let account = Address::repeat_byte(0x11);
+        let backend = &forked_evm.executor.backend;
+        let info = backend.basic_ref(account).unwrap().unwrap();
+        assert_eq!(info.balance, U256::from(100));
+        assert_eq!(info.nonce, 2);
+        assert_eq!(
+            info.code.unwrap().original_bytes(),
+            Bytes::from_static(&[0x00])
+        );
+        assert_eq!(
+            backend.storage_ref(account, U256::from(1)).unwrap(),
+            U256::from(7)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_new_local_runs_calls_against_its_accounts() {
+        let target = Address::repeat_byte(0x22);
+        // PUSH1 42 PUSH1 0 MSTORE PUSH1 32 PUSH1 0 RETURN
+        let code = Bytes::from(revm::primitives::hex::decode("602a60005260206000f3").unwrap());
+        let forked_evm = ForkedEvm::new_local(BTreeMap::from([(
+            target,
+            GenesisAccount {
+                balance: U256::from(5),
+                nonce: None,
+                code: Some(code),
+                storage: None,
+            },
+        )]))
+        .await
+        .unwrap();
+
+        let env = &forked_evm.executor.env;
+        assert_eq!(env.cfg.chain_id, LOCAL_CHAIN_ID);
+        assert_eq!(env.block.timestamp, U256::from(1));
+        let result = forked_evm
+            .call(Address::ZERO.as_slice(), target.as_slice(), &[])
+            .unwrap();
+        assert_eq!(U256::from_be_slice(&result.result), U256::from(42));
+        // accounts it wasn't given read as empty instead of being fetched
+        let other = forked_evm
+            .executor
+            .backend
+            .basic_ref(Address::repeat_byte(0x33))
+            .unwrap();
+        assert!(other.map_or(true, |info| info.balance.is_zero()));
+    }
+}
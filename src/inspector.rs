@@ -0,0 +1,316 @@
+use crate::{precompiles::Precompile, ForkedEvm, ForkerError};
+use foundry_evm::{
+    backend::{CowBackend, DatabaseExt},
+    executors::RawCallResult,
+    inspectors::{InspectorData, InspectorStack},
+};
+use revm::{
+    interpreter::{opcode, CallInputs, CreateInputs, Gas, InstructionResult, Interpreter},
+    primitives::{Address, Bytes, Env, ExecutionResult, Output, ResultAndState, State, B256, U256},
+    DatabaseCommit, EVMData, Inspector,
+};
+use std::collections::HashMap;
+
+/// Inspector for the features the executor's own inspector stack can't
+/// provide: a top level `msg.sender` that differs from `tx.origin`, custom
+/// precompiles and seeded block hashes. Runs the executor's stack alongside,
+/// so coverage, logs and traces are collected as for any other call.
+pub(crate) struct ForkInspector<'a> {
+    /// Sender of the top level call, `env.tx.caller` being the origin.
+    sender: Option<Address>,
+    precompiles: &'a HashMap<Address, Precompile>,
+    block_hashes: &'a HashMap<U256, B256>,
+    /// Seeded hash to put on the stack once the current BLOCKHASH ran.
+    pending_block_hash: Option<B256>,
+    stack: InspectorStack,
+}
+
+impl<'a> ForkInspector<'a> {
+    fn new(
+        precompiles: &'a HashMap<Address, Precompile>,
+        block_hashes: &'a HashMap<U256, B256>,
+        sender: Option<Address>,
+        stack: InspectorStack,
+    ) -> Self {
+        Self {
+            sender,
+            precompiles,
+            block_hashes,
+            pending_block_hash: None,
+            stack,
+        }
+    }
+}
+
+impl<DB: DatabaseExt> Inspector<DB> for ForkInspector<'_> {
+    fn initialize_interp(&mut self, interp: &mut Interpreter<'_>, data: &mut EVMData<'_, DB>) {
+        Inspector::<DB>::initialize_interp(&mut self.stack, interp, data);
+    }
+
+    fn step(&mut self, interp: &mut Interpreter<'_>, data: &mut EVMData<'_, DB>) {
+        if interp.current_opcode() == opcode::BLOCKHASH {
+            self.pending_block_hash = interp
+                .stack
+                .peek(0)
+                .ok()
+                .and_then(|number| self.block_hashes.get(&number).copied());
+        }
+        Inspector::<DB>::step(&mut self.stack, interp, data);
+    }
+
+    fn log(
+        &mut self,
+        data: &mut EVMData<'_, DB>,
+        address: &Address,
+        topics: &[B256],
+        log_data: &Bytes,
+    ) {
+        Inspector::<DB>::log(&mut self.stack, data, address, topics, log_data);
+    }
+
+    fn step_end(&mut self, interp: &mut Interpreter<'_>, data: &mut EVMData<'_, DB>) {
+        if let Some(hash) = self.pending_block_hash.take() {
+            if let Some(top) = interp.stack.data_mut().last_mut() {
+                *top = U256::from_be_bytes(hash.0);
+            }
+        }
+        Inspector::<DB>::step_end(&mut self.stack, interp, data);
+    }
+
+    fn call(
+        &mut self,
+        data: &mut EVMData<'_, DB>,
+        inputs: &mut CallInputs,
+    ) -> (InstructionResult, Gas, Bytes) {
+        if let Some(sender) = self.sender.filter(|_| data.journaled_state.depth() == 0) {
+            inputs.context.caller = sender;
+            inputs.transfer.source = sender;
+        }
+        let (result, gas, output) = Inspector::<DB>::call(&mut self.stack, data, inputs);
+        if result != InstructionResult::Continue {
+            return (result, gas, output);
+        }
+        let mut gas = Gas::new(inputs.gas_limit);
+        let Some(precompile) = self.precompiles.get(&inputs.contract) else {
+            return (InstructionResult::Continue, gas, Bytes::new());
+        };
+        match precompile(&inputs.input, inputs.gas_limit) {
+            Ok((gas_used, output)) if gas.record_cost(gas_used) => {
+                (InstructionResult::Return, gas, output)
+            }
+            Ok(_) => (InstructionResult::PrecompileOOG, gas, Bytes::new()),
+            Err(output) => (InstructionResult::Revert, gas, output),
+        }
+    }
+
+    fn call_end(
+        &mut self,
+        data: &mut EVMData<'_, DB>,
+        inputs: &CallInputs,
+        remaining_gas: Gas,
+        ret: InstructionResult,
+        out: Bytes,
+    ) -> (InstructionResult, Gas, Bytes) {
+        Inspector::<DB>::call_end(&mut self.stack, data, inputs, remaining_gas, ret, out)
+    }
+
+    fn create(
+        &mut self,
+        data: &mut EVMData<'_, DB>,
+        inputs: &mut CreateInputs,
+    ) -> (InstructionResult, Option<Address>, Gas, Bytes) {
+        Inspector::<DB>::create(&mut self.stack, data, inputs)
+    }
+
+    fn create_end(
+        &mut self,
+        data: &mut EVMData<'_, DB>,
+        inputs: &CreateInputs,
+        ret: InstructionResult,
+        address: Option<Address>,
+        remaining_gas: Gas,
+        out: Bytes,
+    ) -> (InstructionResult, Option<Address>, Gas, Bytes) {
+        Inspector::<DB>::create_end(
+            &mut self.stack,
+            data,
+            inputs,
+            ret,
+            address,
+            remaining_gas,
+            out,
+        )
+    }
+
+    fn selfdestruct(&mut self, contract: Address, target: Address, value: U256) {
+        Inspector::<DB>::selfdestruct(&mut self.stack, contract, target, value);
+    }
+}
+
+impl ForkedEvm {
+    /// Executes the tx described by `env` through a [ForkInspector] without
+    /// committing it, with `sender` as the top level `msg.sender` if given,
+    /// `env.tx.caller` being the origin.
+    pub(crate) fn call_inspected(
+        &self,
+        env: Env,
+        sender: Option<Address>,
+    ) -> Result<RawCallResult, ForkerError> {
+        self.call_inspected_with(env, sender, self.executor.inspector.clone())
+    }
+
+    /// Like [ForkedEvm::call_inspected] running `stack` instead of the
+    /// executor's inspectors.
+    pub(crate) fn call_inspected_with(
+        &self,
+        mut env: Env,
+        sender: Option<Address>,
+        stack: InspectorStack,
+    ) -> Result<RawCallResult, ForkerError> {
+        let probe = self.start_probe();
+        let mut inspector =
+            ForkInspector::new(&self.precompiles, &self.block_hashes, sender, stack);
+        let mut db = CowBackend::new(&self.executor.backend);
+        let result = db
+            .inspect_ref(&mut env, &mut inspector)
+            .map_err(ForkerError::executor)
+            .map(|ResultAndState { result, state }| {
+                raw_call_result(result, env, state, inspector.stack.collect())
+            });
+        self.finish_probe(probe, &result, false);
+        let result = result?;
+        self.record_coverage(result.coverage.clone());
+        Ok(result)
+    }
+
+    /// Like [ForkedEvm::call_inspected] but commits the state changes,
+    /// recording the commit under `hash` when it is a signed transaction.
+    pub(crate) fn commit_inspected(
+        &mut self,
+        mut env: Env,
+        sender: Option<Address>,
+        hash: Option<B256>,
+    ) -> Result<RawCallResult, ForkerError> {
+        self.bump_state_version();
+        let probe = self.start_probe();
+        let mut inspector = ForkInspector::new(
+            &self.precompiles,
+            &self.block_hashes,
+            sender,
+            self.executor.inspector.clone(),
+        );
+        let result = self
+            .executor
+            .backend
+            .inspect_ref(&mut env, &mut inspector)
+            .map_err(ForkerError::executor)
+            .map(|ResultAndState { result, state }| {
+                self.executor.backend.commit(state.clone());
+                raw_call_result(result, env, state, inspector.stack.collect())
+            });
+        self.finish_probe(probe, &result, true);
+        let result = result?;
+        self.record_coverage(result.coverage.clone());
+        self.record_commit(&result, hash);
+        Ok(result)
+    }
+}
+
+impl ForkedEvm {
+    /// Whether calls must run through a [ForkInspector] rather than the
+    /// executor's stack.
+    pub(crate) fn needs_fork_inspector(&self) -> bool {
+        !self.precompiles.is_empty() || !self.block_hashes.is_empty()
+    }
+}
+
+/// Builds the result the executor would return for `result`, with what the
+/// stack collected: logs, labels, traces, coverage and debug steps.
+fn raw_call_result(
+    result: ExecutionResult,
+    env: Env,
+    state: State,
+    data: InspectorData,
+) -> RawCallResult {
+    let gas_used = result.gas_used();
+    let inspected = RawCallResult {
+        logs: data.logs,
+        labels: data.labels,
+        traces: data.traces,
+        coverage: data.coverage,
+        debug: data.debug,
+        ..Default::default()
+    };
+    match result {
+        ExecutionResult::Success {
+            reason,
+            gas_refunded,
+            output,
+            ..
+        } => RawCallResult {
+            exit_reason: reason.into(),
+            reverted: false,
+            result: output.clone().into_data(),
+            gas_used,
+            gas_refunded,
+            out: Some(output),
+            state_changeset: Some(state),
+            env,
+            ..inspected
+        },
+        ExecutionResult::Revert { output, .. } => RawCallResult {
+            exit_reason: InstructionResult::Revert,
+            reverted: true,
+            result: output.clone(),
+            gas_used,
+            out: Some(Output::Call(output)),
+            state_changeset: Some(state),
+            env,
+            ..inspected
+        },
+        ExecutionResult::Halt { reason, .. } => RawCallResult {
+            exit_reason: reason.into(),
+            reverted: true,
+            gas_used,
+            state_changeset: Some(state),
+            env,
+            ..inspected
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::testing::{evm_with_code, returning_code};
+    use revm::primitives::{Address, Bytes, U256};
+
+    #[tokio::test]
+    async fn test_precompiles_keep_the_executor_stack() {
+        let sender = Address::repeat_byte(1);
+        let emitter = Address::repeat_byte(2);
+        let precompile = Address::repeat_byte(3);
+        // PUSH1 0 PUSH1 0 LOG0 STOP
+        let mut forked_evm = evm_with_code(&[(emitter, "60006000a000")]).await;
+        forked_evm
+            .register_precompile(precompile, |input, _| Ok((10, Bytes::from(input.to_vec()))));
+
+        let result = forked_evm
+            .write(sender.as_slice(), emitter.as_slice(), &[], U256::ZERO)
+            .unwrap();
+        assert_eq!(result.logs.len(), 1);
+
+        let result = forked_evm
+            .call(sender.as_slice(), precompile.as_slice(), &[7, 8])
+            .unwrap();
+        assert_eq!(result.result, Bytes::from_static(&[7, 8]));
+
+        assert!(forked_evm.remove_precompile(precompile));
+        let other = Address::repeat_byte(4);
+        let mut forked_evm = evm_with_code(&[(other, &returning_code(&[9]))]).await;
+        forked_evm.register_precompile(other, |_, gas_limit| Ok((gas_limit + 1, Bytes::new())));
+        let result = forked_evm
+            .call(sender.as_slice(), other.as_slice(), &[])
+            .unwrap();
+        assert!(result.reverted);
+    }
+}
@@ -0,0 +1,220 @@
+use crate::{fuzz::value_strategy, ForkedEvm, ForkerError};
+use alloy_dyn_abi::DynSolType;
+use proptest::{
+    prelude::*,
+    strategy::Union,
+    test_runner::{Config, TestCaseError, TestError, TestRunner},
+};
+use revm::primitives::{keccak256, Address, Bytes, U256};
+use std::cell::RefCell;
+
+/// A function actors may call, e.g. `deposit(uint256)` on a vault.
+#[derive(Debug, Clone)]
+pub struct InvariantTarget {
+    pub address: Address,
+    pub signature: String,
+    params: DynSolType,
+}
+
+/// One generated call of a sequence.
+#[derive(Debug, Clone)]
+pub struct InvariantCall {
+    pub from: Address,
+    pub to: Address,
+    pub signature: String,
+    pub calldata: Bytes,
+}
+
+/// Invariant broken by a sequence, shrunk to the shortest sequence found
+/// that still breaks it.
+#[derive(Debug, Clone)]
+pub struct InvariantFailure {
+    pub reason: String,
+    pub sequence: Vec<InvariantCall>,
+}
+
+type Invariant<'a> = Box<dyn Fn(&ForkedEvm) -> Result<(), ForkerError> + 'a>;
+
+/// Runs random call sequences by registered actors against registered
+/// functions and checks invariants after each sequence. Every sequence
+/// starts from the evm's state when the run starts, reverted calls are
+/// part of normal activity and don't fail a run.
+pub struct InvariantRunner<'a> {
+    runs: u32,
+    depth: usize,
+    actors: Vec<Address>,
+    targets: Vec<InvariantTarget>,
+    invariants: Vec<(String, Invariant<'a>)>,
+}
+
+impl<'a> InvariantRunner<'a> {
+    /// `runs` sequences of up to `depth` calls each.
+    pub fn new(runs: u32, depth: usize) -> Self {
+        Self {
+            runs,
+            depth,
+            actors: vec![],
+            targets: vec![],
+            invariants: vec![],
+        }
+    }
+
+    pub fn add_actor(&mut self, actor: Address) -> &mut Self {
+        self.actors.push(actor);
+        self
+    }
+
+    pub fn add_target(
+        &mut self,
+        address: Address,
+        signature: &str,
+    ) -> Result<&mut Self, ForkerError> {
+        let params = signature
+            .find('(')
+            .and_then(|start| DynSolType::parse(&signature[start..]).ok())
+            .ok_or_else(|| ForkerError::InvalidSignature(signature.to_string()))?;
+        // rejects parameter types that can't be generated up front
+        value_strategy(&params)?;
+        self.targets.push(InvariantTarget {
+            address,
+            signature: signature.to_string(),
+            params,
+        });
+        Ok(self)
+    }
+
+    /// Adds a check that must hold after every sequence, failing it by
+    /// returning an error, e.g. from [crate::expect] helpers.
+    pub fn add_invariant(
+        &mut self,
+        name: &str,
+        invariant: impl Fn(&ForkedEvm) -> Result<(), ForkerError> + 'a,
+    ) -> &mut Self {
+        self.invariants
+            .push((name.to_string(), Box::new(invariant)));
+        self
+    }
+
+    /// Runs the sequences, returning the shrunk failure of the first
+    /// broken invariant, `None` if they all held.
+    pub fn run(&self, forked_evm: &mut ForkedEvm) -> Result<Option<InvariantFailure>, ForkerError> {
+        if self.actors.is_empty() || self.targets.is_empty() {
+            return Ok(None);
+        }
+        let strategy = self.sequence_strategy()?;
+        let mut runner = TestRunner::new(Config {
+            cases: self.runs,
+            ..Config::default()
+        });
+        let forked_evm = RefCell::new(forked_evm);
+        let outcome = runner.run(&strategy, |sequence| {
+            let mut forked_evm = forked_evm.borrow_mut();
+            let snapshot_id = forked_evm.snapshot();
+            let result = self.check_sequence(&mut forked_evm, &sequence);
+            forked_evm.revert(snapshot_id);
+            result.map_err(TestCaseError::fail)
+        });
+        match outcome {
+            Ok(()) => Ok(None),
+            Err(TestError::Fail(reason, sequence)) => Ok(Some(InvariantFailure {
+                reason: reason.to_string(),
+                sequence,
+            })),
+            Err(TestError::Abort(reason)) => Err(ForkerError::FuzzAborted(reason.to_string())),
+        }
+    }
+
+    fn check_sequence(
+        &self,
+        forked_evm: &mut ForkedEvm,
+        sequence: &[InvariantCall],
+    ) -> Result<(), String> {
+        for call in sequence {
+            forked_evm
+                .write(
+                    call.from.as_slice(),
+                    call.to.as_slice(),
+                    &call.calldata,
+                    U256::ZERO,
+                )
+                .map_err(|e| format!("{} failed: {e}", call.signature))?;
+        }
+        for (name, invariant) in &self.invariants {
+            invariant(forked_evm).map_err(|e| format!("invariant {name} broken: {e}"))?;
+        }
+        Ok(())
+    }
+
+    fn sequence_strategy(&self) -> Result<impl Strategy<Value = Vec<InvariantCall>>, ForkerError> {
+        let calls = self
+            .targets
+            .iter()
+            .map(|target| {
+                let target = target.clone();
+                let selector = keccak256(target.signature.as_bytes());
+                Ok((
+                    proptest::sample::select(self.actors.clone()),
+                    value_strategy(&target.params)?,
+                )
+                    .prop_map(move |(from, args)| InvariantCall {
+                        from,
+                        to: target.address,
+                        signature: target.signature.clone(),
+                        calldata: [&selector[..4], &args.abi_encode_params()].concat().into(),
+                    }))
+            })
+            .collect::<Result<Vec<_>, ForkerError>>()?;
+        Ok(proptest::collection::vec(
+            Union::new(calls),
+            1..=self.depth.max(1),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::InvariantRunner;
+    use crate::{testing::evm_with_code, ForkerError};
+    use revm::primitives::{Address, U256};
+
+    #[test]
+    fn test_targets_need_a_fuzzable_signature() {
+        let mut runner = InvariantRunner::new(1, 1);
+        let target = Address::repeat_byte(0x22);
+        assert!(runner.add_target(target, "set(uint256)").is_ok());
+        assert!(matches!(
+            runner.add_target(target, "set"),
+            Err(ForkerError::InvalidSignature(_))
+        ));
+        assert!(matches!(
+            runner.add_target(target, "call(function)"),
+            Err(ForkerError::UnsupportedFuzzType(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_broken_invariants_are_reported_with_their_sequence() {
+        let target = Address::repeat_byte(0x22);
+        // stores the first argument at slot 0
+        let mut forked_evm = evm_with_code(&[(target, "60043560005500")]).await;
+        let mut runner = InvariantRunner::new(32, 4);
+        runner
+            .add_actor(Address::repeat_byte(0x11))
+            .add_target(target, "set(uint256)")
+            .unwrap();
+        runner.add_invariant("small", |forked_evm| {
+            if forked_evm.storage_at(target, U256::ZERO)? > U256::from(1000) {
+                return Err(ForkerError::InvalidTransaction("too large".to_string()));
+            }
+            Ok(())
+        });
+        let failure = runner.run(&mut forked_evm).unwrap().unwrap();
+        assert!(failure.reason.starts_with("invariant small broken"));
+        assert!(!failure.sequence.is_empty());
+        // every sequence runs on a snapshot that is reverted afterwards
+        assert_eq!(
+            forked_evm.storage_at(target, U256::ZERO).unwrap(),
+            U256::ZERO
+        );
+    }
+}
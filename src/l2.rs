@@ -0,0 +1,307 @@
+use crate::{
+    gas::effective_gas_price, signers::to_ethers_u256, ForkCallResult, ForkedEvm, ForkerError,
+};
+use alloy_sol_types::{sol, SolCall};
+use ethers::types::{
+    transaction::eip2718::TypedTransaction, Eip1559TransactionRequest, Signature, H160,
+    U256 as EthersU256,
+};
+use foundry_evm::executors::RawCallResult;
+use revm::primitives::{address, Address, Env, TransactTo, U256};
+
+/// OP stack predeploy pricing the L1 data of L2 transactions.
+pub const OP_GAS_PRICE_ORACLE_ADDRESS: Address =
+    address!("420000000000000000000000000000000000000F");
+/// Arbitrum precompile reporting L1 and L2 gas prices.
+pub const ARB_GAS_INFO_ADDRESS: Address = address!("000000000000000000000000000000000000006C");
+
+sol! {
+    interface IGasPriceOracle {
+        function getL1Fee(bytes memory data) external view returns (uint256);
+    }
+
+    interface IArbGasInfo {
+        function getPricesInWei()
+            external
+            view
+            returns (uint256, uint256, uint256, uint256, uint256, uint256);
+    }
+}
+
+/// L2 families whose transactions pay for L1 data on top of execution.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum L2Kind {
+    OpStack,
+    Arbitrum,
+}
+
+impl L2Kind {
+    pub fn from_chain_id(chain_id: u64) -> Option<Self> {
+        match chain_id {
+            // optimism, base, zora, mode and their sepolia testnets
+            10 | 8453 | 7777777 | 34443 | 11155420 | 84532 | 999999999 => Some(Self::OpStack),
+            // arbitrum one, nova and sepolia
+            42161 | 42170 | 421614 => Some(Self::Arbitrum),
+            _ => None,
+        }
+    }
+}
+
+/// Fees a transaction pays on an L2, split into execution and L1 data.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct L2Fees {
+    pub kind: L2Kind,
+    /// `gas_used` of the L2 execution at the price the chain charges:
+    /// basefee plus tip on OP stack chains, basefee alone on Arbitrum,
+    /// which doesn't pay tips.
+    pub l2_fee: U256,
+    /// Fee for posting the transaction's data to L1.
+    pub l1_fee: U256,
+    /// Gas Arbitrum adds to the transaction's reported gas used to buy the
+    /// L1 fee at the L2 basefee, zero on OP stack chains, which charge it
+    /// separately.
+    pub l1_gas: u64,
+}
+
+impl L2Fees {
+    pub fn total(&self) -> U256 {
+        self.l2_fee + self.l1_fee
+    }
+}
+
+impl ForkedEvm {
+    pub fn l2_kind(&self) -> Option<L2Kind> {
+        L2Kind::from_chain_id(self.executor.env.cfg.chain_id)
+    }
+
+    /// Execution and L1 data fees the transaction of `result` would pay on
+    /// the fork's chain, `None` on chains without an L1 data fee. The L1
+    /// data is sized as the signed transaction. OP stack fees are priced by
+    /// the forked gas price oracle, Arbitrum ones from the upstream
+    /// `ArbGasInfo` prices at the pinned block, as its precompiles can't
+    /// run locally. Balances are not debited for the L1 fee.
+    pub fn l2_fees(&self, result: &RawCallResult) -> Result<Option<L2Fees>, ForkerError> {
+        let Some(kind) = self.l2_kind() else {
+            return Ok(None);
+        };
+        let signed = signed_tx_estimate(&result.env);
+        let env = &result.env;
+        let gas_used = U256::from(result.gas_used);
+        let fees = match kind {
+            L2Kind::OpStack => {
+                let l1_fee = self
+                    .alloy_read(
+                        Address::ZERO.as_slice(),
+                        OP_GAS_PRICE_ORACLE_ADDRESS.as_slice(),
+                        IGasPriceOracle::getL1FeeCall {
+                            data: signed.into(),
+                        },
+                    )?
+                    ._0;
+                L2Fees {
+                    kind,
+                    l2_fee: gas_used * effective_gas_price(env),
+                    l1_fee,
+                    l1_gas: 0,
+                }
+            }
+            L2Kind::Arbitrum => {
+                // the second price is wei per byte of L1 calldata, charged
+                // here on the uncompressed transaction as an upper bound
+                let prices = self.arb_gas_prices()?;
+                let l1_fee = prices._1 * U256::from(signed.len());
+                let basefee = env.block.basefee;
+                let l1_gas = if basefee.is_zero() {
+                    0
+                } else {
+                    l1_fee.div_ceil(basefee).saturating_to()
+                };
+                L2Fees {
+                    kind,
+                    l2_fee: gas_used * basefee,
+                    l1_fee,
+                    l1_gas,
+                }
+            }
+        };
+        Ok(Some(fees))
+    }
+
+    /// Commits an OP stack deposit transaction: `mint` is credited to
+    /// `from_address` and kept even if the call reverts, no gas is charged
+    /// and the nonce isn't checked. Deposits pay no L1 fee.
+    pub fn write_deposit(
+        &mut self,
+        from_address: &[u8],
+        to_address: &[u8],
+        calldata: &[u8],
+        value: U256,
+        mint: U256,
+    ) -> Result<ForkCallResult, ForkerError> {
+        if self.l2_kind() != Some(L2Kind::OpStack) {
+            return Err(ForkerError::DepositsUnsupported(
+                self.executor.env.cfg.chain_id,
+            ));
+        }
+        let mut env = self.build_env(from_address, to_address, calldata)?;
+        env.tx.value = value;
+        env.tx.gas_price = U256::ZERO;
+        env.tx.gas_priority_fee = None;
+        env.tx.nonce = None;
+        env.cfg.disable_base_fee = true;
+
+        let sender = env.tx.caller;
+        let mut info = self.account_info(sender)?.unwrap_or_default();
+        info.balance = info.balance.saturating_add(mint);
+        self.executor.backend.insert_account_info(sender, info);
+
+        let mut result = ForkCallResult::from(self.commit_with_env(env)?);
+        result.gas.l1_fee = Some(U256::ZERO);
+        Ok(result)
+    }
+
+    #[cfg(feature = "native")]
+    fn arb_gas_prices(&self) -> Result<IArbGasInfo::getPricesInWeiReturn, ForkerError> {
+        use alloy_providers::provider::TempProvider;
+        use alloy_rpc_types::{CallInput, CallRequest};
+
+        let info = self.active_fork().ok_or(ForkerError::NoActiveFork)?;
+        let provider = self.fork_provider()?;
+        let request = CallRequest {
+            to: Some(ARB_GAS_INFO_ADDRESS),
+            input: CallInput::new(IArbGasInfo::getPricesInWeiCall {}.abi_encode().into()),
+            ..Default::default()
+        };
+        // the fork backend only serves state, so the precompile is called
+        // upstream on a runtime of its own, keeping this usable from sync
+        // code running inside the caller's runtime
+        let output = std::thread::scope(|scope| {
+            scope
+                .spawn(|| {
+                    let runtime = tokio::runtime::Builder::new_current_thread()
+                        .enable_all()
+                        .build()?;
+                    runtime
+                        .block_on(provider.call(request, Some(info.pinned_block.into())))
+                        .map_err(|e| ForkerError::rpc(&info.url, e))
+                })
+                .join()
+                .map_err(|_| ForkerError::WorkerPanicked)?
+        })?;
+        Ok(IArbGasInfo::getPricesInWeiCall::abi_decode_returns(
+            &output, true,
+        )?)
+    }
+
+    #[cfg(not(feature = "native"))]
+    fn arb_gas_prices(&self) -> Result<IArbGasInfo::getPricesInWeiReturn, ForkerError> {
+        let info = self.active_fork().ok_or(ForkerError::NoActiveFork)?;
+        Err(ForkerError::rpc(
+            &info.url,
+            "arbitrum l1 prices are fetched upstream, which needs the native feature",
+        ))
+    }
+}
+
+/// EIP-1559 encoding of the tx in `env` with a placeholder signature of
+/// full length, the size L1 data fees are charged on.
+fn signed_tx_estimate(env: &Env) -> Vec<u8> {
+    let mut request = Eip1559TransactionRequest::new()
+        .data(env.tx.data.to_vec())
+        .value(to_ethers_u256(env.tx.value))
+        .gas(env.tx.gas_limit)
+        .nonce(env.tx.nonce.unwrap_or_default())
+        .chain_id(env.cfg.chain_id)
+        .max_fee_per_gas(to_ethers_u256(env.tx.gas_price))
+        .max_priority_fee_per_gas(to_ethers_u256(env.tx.gas_priority_fee.unwrap_or_default()));
+    if let TransactTo::Call(to) = env.tx.transact_to {
+        request = request.to(H160::from(to.0 .0));
+    }
+    let signature = Signature {
+        r: EthersU256::MAX,
+        s: EthersU256::MAX,
+        v: 1,
+    };
+    TypedTransaction::Eip1559(request)
+        .rlp_signed(&signature)
+        .to_vec()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{signed_tx_estimate, L2Kind};
+    use crate::{testing::evm_with_code, ForkerError};
+    use revm::primitives::{Address, CreateScheme, Env, TransactTo, U256};
+
+    #[test]
+    fn test_l2_kind_from_chain_id() {
+        assert_eq!(L2Kind::from_chain_id(10), Some(L2Kind::OpStack));
+        assert_eq!(L2Kind::from_chain_id(8453), Some(L2Kind::OpStack));
+        assert_eq!(L2Kind::from_chain_id(42161), Some(L2Kind::Arbitrum));
+        assert_eq!(L2Kind::from_chain_id(1), None);
+    }
+
+    #[test]
+    fn test_signed_tx_estimate_is_eip1559() {
+        let mut env = Env::default();
+        env.tx.transact_to = TransactTo::Create(CreateScheme::Create);
+        let create = signed_tx_estimate(&env);
+        env.tx.transact_to = TransactTo::Call(Address::repeat_byte(1));
+        let call = signed_tx_estimate(&env);
+        assert_eq!(create[0], 2);
+        assert_eq!(call[0], 2);
+        // the recipient is encoded as 20 bytes instead of an empty string
+        assert_eq!(call.len(), create.len() + 20);
+        // y parity and two full 32 byte words
+        assert!(call.len() > 1 + 32 + 32);
+    }
+
+    #[tokio::test]
+    async fn test_deposits_mint_without_charging_gas() {
+        let sender = Address::repeat_byte(0x11);
+        let target = Address::repeat_byte(0x22);
+        // stores CALLVALUE at slot 0, then reverts
+        let mut forked_evm = evm_with_code(&[(target, "3460005560006000fd")]).await;
+        forked_evm.executor.env.block.basefee = U256::from(1_000);
+        assert!(matches!(
+            forked_evm.write_deposit(
+                sender.as_slice(),
+                target.as_slice(),
+                &[],
+                U256::ZERO,
+                U256::from(5)
+            ),
+            Err(ForkerError::DepositsUnsupported(_))
+        ));
+
+        forked_evm.executor.env.cfg.chain_id = 10;
+        let result = forked_evm
+            .write_deposit(
+                sender.as_slice(),
+                target.as_slice(),
+                &[],
+                U256::from(2),
+                U256::from(5),
+            )
+            .unwrap();
+        assert!(result.reverted);
+        assert_eq!(result.gas.l1_fee, Some(U256::ZERO));
+        // the mint survives the revert, the value transfer and gas don't
+        assert_eq!(forked_evm.balance_of(sender).unwrap(), U256::from(5));
+    }
+
+    #[tokio::test]
+    async fn test_l1_fee_is_none_off_l2() {
+        let target = Address::repeat_byte(0x22);
+        let forked_evm = evm_with_code(&[(target, "00")]).await;
+        let result = forked_evm
+            .call_detailed(
+                Address::ZERO.as_slice(),
+                target.as_slice(),
+                &[],
+                &Default::default(),
+            )
+            .unwrap();
+        assert_eq!(result.gas.l1_fee, None);
+    }
+}
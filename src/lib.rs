@@ -1,17 +1,258 @@
+use alloy_sol_types::SolCall;
+use error::check_address;
 use foundry_evm::{
-    backend::Backend,
-    executors::{Executor, ExecutorBuilder, RawCallResult},
-    fork::CreateFork,
-    opts::EvmOpts,
+    backend::{Backend, DatabaseExt},
+    executors::{ExecutorBuilder, RawCallResult},
+    fork::{CreateFork, ForkId},
 };
-use revm::primitives::{Address, Bytes, Env, TransactTo, U256};
+use revm::{
+    db::DbAccount,
+    primitives::{
+        AccountInfo, Address, Bytecode, Bytes, CreateScheme, Env, Output, SpecId, TransactTo, B256,
+        KECCAK_EMPTY, U256,
+    },
+    JournaledState,
+};
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+pub mod account;
+pub mod anvil;
+pub mod bench;
+pub mod block_hash;
+#[cfg(feature = "native")]
+pub mod blocking;
+pub mod bundle;
+pub mod cache;
+pub mod chain;
+pub mod chains;
+pub mod console;
+pub mod coverage;
+pub mod cow;
+pub mod debug;
+pub mod dynamic;
+pub mod ens;
+pub mod erc4337;
+pub mod error;
+pub mod expect;
+#[cfg(feature = "native")]
+pub mod failover;
+pub mod fees;
+pub mod forks;
+#[cfg(feature = "fuzz")]
+pub mod fuzz;
+pub mod gas;
+pub mod genesis;
+mod inspector;
+#[cfg(feature = "fuzz")]
+pub mod invariant;
+pub mod l2;
+pub mod logs;
+mod memo;
+pub mod metrics;
+pub mod multicall;
+pub mod multichain;
+pub mod nonces;
+#[cfg(feature = "native")]
+pub mod offline;
+pub mod origin;
+pub mod overrides;
+pub mod permit;
+pub mod plan;
+pub mod precompiles;
+pub mod prefetch;
+#[cfg(feature = "native")]
+pub mod provider;
+pub mod proxy;
+pub mod rain;
+pub mod receipt;
+pub mod report;
+pub mod request;
+#[cfg(feature = "native")]
+pub mod rpc;
+pub mod session;
+pub mod signers;
+pub mod simulate;
+pub mod spec;
+pub mod stack;
+pub mod storage_layout;
+#[cfg(test)]
+mod testing;
+pub mod tokens;
+#[cfg(feature = "native")]
+pub mod transport;
+pub mod tx;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+#[cfg(feature = "native")]
+pub mod watcher;
+
+pub use error::ForkerError;
 
 // re-export
+pub use alloy_dyn_abi;
+pub use alloy_json_abi;
+pub use alloy_rpc_types;
+pub use alloy_sol_types;
 pub use foundry_evm;
 pub use revm;
 
+/// Per-transaction overrides of the executor env.
+#[derive(Debug, Clone, Default)]
+pub struct CallOptions {
+    pub gas_limit: Option<u64>,
+    pub gas_price: Option<U256>,
+    pub nonce: Option<u64>,
+    pub chain_id: Option<u64>,
+    /// `msg.value` attached to the call, e.g. to simulate payable calls
+    /// without committing them.
+    pub value: Option<U256>,
+    /// `tx.origin` of the call when it should differ from `msg.sender`.
+    pub origin: Option<Address>,
+    /// `block.number` seen by the call, e.g. to quote against a later block.
+    pub block_number: Option<U256>,
+    /// `block.timestamp` seen by the call.
+    pub timestamp: Option<U256>,
+    pub coinbase: Option<Address>,
+    pub basefee: Option<U256>,
+}
+
+impl CallOptions {
+    pub(crate) fn apply(&self, env: &mut Env) {
+        if let Some(gas_limit) = self.gas_limit {
+            env.tx.gas_limit = gas_limit;
+        }
+        if let Some(gas_price) = self.gas_price {
+            env.tx.gas_price = gas_price;
+        }
+        if let Some(nonce) = self.nonce {
+            env.tx.nonce = Some(nonce);
+        }
+        if let Some(chain_id) = self.chain_id {
+            env.tx.chain_id = Some(chain_id);
+            env.cfg.chain_id = chain_id;
+        }
+        if let Some(value) = self.value {
+            env.tx.value = value;
+        }
+        if let Some(block_number) = self.block_number {
+            env.block.number = block_number;
+        }
+        if let Some(timestamp) = self.timestamp {
+            env.block.timestamp = timestamp;
+        }
+        if let Some(coinbase) = self.coinbase {
+            env.block.coinbase = coinbase;
+        }
+        if let Some(basefee) = self.basefee {
+            env.block.basefee = basefee;
+        }
+    }
+}
+
+/// Result of [ForkedEvm::call_detailed] and [ForkedEvm::write_detailed]:
+/// the raw result with what it emitted decoded. Derefs to the raw result.
+#[derive(Debug, Clone)]
+pub struct ForkCallResult {
+    pub raw: RawCallResult,
+    /// `console.log` and DSTest `log_*` output, in emission order.
+    pub console_logs: Vec<String>,
+    /// Gas and wei the transaction pays, including the L1 data fee on L2
+    /// forks.
+    pub gas: gas::GasBreakdown,
+}
+
+impl From<RawCallResult> for ForkCallResult {
+    fn from(raw: RawCallResult) -> Self {
+        Self {
+            console_logs: console::console_logs(&raw),
+            gas: gas::GasBreakdown::execution(&raw),
+            raw,
+        }
+    }
+}
+
+impl std::ops::Deref for ForkCallResult {
+    type Target = RawCallResult;
+
+    fn deref(&self) -> &RawCallResult {
+        &self.raw
+    }
+}
+
 pub struct ForkedEvm {
-    pub executor: Executor,
+    pub executor: cow::CowExecutor,
+    coverage: Arc<Mutex<Option<foundry_evm::coverage::HitMaps>>>,
+    forks: HashMap<ForkId, forks::ForkInfo>,
+    fork_clock: u64,
+    memory_budget: Option<usize>,
+    local_logs: Vec<logs::LocalLog>,
+    /// Receipt data of every local commit, in commit order.
+    local_txs: Vec<receipt::LocalTx>,
+    /// Local commit record at each live snapshot, see [ForkedEvm::revert].
+    snapshot_marks: HashMap<U256, logs::LocalMark>,
+    signers: HashMap<Address, ethers::signers::LocalWallet>,
+    default_origin: Option<Address>,
+    metrics: Option<Arc<dyn metrics::MetricsSink>>,
+    precompiles: HashMap<Address, precompiles::Precompile>,
+    auto_fees: Option<fees::FeeSuggestion>,
+    read_cache: Option<memo::ReadCache>,
+    state_version: u64,
+    inspector_config: stack::InspectorConfig,
+    #[cfg(feature = "native")]
+    event_sender: Option<tokio::sync::broadcast::Sender<logs::LocalLog>>,
+    block_hashes: HashMap<U256, B256>,
+    /// Relays serving forks of several RPCs, shared by forks of the same
+    /// upstream.
+    #[cfg(feature = "native")]
+    rpc_relays: HashMap<transport::RelayKey, transport::RpcRelay>,
+    rpc_config: forks::RpcConfig,
+    /// Requests per second budgets, keyed by provider url and budget.
+    #[cfg(feature = "native")]
+    rpc_limiters: HashMap<(String, u32), Arc<transport::RateLimiter>>,
+    /// Shared with the relays, see [ForkedEvm::set_offline].
+    #[cfg(feature = "native")]
+    offline: Arc<std::sync::atomic::AtomicBool>,
+}
+
+impl Clone for ForkedEvm {
+    /// Clones share the backend's fork cache, the relays serving forks, the
+    /// rate limits of their RPCs, offline mode and the executor until one of
+    /// them writes, but take their own copy of the collected coverage, so
+    /// hits recorded on one handle never show up on another, and start
+    /// without event subscribers.
+    fn clone(&self) -> Self {
+        Self {
+            executor: self.executor.clone(),
+            coverage: Arc::new(Mutex::new(self.coverage.lock().unwrap().clone())),
+            forks: self.forks.clone(),
+            fork_clock: self.fork_clock,
+            memory_budget: self.memory_budget,
+            local_logs: self.local_logs.clone(),
+            local_txs: self.local_txs.clone(),
+            snapshot_marks: self.snapshot_marks.clone(),
+            signers: self.signers.clone(),
+            default_origin: self.default_origin,
+            metrics: self.metrics.clone(),
+            precompiles: self.precompiles.clone(),
+            auto_fees: self.auto_fees,
+            read_cache: self.read_cache.clone(),
+            state_version: self.state_version,
+            inspector_config: self.inspector_config,
+            #[cfg(feature = "native")]
+            event_sender: None,
+            block_hashes: self.block_hashes.clone(),
+            #[cfg(feature = "native")]
+            rpc_relays: self.rpc_relays.clone(),
+            rpc_config: self.rpc_config.clone(),
+            #[cfg(feature = "native")]
+            rpc_limiters: self.rpc_limiters.clone(),
+            #[cfg(feature = "native")]
+            offline: self.offline.clone(),
+        }
+    }
 }
 
 impl ForkedEvm {
@@ -20,58 +261,657 @@ impl ForkedEvm {
         fork_block_number: Option<u64>,
         gas_limit: Option<u64>,
         env: Option<Env>,
-    ) -> ForkedEvm {
-        let evm_opts = EvmOpts {
-            fork_url: Some(fork_url.to_string()),
-            fork_block_number,
-            env: foundry_evm::opts::Env {
-                chain_id: None,
-                code_size_limit: None,
-                // gas_price: Some(100),
-                gas_limit: u64::MAX,
-                ..Default::default()
-            },
-            ..Default::default()
-        };
-
-        let fork_opts = CreateFork {
-            url: fork_url.to_string(),
-            enable_caching: true,
-            env: evm_opts.fork_evm_env(fork_url).await.unwrap().0,
-            evm_opts,
-        };
+    ) -> Result<ForkedEvm, ForkerError> {
+        // ws and ipc forks are served through a relay, see add_or_select
+        #[cfg(feature = "native")]
+        if !transport::is_http(fork_url) {
+            let policy = transport::RelayPolicy::default();
+            let relay = transport::RpcRelay::connect(fork_url.into(), policy.clone()).await?;
+            let fork_opts =
+                Self::create_fork_opts(&relay.url, fork_block_number, &Default::default()).await?;
+            let mut forked_evm =
+                Self::new_with_fork_opts(fork_opts, fork_block_number, gas_limit, env).await;
+            forked_evm.offline = policy.offline;
+            forked_evm.adopt_relay(relay);
+            return Ok(forked_evm);
+        }
+        let fork_opts =
+            Self::create_fork_opts(fork_url, fork_block_number, &Default::default()).await?;
+        Ok(Self::new_with_fork_opts(fork_opts, fork_block_number, gas_limit, env).await)
+    }
 
+    pub(crate) async fn new_with_fork_opts(
+        fork_opts: CreateFork,
+        fork_block_number: Option<u64>,
+        gas_limit: Option<u64>,
+        env: Option<Env>,
+    ) -> ForkedEvm {
+        let fork_url = fork_opts.url.clone();
         let db = Backend::spawn(Some(fork_opts.clone())).await;
         // new(MultiFork::spawn().await, Some(fork_opts.clone()));
 
+        // a caller provided env keeps its spec, the fork's env gets the
+        // hardfork of its chain and block
+        let env = env.unwrap_or_else(|| {
+            let mut env = fork_opts.env.clone();
+            env.cfg.spec_id =
+                spec::spec_for_block(env.cfg.chain_id, env.block.number.saturating_to());
+            env
+        });
+        let mut forked_evm = Self::new_with_shared_backend(db, env, gas_limit);
+        if let Some(local_id) = forked_evm.executor.backend.active_fork_id() {
+            forked_evm.track_fork(
+                ForkId::new(&fork_url, fork_block_number),
+                &fork_url,
+                fork_block_number,
+                local_id,
+            );
+        }
+        forked_evm
+    }
+
+    /// Builds an executor on top of an existing backend. Backends share their
+    /// fork RPC cache when cloned, so handles built from clones of the same
+    /// backend are cheap and independent of each other's local state.
+    pub fn new_with_shared_backend(
+        backend: Backend,
+        env: Env,
+        gas_limit: Option<u64>,
+    ) -> ForkedEvm {
+        // collect hardhat console and DSTest logs so they can be decoded
+        // with [console::console_logs]
+        let builder = ExecutorBuilder::default()
+            .inspectors(|stack| stack.logs(true))
+            .spec(env.cfg.spec_id);
         let builder = if let Some(gas) = gas_limit {
-            ExecutorBuilder::default().gas_limit(U256::from(gas))
+            builder.gas_limit(U256::from(gas))
         } else {
-            ExecutorBuilder::default()
+            builder
         };
 
         Self {
-            executor: builder.build(env.unwrap_or(fork_opts.env.clone()), db),
+            executor: builder.build(env, backend).into(),
+            coverage: Arc::new(Mutex::new(None)),
+            forks: HashMap::new(),
+            fork_clock: 0,
+            memory_budget: None,
+            local_logs: vec![],
+            local_txs: vec![],
+            snapshot_marks: HashMap::new(),
+            signers: HashMap::new(),
+            default_origin: None,
+            metrics: None,
+            precompiles: HashMap::new(),
+            auto_fees: None,
+            read_cache: None,
+            state_version: memo::next_state_version(),
+            inspector_config: stack::InspectorConfig::default(),
+            #[cfg(feature = "native")]
+            event_sender: None,
+            block_hashes: HashMap::new(),
+            #[cfg(feature = "native")]
+            rpc_relays: HashMap::new(),
+            rpc_config: forks::RpcConfig::default(),
+            #[cfg(feature = "native")]
+            rpc_limiters: HashMap::new(),
+            #[cfg(feature = "native")]
+            offline: Default::default(),
         }
     }
 
+    /// Executes a call against the current fork state without committing it.
+    /// Takes `&self` so many reads can run in parallel against one handle.
+    #[cfg_attr(feature = "tracing", tracing::instrument(
+        level = "debug",
+        skip_all,
+        fields(
+            from = %revm::primitives::hex::encode(from_address),
+            to = %revm::primitives::hex::encode(to_address),
+        ),
+    ))]
     pub fn call(
+        &self,
+        from_address: &[u8],
+        to_address: &[u8],
+        calldata: &[u8],
+    ) -> Result<RawCallResult, ForkerError> {
+        if self.default_origin.is_some() || self.needs_fork_inspector() {
+            return self.call_with_opts(
+                from_address,
+                to_address,
+                calldata,
+                &CallOptions::default(),
+            );
+        }
+        check_address(from_address)?;
+        check_address(to_address)?;
+        let from = Address::from_slice(from_address);
+        let to = Address::from_slice(to_address);
+        let key = self.read_key(from, to, calldata);
+        if let Some(result) = self.memoized_read(&key) {
+            return Ok(result);
+        }
+        let probe = self.start_probe();
+        let result = self
+            .executor
+            .call_raw(from, to, Bytes::from(calldata.to_vec()), U256::ZERO)
+            .map_err(ForkerError::executor);
+        self.finish_probe(probe, &result, false);
+        let result = result?;
+        self.record_coverage(result.coverage.clone());
+        self.memoize_read(key, &result);
+        Ok(result)
+    }
+
+    /// Like [ForkedEvm::call] but with gas, nonce, chain id, value or block
+    /// fields overridden for just this call.
+    pub fn call_with_opts(
+        &self,
+        from_address: &[u8],
+        to_address: &[u8],
+        calldata: &[u8],
+        opts: &CallOptions,
+    ) -> Result<RawCallResult, ForkerError> {
+        let mut env = self.build_env(from_address, to_address, calldata)?;
+        opts.apply(&mut env);
+        match opts.origin.or(self.default_origin) {
+            Some(origin) => self.call_as_origin(env, origin),
+            None => self.call_with_env(env),
+        }
+    }
+
+    /// Executes the tx described by `env` without committing it.
+    #[cfg_attr(feature = "tracing", tracing::instrument(
+        level = "debug",
+        skip_all,
+        fields(from = %env.tx.caller, to = ?env.tx.transact_to),
+    ))]
+    pub(crate) fn call_with_env(&self, env: Env) -> Result<RawCallResult, ForkerError> {
+        if self.needs_fork_inspector() {
+            return self.call_inspected(env, None);
+        }
+        // the executor only runs custom envs through `&mut self`, so run it on
+        // a copy, which costs about the same as the backend copy every read
+        // makes anyway
+        let probe = self.start_probe();
+        let result = self
+            .executor
+            .clone()
+            .call_raw_with_env(env)
+            .map_err(ForkerError::executor);
+        self.finish_probe(probe, &result, false);
+        let result = result?;
+        self.record_coverage(result.coverage.clone());
+        Ok(result)
+    }
+
+    /// Executes a typed read and decodes its return with the call's abi.
+    pub fn alloy_read<C: SolCall>(
+        &self,
+        from_address: &[u8],
+        to_address: &[u8],
+        call: C,
+    ) -> Result<C::Return, ForkerError> {
+        self.alloy_read_with_opts(from_address, to_address, call, &CallOptions::default())
+    }
+
+    /// Like [ForkedEvm::alloy_read] with [CallOptions] applied, e.g. to read
+    /// the result of a payable call.
+    pub fn alloy_read_with_opts<C: SolCall>(
+        &self,
+        from_address: &[u8],
+        to_address: &[u8],
+        call: C,
+        opts: &CallOptions,
+    ) -> Result<C::Return, ForkerError> {
+        let result = self.call_with_opts(from_address, to_address, &call.abi_encode(), opts)?;
+        if result.reverted {
+            return Err(ForkerError::reverted(&result));
+        }
+        Ok(C::abi_decode_returns(&result.result, true)?)
+    }
+
+    /// Executes and commits a typed write, decoding its return with the
+    /// call's abi alongside the raw result.
+    pub fn alloy_write<C: SolCall>(
+        &mut self,
+        from_address: &[u8],
+        to_address: &[u8],
+        call: C,
+        value: U256,
+    ) -> Result<(RawCallResult, C::Return), ForkerError> {
+        let result = self.write(from_address, to_address, &call.abi_encode(), value)?;
+        if result.reverted {
+            return Err(ForkerError::reverted(&result));
+        }
+        let typed_return = C::abi_decode_returns(&result.result, true)?;
+        Ok((result, typed_return))
+    }
+
+    /// Executes a call against the current fork state and commits its
+    /// state changes.
+    pub fn write(
+        &mut self,
+        from_address: &[u8],
+        to_address: &[u8],
+        calldata: &[u8],
+        value: U256,
+    ) -> Result<RawCallResult, ForkerError> {
+        self.write_with_opts(
+            from_address,
+            to_address,
+            calldata,
+            value,
+            &CallOptions::default(),
+        )
+    }
+
+    /// Like [ForkedEvm::write] but with gas, nonce and chain id overridden
+    /// for just this transaction. Without an explicit nonce the current
+    /// nonce of the account sending the tx, the origin if set, is used.
+    pub fn write_with_opts(
+        &mut self,
+        from_address: &[u8],
+        to_address: &[u8],
+        calldata: &[u8],
+        value: U256,
+        opts: &CallOptions,
+    ) -> Result<RawCallResult, ForkerError> {
+        let mut env = self.build_env(from_address, to_address, calldata)?;
+        env.tx.value = value;
+        if let Some(fees) = self.auto_fees.filter(|_| opts.gas_price.is_none()) {
+            fees.apply(&mut env);
+        }
+        opts.apply(&mut env);
+        let origin = opts.origin.or(self.default_origin).unwrap_or(env.tx.caller);
+        self.commit_as_origin(env, origin)
+    }
+
+    /// Like [ForkedEvm::call_with_opts], with the console output decoded
+    /// and the gas it pays, L1 data fee included on L2 forks.
+    pub fn call_detailed(
+        &self,
+        from_address: &[u8],
+        to_address: &[u8],
+        calldata: &[u8],
+        opts: &CallOptions,
+    ) -> Result<ForkCallResult, ForkerError> {
+        let result = self.call_with_opts(from_address, to_address, calldata, opts)?;
+        self.with_gas(result.into())
+    }
+
+    /// Like [ForkedEvm::write_with_opts], with the console output decoded
+    /// and the gas it pays, L1 data fee included on L2 forks.
+    pub fn write_detailed(
         &mut self,
         from_address: &[u8],
         to_address: &[u8],
         calldata: &[u8],
-    ) -> eyre::Result<RawCallResult> {
-        if from_address.len() != 20 || to_address.len() != 20 {
-            return Err(eyre::Report::msg("invalid address!"));
+        value: U256,
+        opts: &CallOptions,
+    ) -> Result<ForkCallResult, ForkerError> {
+        let result = self.write_with_opts(from_address, to_address, calldata, value, opts)?;
+        self.with_gas(result.into())
+    }
+
+    /// Executes the tx described by `env` and commits its state changes.
+    #[cfg_attr(feature = "tracing", tracing::instrument(
+        level = "debug",
+        skip_all,
+        fields(from = %env.tx.caller, to = ?env.tx.transact_to),
+    ))]
+    pub(crate) fn commit_with_env(&mut self, env: Env) -> Result<RawCallResult, ForkerError> {
+        self.commit_tx(env, None)
+    }
+
+    /// Like [ForkedEvm::commit_with_env], recording the commit under `hash`
+    /// when it is a signed transaction.
+    pub(crate) fn commit_tx(
+        &mut self,
+        env: Env,
+        hash: Option<B256>,
+    ) -> Result<RawCallResult, ForkerError> {
+        self.bump_state_version();
+        if self.needs_fork_inspector() {
+            return self.commit_inspected(env, None, hash);
         }
-        let mut env = Env::default();
+        let probe = self.start_probe();
+        let result = self
+            .executor
+            .commit_tx_with_env(env)
+            .map_err(ForkerError::executor);
+        self.finish_probe(probe, &result, true);
+        let result = result?;
+        self.record_coverage(result.coverage.clone());
+        self.record_commit(&result, hash);
+        Ok(result)
+    }
+
+    /// Deploys `bytecode` (creation code including any constructor args) and
+    /// commits it, returning the new contract's address.
+    pub fn deploy(
+        &mut self,
+        from_address: &[u8],
+        bytecode: &[u8],
+        value: U256,
+    ) -> Result<Address, ForkerError> {
+        check_address(from_address)?;
+        let mut env = self.executor.env.clone();
+        env.tx.caller = Address::from_slice(from_address);
+        env.tx.data = Bytes::from(bytecode.to_vec());
+        env.tx.transact_to = TransactTo::Create(CreateScheme::Create);
+        env.tx.value = value;
+        self.assign_nonce(&mut env)?;
+        let result = self.commit_with_env(env)?;
+        match result.out {
+            Some(Output::Create(_, Some(address))) if !result.reverted => Ok(address),
+            _ => Err(ForkerError::DeploymentFailed(result.exit_reason)),
+        }
+    }
+
+    /// Snapshots the current fork state, returning an id to [ForkedEvm::revert] to.
+    pub fn snapshot(&mut self) -> U256 {
+        let journaled_state = JournaledState::new(SpecId::LATEST, vec![]);
+        let snapshot_id = self
+            .executor
+            .backend
+            .snapshot(&journaled_state, &self.executor.env);
+        self.snapshot_marks.insert(snapshot_id, self.local_mark());
+        snapshot_id
+    }
+
+    /// Reverts the fork state to the given snapshot, returning false if the
+    /// snapshot doesn't exist. Local logs of the commits made since are
+    /// dropped too.
+    pub fn revert(&mut self, snapshot_id: U256) -> bool {
+        self.bump_state_version();
+        let journaled_state = JournaledState::new(SpecId::LATEST, vec![]);
+        let reverted = self
+            .executor
+            .backend
+            .revert(snapshot_id, &journaled_state, &mut self.executor.env)
+            .is_some();
+        if let Some(mark) = self.snapshot_marks.remove(&snapshot_id) {
+            if reverted {
+                self.rewind_local(mark);
+            }
+        }
+        reverted
+    }
+
+    /// Overwrites an account's info and the given storage slots on the
+    /// active fork.
+    pub(crate) fn insert_account(
+        &mut self,
+        address: Address,
+        balance: U256,
+        nonce: u64,
+        code: Bytes,
+        storage: impl IntoIterator<Item = (U256, U256)>,
+    ) -> Result<(), ForkerError> {
+        self.bump_state_version();
+        let (code_hash, code) = if code.is_empty() {
+            (KECCAK_EMPTY, None)
+        } else {
+            let code = Bytecode::new_raw(code);
+            (code.hash_slow(), Some(code))
+        };
+        self.executor.backend.insert_account_info(
+            address,
+            AccountInfo {
+                balance,
+                nonce,
+                code_hash,
+                code,
+            },
+        );
+        for (slot, value) in storage {
+            self.executor
+                .backend
+                .insert_account_storage(address, slot, value)?;
+        }
+        Ok(())
+    }
+
+    /// Accounts loaded or modified locally on the active fork, or in memory
+    /// when there is no fork.
+    pub(crate) fn local_accounts(&self) -> &revm::primitives::HashMap<Address, DbAccount> {
+        match self.executor.backend.active_fork_db() {
+            Some(fork_db) => &fork_db.accounts,
+            None => &self.executor.backend.mem_db().accounts,
+        }
+    }
+
+    /// Local layer of the active fork, see [ForkedEvm::local_accounts].
+    pub(crate) fn local_accounts_mut(
+        &mut self,
+    ) -> Result<&mut revm::primitives::HashMap<Address, DbAccount>, ForkerError> {
+        self.executor
+            .backend
+            .active_fork_mut()
+            .map(|fork| &mut fork.db.accounts)
+            .ok_or(ForkerError::NoActiveFork)
+    }
+
+    /// Builds a tx env on top of the executor's env (fork block, chain id)
+    /// for a call from `from_address` to `to_address`.
+    pub(crate) fn build_env(
+        &self,
+        from_address: &[u8],
+        to_address: &[u8],
+        calldata: &[u8],
+    ) -> Result<Env, ForkerError> {
+        check_address(from_address)?;
+        check_address(to_address)?;
+        let mut env = self.executor.env.clone();
         env.tx.caller = Address::from_slice(from_address);
         env.tx.data = Bytes::from(calldata.to_vec());
         env.tx.transact_to = TransactTo::Call(Address::from_slice(to_address));
-        // env.tx.gas_limit = 1000;
-        // env.tx.gas_price = U256::from(20000);
-        // env.tx.gas_priority_fee = Some(U256::from(20000));
+        env.tx.value = U256::ZERO;
+        env.tx.gas_limit = self.executor.gas_limit.saturating_to();
+        Ok(env)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::evm_with_code;
+    use revm::{
+        primitives::{hex, AccountInfo},
+        DatabaseRef,
+    };
+
+    #[tokio::test]
+    async fn test_clones_have_independent_local_state() {
+        let target = Address::repeat_byte(0x11);
+        let mut forked_evm = evm_with_code(&[(target, "00")]).await;
+        forked_evm.enable_coverage();
+        let mut handle = forked_evm.clone();
+
+        let funded = Address::repeat_byte(0x22);
+        handle.executor.backend.insert_account_info(
+            funded,
+            AccountInfo {
+                balance: U256::from(1),
+                ..Default::default()
+            },
+        );
+        handle.call(&[0u8; 20], target.as_slice(), &[]).unwrap();
+
+        let balance = |evm: &ForkedEvm| {
+            evm.executor
+                .backend
+                .basic_ref(funded)
+                .unwrap()
+                .map(|info| info.balance)
+                .unwrap_or_default()
+        };
+        assert_eq!(balance(&handle), U256::from(1));
+        assert_eq!(balance(&forked_evm), U256::ZERO);
+        assert!(!handle.coverage_hits().unwrap().0.is_empty());
+        assert!(forked_evm.coverage_hits().unwrap().0.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_reads_through_shared_handle() {
+        let target = Address::repeat_byte(0x11);
+        // returns 1 + 2 as a single word
+        let forked_evm = evm_with_code(&[(target, "600160020160005260206000f3")]).await;
+
+        std::thread::scope(|scope| {
+            let handles: Vec<_> = (0..4)
+                .map(|_| scope.spawn(|| forked_evm.call(&[0u8; 20], target.as_slice(), &[])))
+                .collect();
+            for handle in handles {
+                let result = handle.join().unwrap().unwrap();
+                assert_eq!(U256::from_be_slice(&result.result), U256::from(3));
+            }
+        });
+    }
+
+    #[tokio::test]
+    async fn test_errors_are_matchable() {
+        let mut forked_evm = evm_with_code(&[]).await;
+        assert!(matches!(
+            forked_evm.call(&[0u8; 19], &[0u8; 20], &[]),
+            Err(ForkerError::InvalidAddress { got_len: 19 })
+        ));
+        // init code that reverts
+        assert!(matches!(
+            forked_evm.deploy(&[0u8; 20], &hex::decode("60006000fd").unwrap(), U256::ZERO),
+            Err(ForkerError::DeploymentFailed(_))
+        ));
+    }
+
+    alloy_sol_types::sol! {
+        function value() returns (uint256);
+    }
+
+    #[tokio::test]
+    async fn test_reverted_reads_carry_gas_and_exit_reason() {
+        let adder = Address::repeat_byte(0x11);
+        let reverter = Address::repeat_byte(0x12);
+        let mut forked_evm = evm_with_code(&[
+            (adder, "600160020160005260206000f3"),
+            (reverter, "60006000fd"),
+        ])
+        .await;
 
-        self.executor.call_raw_with_env(env)
+        let read = forked_evm.alloy_read(&[0u8; 20], adder.as_slice(), valueCall {});
+        assert_eq!(read.unwrap()._0, U256::from(3));
+        let (result, written) = forked_evm
+            .alloy_write(&[0u8; 20], adder.as_slice(), valueCall {}, U256::ZERO)
+            .unwrap();
+        assert!(!result.reverted);
+        assert_eq!(written._0, U256::from(3));
+
+        match forked_evm.alloy_read(&[0u8; 20], reverter.as_slice(), valueCall {}) {
+            Err(ForkerError::ExecutionReverted {
+                data,
+                reason,
+                gas_used,
+                exit_reason,
+            }) => {
+                assert!(data.is_empty());
+                assert_eq!(reason, None);
+                assert!(gas_used > 0);
+                assert_eq!(exit_reason, revm::interpreter::InstructionResult::Revert);
+            }
+            other => panic!("unexpected result {other:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_call_options_override_the_env() {
+        let target = Address::repeat_byte(0x11);
+        // returns CHAINID as a single word
+        let mut forked_evm = evm_with_code(&[(target, "4660005260206000f3")]).await;
+        let opts = CallOptions {
+            chain_id: Some(42),
+            ..Default::default()
+        };
+        let result = forked_evm
+            .call_with_opts(&[0u8; 20], target.as_slice(), &[], &opts)
+            .unwrap();
+        assert_eq!(U256::from_be_slice(&result.result), U256::from(42));
+        assert_eq!(forked_evm.executor.env.cfg.chain_id, 1);
+
+        // too little gas for the intrinsic cost
+        let opts = CallOptions {
+            gas_limit: Some(1_000),
+            ..Default::default()
+        };
+        let starved =
+            forked_evm.write_with_opts(&[0u8; 20], target.as_slice(), &[], U256::ZERO, &opts);
+        assert!(!matches!(starved, Ok(result) if !result.reverted));
+    }
+
+    #[tokio::test]
+    async fn test_call_options_override_the_block() {
+        let target = Address::repeat_byte(0x11);
+        let sender = Address::repeat_byte(0x22);
+        // returns NUMBER, TIMESTAMP, COINBASE and BASEFEE as four words
+        let code = "4360005242602052416040524860605260806000f3";
+        let mut forked_evm = evm_with_code(&[(target, code)]).await;
+        forked_evm.executor.backend.insert_account_info(
+            sender,
+            AccountInfo {
+                balance: U256::from(10).pow(U256::from(18)),
+                ..Default::default()
+            },
+        );
+        let coinbase = Address::repeat_byte(0x33);
+        let opts = CallOptions {
+            block_number: Some(U256::from(100)),
+            timestamp: Some(U256::from(1_700_000_000)),
+            coinbase: Some(coinbase),
+            basefee: Some(U256::from(7)),
+            gas_price: Some(U256::from(7)),
+            gas_limit: Some(100_000),
+            ..Default::default()
+        };
+        let result = forked_evm
+            .call_with_opts(sender.as_slice(), target.as_slice(), &[], &opts)
+            .unwrap();
+        let word = |i: usize| U256::from_be_slice(&result.result[i * 32..(i + 1) * 32]);
+        assert_eq!(word(0), U256::from(100));
+        assert_eq!(word(1), U256::from(1_700_000_000));
+        assert_eq!(word(2), U256::from_be_slice(coinbase.as_slice()));
+        assert_eq!(word(3), U256::from(7));
+        // the overrides are the call's own
+        assert_eq!(forked_evm.executor.env.block.number, U256::ZERO);
+        assert_eq!(forked_evm.executor.env.block.coinbase, Address::ZERO);
+    }
+
+    #[tokio::test]
+    async fn test_calls_carry_value_without_committing() {
+        let target = Address::repeat_byte(0x11);
+        let sender = Address::repeat_byte(0x22);
+        // returns CALLVALUE as a single word
+        let mut forked_evm = evm_with_code(&[(target, "3460005260206000f3")]).await;
+        forked_evm.executor.backend.insert_account_info(
+            sender,
+            AccountInfo {
+                balance: U256::from(10),
+                ..Default::default()
+            },
+        );
+        let opts = CallOptions {
+            value: Some(U256::from(5)),
+            ..Default::default()
+        };
+        let read = forked_evm
+            .alloy_read_with_opts(sender.as_slice(), target.as_slice(), valueCall {}, &opts)
+            .unwrap();
+        assert_eq!(read._0, U256::from(5));
+        let balance = forked_evm
+            .executor
+            .backend
+            .basic_ref(sender)
+            .unwrap()
+            .unwrap()
+            .balance;
+        assert_eq!(balance, U256::from(10));
     }
 }
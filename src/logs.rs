@@ -0,0 +1,210 @@
+use crate::{
+    receipt::{local_tx_hash, LocalTx},
+    ForkedEvm,
+};
+use alloy_sol_types::SolEvent;
+use foundry_evm::executors::RawCallResult;
+use revm::primitives::{Address, Log, B256, U256};
+#[cfg(feature = "native")]
+use tokio::sync::broadcast;
+
+/// Logs buffered per [ForkedEvm::subscribe_events] receiver.
+#[cfg(feature = "native")]
+pub const EVENT_CHANNEL_CAPACITY: usize = 1024;
+
+/// A log emitted by a transaction committed locally on the fork.
+#[derive(Debug, Clone)]
+pub struct LocalLog {
+    pub log: Log,
+    /// Local fork the transaction was committed on, if any.
+    pub fork_id: Option<U256>,
+    pub block_number: U256,
+    /// Index of the committing transaction among all local commits.
+    pub transaction_index: u64,
+    /// Index of the log among all local logs.
+    pub log_index: u64,
+}
+
+impl LocalLog {
+    /// Decodes the log as event `E`, `None` if it isn't one.
+    pub fn decode<E: SolEvent>(&self) -> Option<E> {
+        if !E::ANONYMOUS && self.log.topics.first() != Some(&E::SIGNATURE_HASH) {
+            return None;
+        }
+        E::decode_raw_log(self.log.topics.iter().copied(), &self.log.data, true).ok()
+    }
+}
+
+/// Selects logs by emitter and topics. A `None` matches anything, topics
+/// are matched positionally.
+#[derive(Debug, Clone, Default)]
+pub struct LogFilter {
+    pub address: Option<Address>,
+    pub topics: Vec<Option<B256>>,
+}
+
+impl LogFilter {
+    pub fn matches(&self, log: &Log) -> bool {
+        if self.address.is_some_and(|address| address != log.address) {
+            return false;
+        }
+        self.topics
+            .iter()
+            .enumerate()
+            .all(|(i, topic)| match topic {
+                Some(topic) => log.topics.get(i) == Some(topic),
+                None => true,
+            })
+    }
+}
+
+/// Lengths of the local log and commit record at a snapshot, to rewind them
+/// to when the snapshot is reverted.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct LocalMark {
+    logs: usize,
+    txs: usize,
+}
+
+impl ForkedEvm {
+    /// Logs emitted by locally committed transactions since the evm was
+    /// created, in emission order.
+    pub fn local_logs(&self, filter: &LogFilter) -> Vec<&LocalLog> {
+        self.local_logs
+            .iter()
+            .filter(|local_log| filter.matches(&local_log.log))
+            .collect()
+    }
+
+    /// Streams the logs of every subsequent local commit as it happens, e.g.
+    /// to drive a UI from simulated events. A receiver lagging more than
+    /// [EVENT_CHANNEL_CAPACITY] logs behind skips the oldest ones.
+    #[cfg(feature = "native")]
+    pub fn subscribe_events(&mut self) -> broadcast::Receiver<LocalLog> {
+        self.event_sender
+            .get_or_insert_with(|| broadcast::channel(EVENT_CHANNEL_CAPACITY).0)
+            .subscribe()
+    }
+
+    /// Records the logs and receipt data of a locally committed
+    /// transaction. Signed transactions pass their hash, other commits get a
+    /// local one, see [ForkedEvm::to_receipt].
+    pub(crate) fn record_commit(&mut self, result: &RawCallResult, hash: Option<B256>) {
+        if !result.reverted {
+            self.record_logs(&result.logs);
+        }
+        let transaction_index = self.local_txs.len() as u64;
+        let hash = hash.unwrap_or_else(|| local_tx_hash(transaction_index, &result.env));
+        let cumulative_gas_used =
+            self.local_block_gas_used(self.executor.env.block.number) + result.gas_used;
+        self.local_txs
+            .push(LocalTx::new(hash, result, cumulative_gas_used));
+    }
+
+    pub(crate) fn local_mark(&self) -> LocalMark {
+        LocalMark {
+            logs: self.local_logs.len(),
+            txs: self.local_txs.len(),
+        }
+    }
+
+    /// Forgets the logs and receipts of commits made since `mark`.
+    pub(crate) fn rewind_local(&mut self, mark: LocalMark) {
+        self.local_logs.truncate(mark.logs);
+        self.local_txs.truncate(mark.txs);
+    }
+
+    fn record_logs(&mut self, logs: &[Log]) {
+        let fork_id = self.executor.backend.active_fork_id();
+        for log in logs {
+            let local_log = LocalLog {
+                log: log.clone(),
+                fork_id,
+                block_number: self.executor.env.block.number,
+                transaction_index: self.local_txs.len() as u64,
+                log_index: self.local_logs.len() as u64,
+            };
+            // sending only fails once every receiver is dropped
+            #[cfg(feature = "native")]
+            if let Some(sender) = &self.event_sender {
+                let _ = sender.send(local_log.clone());
+            }
+            self.local_logs.push(local_log);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::LogFilter;
+    use crate::testing::evm_with_code;
+    use revm::primitives::{Address, B256, U256};
+
+    // PUSH1 1 PUSH1 0 PUSH1 0 LOG1 STOP, a log with topic 1
+    const LOG_CODE: &str = "600160006000a100";
+
+    #[tokio::test]
+    async fn test_writes_record_filtered_logs() {
+        let emitter = Address::repeat_byte(0x11);
+        let mut forked_evm = evm_with_code(&[(emitter, LOG_CODE)]).await;
+        forked_evm
+            .call(&[0u8; 20], emitter.as_slice(), &[])
+            .unwrap();
+        assert!(forked_evm.local_logs(&LogFilter::default()).is_empty());
+
+        for _ in 0..2 {
+            forked_evm
+                .write(&[0u8; 20], emitter.as_slice(), &[], U256::ZERO)
+                .unwrap();
+        }
+        let logs = forked_evm.local_logs(&LogFilter {
+            address: Some(emitter),
+            topics: vec![Some(B256::with_last_byte(1))],
+        });
+        assert_eq!(logs.len(), 2);
+        assert_eq!(logs[1].transaction_index, 1);
+        assert_eq!(logs[1].log_index, 1);
+        let other = LogFilter {
+            topics: vec![Some(B256::with_last_byte(2))],
+            ..Default::default()
+        };
+        assert!(forked_evm.local_logs(&other).is_empty());
+    }
+
+    #[cfg(feature = "native")]
+    #[tokio::test]
+    async fn test_subscribers_receive_committed_logs() {
+        let emitter = Address::repeat_byte(0x11);
+        let mut forked_evm = evm_with_code(&[(emitter, LOG_CODE)]).await;
+        let mut events = forked_evm.subscribe_events();
+        forked_evm
+            .call(&[0u8; 20], emitter.as_slice(), &[])
+            .unwrap();
+        assert!(events.try_recv().is_err());
+
+        forked_evm
+            .write(&[0u8; 20], emitter.as_slice(), &[], U256::ZERO)
+            .unwrap();
+        let event = events.try_recv().unwrap();
+        assert_eq!(event.log.address, emitter);
+        assert_eq!(event.log.topics, vec![B256::with_last_byte(1)]);
+        // clones don't stream to the original's subscribers
+        let mut copy = forked_evm.fork_clone();
+        copy.write(&[0u8; 20], emitter.as_slice(), &[], U256::ZERO)
+            .unwrap();
+        assert!(events.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_revert_drops_local_logs() {
+        let emitter = Address::repeat_byte(0x11);
+        let mut forked_evm = evm_with_code(&[(emitter, LOG_CODE)]).await;
+        let snapshot_id = forked_evm.snapshot();
+        forked_evm
+            .write(&[0u8; 20], emitter.as_slice(), &[], U256::ZERO)
+            .unwrap();
+        assert_eq!(forked_evm.local_logs(&LogFilter::default()).len(), 1);
+        assert!(forked_evm.revert(snapshot_id));
+        assert!(forked_evm.local_logs(&LogFilter::default()).is_empty());
+    }
+}
@@ -0,0 +1,144 @@
+use crate::ForkedEvm;
+use foundry_evm::executors::RawCallResult;
+use revm::primitives::{Address, Bytes, SpecId, U256};
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+};
+
+/// Memoized reads kept before the cache is cleared.
+const MAX_MEMOIZED_READS: usize = 10_000;
+
+/// Source of state versions, global so clones of an evm that diverge never
+/// share a version and can share one read cache.
+static NEXT_STATE_VERSION: AtomicU64 = AtomicU64::new(0);
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub(crate) struct ReadKey {
+    fork_id: Option<U256>,
+    block_number: U256,
+    timestamp: U256,
+    coinbase: Address,
+    basefee: U256,
+    chain_id: u64,
+    spec_id: SpecId,
+    state_version: u64,
+    from: Address,
+    to: Address,
+    calldata: Bytes,
+}
+
+pub(crate) type ReadCache = Arc<Mutex<HashMap<ReadKey, RawCallResult>>>;
+
+pub(crate) fn next_state_version() -> u64 {
+    NEXT_STATE_VERSION.fetch_add(1, Ordering::Relaxed)
+}
+
+impl ForkedEvm {
+    /// Memoizes [ForkedEvm::call] results until the next write, snapshot
+    /// revert, fork switch or roll. State changed by reaching into
+    /// `executor` directly isn't tracked, so disable memoization around it.
+    pub fn memoize_reads(&mut self, enabled: bool) {
+        self.read_cache = enabled.then(ReadCache::default);
+    }
+
+    /// Invalidates memoized reads, called on every change of the state.
+    pub(crate) fn bump_state_version(&mut self) {
+        self.state_version = next_state_version();
+    }
+
+    pub(crate) fn read_key(&self, from: Address, to: Address, calldata: &[u8]) -> ReadKey {
+        let env = &self.executor.env;
+        ReadKey {
+            fork_id: self.executor.backend.active_fork_id(),
+            block_number: env.block.number,
+            timestamp: env.block.timestamp,
+            coinbase: env.block.coinbase,
+            basefee: env.block.basefee,
+            chain_id: env.cfg.chain_id,
+            spec_id: env.cfg.spec_id,
+            state_version: self.state_version,
+            from,
+            to,
+            calldata: Bytes::from(calldata.to_vec()),
+        }
+    }
+
+    pub(crate) fn memoized_read(&self, key: &ReadKey) -> Option<RawCallResult> {
+        let read_cache = self.read_cache.as_ref()?;
+        read_cache.lock().unwrap().get(key).cloned()
+    }
+
+    pub(crate) fn memoize_read(&self, key: ReadKey, result: &RawCallResult) {
+        let Some(read_cache) = &self.read_cache else {
+            return;
+        };
+        let mut read_cache = read_cache.lock().unwrap();
+        if read_cache.len() >= MAX_MEMOIZED_READS {
+            read_cache.clear();
+        }
+        read_cache.insert(key, result.clone());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{testing::evm_with_code, ForkedEvm};
+    use revm::primitives::{Address, U256};
+
+    /// Evm with memoized reads and a contract running `prefix`, then
+    /// returning the word it left on the stack.
+    async fn evm_returning(prefix: &str) -> (ForkedEvm, Address) {
+        let contract = Address::repeat_byte(2);
+        // <prefix> PUSH1 0 MSTORE PUSH1 32 PUSH1 0 RETURN
+        let code = format!("{prefix}60005260206000f3");
+        let mut forked_evm = evm_with_code(&[(contract, &code)]).await;
+        forked_evm.memoize_reads(true);
+        (forked_evm, contract)
+    }
+
+    fn read_word(forked_evm: &ForkedEvm, contract: Address) -> U256 {
+        let result = forked_evm
+            .call(Address::repeat_byte(1).as_slice(), contract.as_slice(), &[])
+            .unwrap();
+        U256::from_be_slice(&result.result)
+    }
+
+    #[tokio::test]
+    async fn test_memoized_reads_follow_the_chain_id() {
+        // CHAINID
+        let (mut forked_evm, contract) = evm_returning("46").await;
+        forked_evm.executor.env.cfg.chain_id = 1;
+        assert_eq!(read_word(&forked_evm, contract), U256::from(1));
+        forked_evm.executor.env.cfg.chain_id = 10;
+        assert_eq!(read_word(&forked_evm, contract), U256::from(10));
+    }
+
+    #[tokio::test]
+    async fn test_memoized_reads_follow_the_basefee() {
+        // BASEFEE
+        let (mut forked_evm, contract) = evm_returning("48").await;
+        assert_eq!(read_word(&forked_evm, contract), U256::ZERO);
+        forked_evm.executor.env.block.basefee = U256::from(7);
+        assert_eq!(read_word(&forked_evm, contract), U256::from(7));
+    }
+
+    #[tokio::test]
+    async fn test_writes_invalidate_memoized_reads() {
+        // stores 7 at slot 0 when called with calldata, then loads slot 0
+        let (mut forked_evm, contract) = evm_returning("3615600a5760076000555b600054").await;
+        assert_eq!(read_word(&forked_evm, contract), U256::ZERO);
+        forked_evm
+            .write(
+                Address::repeat_byte(1).as_slice(),
+                contract.as_slice(),
+                &[1],
+                U256::ZERO,
+            )
+            .unwrap();
+        assert_eq!(read_word(&forked_evm, contract), U256::from(7));
+    }
+}
@@ -0,0 +1,173 @@
+use crate::{ForkedEvm, ForkerError};
+use foundry_evm::executors::RawCallResult;
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+/// Measurements of a single call or write.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CallMetrics {
+    pub duration: Duration,
+    /// Accounts and slots fetched from the fork's RPC during the call.
+    pub rpc_fetches: usize,
+    /// Accounts and slots the call loaded without a fetch, from the local
+    /// state or the fork's RPC cache.
+    pub cache_hits: usize,
+    pub committed: bool,
+    /// `None` if the call failed before producing a result.
+    pub reverted: Option<bool>,
+}
+
+/// Receives metrics from a [ForkedEvm], e.g. to forward them to a
+/// metrics exporter. Called inline, so implementations should be cheap.
+pub trait MetricsSink: Send + Sync {
+    fn on_call(&self, metrics: &CallMetrics);
+
+    /// Called whenever a fork is added, with the number of known forks.
+    fn on_fork_count(&self, _forks: usize) {}
+}
+
+/// Start of a measured call.
+pub(crate) struct CallProbe {
+    started: Instant,
+    fetched: (usize, usize),
+}
+
+impl ForkedEvm {
+    pub fn set_metrics(&mut self, metrics: Option<Arc<dyn MetricsSink>>) {
+        self.metrics = metrics;
+    }
+
+    pub(crate) fn start_probe(&self) -> Option<CallProbe> {
+        #[cfg(feature = "tracing")]
+        let traced = tracing::enabled!(tracing::Level::DEBUG);
+        #[cfg(not(feature = "tracing"))]
+        let traced = false;
+        if self.metrics.is_none() && !traced {
+            return None;
+        }
+        Some(CallProbe {
+            started: Instant::now(),
+            fetched: self.fetched_entries(),
+        })
+    }
+
+    pub(crate) fn finish_probe(
+        &self,
+        probe: Option<CallProbe>,
+        result: &Result<RawCallResult, ForkerError>,
+        committed: bool,
+    ) {
+        let Some(probe) = probe else {
+            return;
+        };
+        let duration = probe.started.elapsed();
+        let (accounts, slots) = self.fetched_entries();
+        let accounts_fetched = accounts.saturating_sub(probe.fetched.0);
+        let slots_fetched = slots.saturating_sub(probe.fetched.1);
+        #[cfg(feature = "tracing")]
+        tracing::debug!(?duration, accounts_fetched, slots_fetched, "call finished");
+        let Some(metrics) = &self.metrics else {
+            return;
+        };
+        let rpc_fetches = accounts_fetched + slots_fetched;
+        // the changeset holds every account and slot the call loaded, each
+        // either fetched during the call or already held somewhere
+        let loaded: usize = result
+            .as_ref()
+            .ok()
+            .and_then(|result| result.state_changeset.as_ref())
+            .map(|state| {
+                state
+                    .values()
+                    .map(|account| 1 + account.storage.len())
+                    .sum()
+            })
+            .unwrap_or_default();
+        metrics.on_call(&CallMetrics {
+            duration,
+            rpc_fetches,
+            cache_hits: loaded.saturating_sub(rpc_fetches),
+            committed,
+            reverted: result.as_ref().ok().map(|result| result.reverted),
+        });
+    }
+
+    pub(crate) fn report_fork_count(&self) {
+        if let Some(metrics) = &self.metrics {
+            metrics.on_fork_count(self.forks.len());
+        }
+    }
+
+    /// Runs a single read of the fork's state, with the `tracing` feature
+    /// reporting what it fetched from the fork's RPC.
+    pub(crate) fn traced_read<T>(&self, read: impl FnOnce() -> T) -> T {
+        #[cfg(feature = "tracing")]
+        if tracing::enabled!(tracing::Level::TRACE) {
+            let before = self.fetched_entries();
+            let result = read();
+            let after = self.fetched_entries();
+            tracing::trace!(
+                accounts_fetched = after.0.saturating_sub(before.0),
+                slots_fetched = after.1.saturating_sub(before.1),
+                "read state"
+            );
+            return result;
+        }
+        read()
+    }
+
+    /// Accounts and slots the active fork's backend has fetched from its
+    /// RPC. Its cache only grows by fetches, so this counts map entries
+    /// without walking cached slots or code.
+    pub(crate) fn fetched_entries(&self) -> (usize, usize) {
+        let Ok(db) = self.fork_backend() else {
+            return (0, 0);
+        };
+        let data = db.data();
+        let accounts = data.accounts.read().len();
+        let slots = data.storage.read().values().map(|slots| slots.len()).sum();
+        (accounts, slots)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CallMetrics, MetricsSink};
+    use crate::testing::{evm_with_code, returning_code};
+    use revm::primitives::Address;
+    use std::sync::{Arc, Mutex};
+
+    #[derive(Default)]
+    struct Recorder(Mutex<Vec<CallMetrics>>);
+
+    impl MetricsSink for Recorder {
+        fn on_call(&self, metrics: &CallMetrics) {
+            self.0.lock().unwrap().push(*metrics);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_calls_are_reported() {
+        let target = Address::repeat_byte(0x22);
+        let mut forked_evm = evm_with_code(&[(target, &returning_code(&[1]))]).await;
+        let recorder = Arc::new(Recorder::default());
+        forked_evm.set_metrics(Some(recorder.clone()));
+
+        forked_evm
+            .call(Address::ZERO.as_slice(), target.as_slice(), &[])
+            .unwrap();
+        forked_evm.set_metrics(None);
+        forked_evm
+            .call(Address::ZERO.as_slice(), target.as_slice(), &[])
+            .unwrap();
+
+        let calls = recorder.0.lock().unwrap();
+        assert_eq!(calls.len(), 1);
+        assert_eq!(calls[0].rpc_fetches, 0);
+        assert!(calls[0].cache_hits >= 2);
+        assert!(!calls[0].committed);
+        assert_eq!(calls[0].reverted, Some(false));
+    }
+}
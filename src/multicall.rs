@@ -0,0 +1,136 @@
+use crate::{ForkedEvm, ForkerError};
+use alloy_sol_types::{sol, SolCall};
+use revm::{
+    primitives::{address, Address, Bytes, KECCAK_EMPTY},
+    DatabaseRef,
+};
+
+/// Canonical Multicall3 deployment, present at the same address on most chains.
+pub const MULTICALL3_ADDRESS: Address = address!("cA11bde05977b3631167028862bE2a173976CA11");
+
+sol! {
+    struct Call3 {
+        address target;
+        bool allowFailure;
+        bytes callData;
+    }
+
+    struct Call3Result {
+        bool success;
+        bytes returnData;
+    }
+
+    function aggregate3(Call3[] calldata calls) external payable returns (Call3Result[] memory returnData);
+}
+
+impl ForkedEvm {
+    /// Executes many typed reads in a single evm pass through the fork's
+    /// Multicall3 deployment, falling back to sequential reads when Multicall3
+    /// is not deployed on the active fork. Inner calls see Multicall3 as
+    /// `msg.sender` when aggregated.
+    pub fn alloy_multiread<C: SolCall>(
+        &self,
+        from_address: &[u8],
+        calls: Vec<(Address, C)>,
+    ) -> Result<Vec<Result<C::Return, ForkerError>>, ForkerError> {
+        let has_multicall = self
+            .executor
+            .backend
+            .basic_ref(MULTICALL3_ADDRESS)?
+            .is_some_and(|account| account.code_hash != KECCAK_EMPTY);
+        if !has_multicall {
+            return Ok(calls
+                .into_iter()
+                .map(|(target, call)| self.alloy_read(from_address, target.as_slice(), call))
+                .collect());
+        }
+
+        let aggregate = aggregate3Call {
+            calls: calls
+                .into_iter()
+                .map(|(target, call)| Call3 {
+                    target,
+                    allowFailure: true,
+                    callData: Bytes::from(call.abi_encode()),
+                })
+                .collect(),
+        };
+        let results = self
+            .alloy_read(from_address, MULTICALL3_ADDRESS.as_slice(), aggregate)?
+            .returnData;
+        Ok(results
+            .into_iter()
+            .map(|result| {
+                if !result.success {
+                    return Err(ForkerError::MulticallReverted {
+                        reason: alloy_sol_types::decode_revert_reason(&result.returnData),
+                        data: result.returnData,
+                    });
+                }
+                Ok(C::abi_decode_returns(&result.returnData, true)?)
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{evm_with_code, returning_code};
+    use alloy_sol_types::{sol_data, SolType};
+    use revm::primitives::U256;
+
+    sol! {
+        function value() external view returns (uint256);
+    }
+
+    // returns 1 + 2 as a single word
+    const ADD_CODE: &str = "600160020160005260206000f3";
+    const REVERT_CODE: &str = "60006000fd";
+
+    #[tokio::test]
+    async fn test_multiread_falls_back_to_sequential_reads() {
+        let ok = Address::repeat_byte(0x11);
+        let reverts = Address::repeat_byte(0x22);
+        let forked_evm = evm_with_code(&[(ok, ADD_CODE), (reverts, REVERT_CODE)]).await;
+
+        let results = forked_evm
+            .alloy_multiread(
+                &[0u8; 20],
+                vec![(ok, valueCall {}), (reverts, valueCall {})],
+            )
+            .unwrap();
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].as_ref().unwrap()._0, U256::from(3));
+        assert!(results[1].is_err());
+    }
+
+    #[tokio::test]
+    async fn test_multiread_aggregates_through_multicall3() {
+        let returned = <(sol_data::Array<Call3Result>,)>::abi_encode_params(&(vec![
+            Call3Result {
+                success: true,
+                returnData: U256::from(7).to_be_bytes::<32>().to_vec().into(),
+            },
+            Call3Result {
+                success: false,
+                returnData: Bytes::new(),
+            },
+        ],));
+        let multicall_code = returning_code(&returned);
+        let forked_evm = evm_with_code(&[(MULTICALL3_ADDRESS, &multicall_code)]).await;
+
+        // the targets have no code, so only the aggregated path can produce 7
+        let results = forked_evm
+            .alloy_multiread(
+                &[0u8; 20],
+                vec![
+                    (Address::repeat_byte(0x11), valueCall {}),
+                    (Address::repeat_byte(0x22), valueCall {}),
+                ],
+            )
+            .unwrap();
+        assert_eq!(results[0].as_ref().unwrap()._0, U256::from(7));
+        assert!(results[1].is_err());
+    }
+}
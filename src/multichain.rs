@@ -0,0 +1,278 @@
+use crate::{logs::LogFilter, ForkedEvm, ForkerError};
+use foundry_evm::{executors::RawCallResult, fork::ForkId};
+use revm::primitives::{Address, Bytes, Log, U256};
+use std::{
+    collections::{BTreeMap, HashMap, VecDeque},
+    sync::Arc,
+};
+
+/// Relay rounds [MultiChainSession::deliver_messages] runs before giving up
+/// on messages that keep triggering further messages.
+const MAX_RELAY_ROUNDS: usize = 64;
+
+/// Call a relay delivers on the destination chain.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RelayedCall {
+    pub from: Address,
+    pub to: Address,
+    pub calldata: Bytes,
+    pub value: U256,
+}
+
+/// Turns a captured log into the call delivering it, `None` to skip it.
+pub type RelayHandler = Arc<dyn Fn(&Log) -> Option<RelayedCall> + Send + Sync>;
+
+/// Message captured on one chain, waiting for delivery on another.
+#[derive(Debug, Clone)]
+pub struct PendingMessage {
+    pub source_chain: String,
+    pub destination_chain: String,
+    /// Log the message was captured from.
+    pub log: Log,
+    pub call: RelayedCall,
+}
+
+/// Outcome of delivering a [PendingMessage].
+#[derive(Debug)]
+pub struct Delivery {
+    pub message: PendingMessage,
+    pub result: Result<RawCallResult, ForkerError>,
+}
+
+struct Relay {
+    source_chain: String,
+    destination_chain: String,
+    filter: LogFilter,
+    handler: RelayHandler,
+}
+
+/// Several chains forked side by side in one evm and addressed by name,
+/// with relays carrying bridge style messages between them: a log emitted
+/// by a commit on one chain, whether a write, a deploy or anything done
+/// through [MultiChainSession::chain], is captured and delivered as a call
+/// on another.
+pub struct MultiChainSession {
+    evm: ForkedEvm,
+    chains: BTreeMap<String, ForkId>,
+    relays: Vec<Relay>,
+    pending: VecDeque<PendingMessage>,
+    /// Local logs of the evm already checked for messages.
+    captured_logs: usize,
+}
+
+impl MultiChainSession {
+    pub fn new(evm: ForkedEvm) -> Self {
+        Self {
+            evm,
+            chains: BTreeMap::new(),
+            relays: vec![],
+            pending: VecDeque::new(),
+            captured_logs: 0,
+        }
+    }
+
+    /// Forks `fork_url` at `fork_block_number` as chain `name`.
+    pub async fn add_chain(
+        &mut self,
+        name: &str,
+        fork_url: &str,
+        fork_block_number: Option<u64>,
+    ) -> Result<(), ForkerError> {
+        self.evm
+            .add_or_select(fork_url, fork_block_number, None)
+            .await?;
+        let active = self
+            .evm
+            .active_fork()
+            .map(|info| info.local_id)
+            .ok_or(ForkerError::NoActiveFork)?;
+        let fork_id = self
+            .evm
+            .forks()
+            .find(|(_, info)| info.local_id == active)
+            .map(|(fork_id, _)| fork_id.clone())
+            .ok_or(ForkerError::NoActiveFork)?;
+        self.chains.insert(name.to_string(), fork_id);
+        Ok(())
+    }
+
+    pub fn chains(&self) -> impl Iterator<Item = &str> {
+        self.chains.keys().map(String::as_str)
+    }
+
+    /// The evm with chain `name` selected, e.g. for cheats or anything
+    /// else not routed by the session.
+    pub fn chain(&mut self, name: &str) -> Result<&mut ForkedEvm, ForkerError> {
+        self.capture_messages();
+        let fork_id = self
+            .chains
+            .get(name)
+            .ok_or_else(|| ForkerError::UnknownChain(name.to_string()))?;
+        self.evm.select(fork_id)?;
+        Ok(&mut self.evm)
+    }
+
+    /// Relays logs matching `filter` committed on `source_chain` to
+    /// `destination_chain`, as the calls `handler` maps them to.
+    pub fn add_relay(
+        &mut self,
+        source_chain: &str,
+        destination_chain: &str,
+        filter: LogFilter,
+        handler: impl Fn(&Log) -> Option<RelayedCall> + Send + Sync + 'static,
+    ) {
+        self.relays.push(Relay {
+            source_chain: source_chain.to_string(),
+            destination_chain: destination_chain.to_string(),
+            filter,
+            handler: Arc::new(handler),
+        });
+    }
+
+    pub fn call(
+        &mut self,
+        chain: &str,
+        from_address: &[u8],
+        to_address: &[u8],
+        calldata: &[u8],
+    ) -> Result<RawCallResult, ForkerError> {
+        self.chain(chain)?.call(from_address, to_address, calldata)
+    }
+
+    /// Commits a write on `chain` and queues the messages its logs trigger
+    /// for [MultiChainSession::deliver_messages].
+    pub fn write(
+        &mut self,
+        chain: &str,
+        from_address: &[u8],
+        to_address: &[u8],
+        calldata: &[u8],
+        value: U256,
+    ) -> Result<RawCallResult, ForkerError> {
+        let result = self
+            .chain(chain)?
+            .write(from_address, to_address, calldata, value);
+        self.capture_messages();
+        result
+    }
+
+    /// Deploys `bytecode` on `chain` and queues the messages its
+    /// constructor's logs trigger.
+    pub fn deploy(
+        &mut self,
+        chain: &str,
+        from_address: &[u8],
+        bytecode: &[u8],
+        value: U256,
+    ) -> Result<Address, ForkerError> {
+        let address = self.chain(chain)?.deploy(from_address, bytecode, value);
+        self.capture_messages();
+        address
+    }
+
+    /// Messages captured but not delivered yet, in capture order.
+    pub fn pending_messages(&mut self) -> impl Iterator<Item = &PendingMessage> {
+        self.capture_messages();
+        self.pending.iter()
+    }
+
+    /// Delivers pending messages in capture order, including the ones the
+    /// deliveries themselves trigger, until none are left. Fails with
+    /// [ForkerError::RelayLimitReached], holding the deliveries made, when
+    /// messages keep triggering further ones; those still pending stay
+    /// queued.
+    pub fn deliver_messages(&mut self) -> Result<Vec<Delivery>, ForkerError> {
+        self.capture_messages();
+        let mut deliveries = vec![];
+        for round in 0.. {
+            if self.pending.is_empty() {
+                break;
+            }
+            if round == MAX_RELAY_ROUNDS {
+                return Err(ForkerError::RelayLimitReached {
+                    rounds: MAX_RELAY_ROUNDS,
+                    deliveries,
+                });
+            }
+            let round: Vec<_> = self.pending.drain(..).collect();
+            for message in round {
+                let call = &message.call;
+                let result = self.write(
+                    &message.destination_chain,
+                    call.from.as_slice(),
+                    call.to.as_slice(),
+                    &call.calldata,
+                    call.value,
+                );
+                deliveries.push(Delivery { message, result });
+            }
+        }
+        Ok(deliveries)
+    }
+
+    pub fn into_evm(self) -> ForkedEvm {
+        self.evm
+    }
+
+    /// Queues the messages of the logs the evm recorded since the last
+    /// capture, so commits made any way on a chain are relayed.
+    fn capture_messages(&mut self) {
+        let chain_names: HashMap<U256, &str> = self
+            .chains
+            .iter()
+            .filter_map(|(name, fork_id)| {
+                let info = self.evm.forks.get(fork_id)?;
+                Some((info.local_id, name.as_str()))
+            })
+            .collect();
+        // reverting a snapshot drops the logs committed since
+        let start = self.captured_logs.min(self.evm.local_logs.len());
+        for local_log in &self.evm.local_logs[start..] {
+            let Some(&chain) = local_log
+                .fork_id
+                .and_then(|local_id| chain_names.get(&local_id))
+            else {
+                continue;
+            };
+            let log = &local_log.log;
+            for relay in self
+                .relays
+                .iter()
+                .filter(|relay| relay.source_chain == chain && relay.filter.matches(log))
+            {
+                if let Some(call) = (relay.handler)(log) {
+                    self.pending.push_back(PendingMessage {
+                        source_chain: chain.to_string(),
+                        destination_chain: relay.destination_chain.clone(),
+                        log: log.clone(),
+                        call,
+                    });
+                }
+            }
+        }
+        self.captured_logs = self.evm.local_logs.len();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::MultiChainSession;
+    use crate::{logs::LogFilter, testing::evm_with_code, ForkerError};
+    use revm::primitives::{Address, U256};
+
+    #[tokio::test]
+    async fn test_sessions_route_by_known_chains_only() {
+        let mut session = MultiChainSession::new(evm_with_code(&[]).await);
+        session.add_relay("a", "b", LogFilter::default(), |_| None);
+        assert!(matches!(
+            session.call("a", Address::ZERO.as_slice(), Address::ZERO.as_slice(), &[]),
+            Err(ForkerError::UnknownChain(chain)) if chain == "a"
+        ));
+        assert!(matches!(
+            session.deploy("b", Address::ZERO.as_slice(), &[], U256::ZERO),
+            Err(ForkerError::UnknownChain(_))
+        ));
+        assert_eq!(session.pending_messages().count(), 0);
+        assert!(session.deliver_messages().unwrap().is_empty());
+    }
+}
@@ -0,0 +1,74 @@
+use crate::{ForkedEvm, ForkerError};
+use revm::primitives::{Address, Env};
+
+impl ForkedEvm {
+    /// Next nonce of `address` on the active fork, i.e. the nonce its next
+    /// write will be sent with.
+    pub fn get_nonce(&self, address: Address) -> Result<u64, ForkerError> {
+        self.nonce_of(address)
+    }
+
+    /// Overwrites the nonce of `address`, keeping its balance, code and
+    /// storage, so later writes and deploys continue from `nonce`.
+    pub fn set_nonce(&mut self, address: Address, nonce: u64) -> Result<(), ForkerError> {
+        let balance = self.balance_of(address)?;
+        let code = self.code_at(address)?;
+        self.insert_account(address, balance, nonce, code, [])
+    }
+
+    /// Sends the tx with the caller's current nonce unless one was set
+    /// explicitly, so nonce checks and CREATE addresses match a real chain.
+    pub(crate) fn assign_nonce(&self, env: &mut Env) -> Result<(), ForkerError> {
+        if env.tx.nonce.is_none() {
+            env.tx.nonce = Some(self.nonce_of(env.tx.caller)?);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{testing::evm_with_code, CallOptions};
+    use revm::primitives::{Address, U256};
+
+    #[tokio::test]
+    async fn test_deploys_follow_the_set_nonce() {
+        let sender = Address::repeat_byte(1);
+        let mut forked_evm = evm_with_code(&[]).await;
+        forked_evm.set_nonce(sender, 5).unwrap();
+        // empty init code deploys an empty contract
+        let first = forked_evm
+            .deploy(sender.as_slice(), &[], U256::ZERO)
+            .unwrap();
+        let second = forked_evm
+            .deploy(sender.as_slice(), &[], U256::ZERO)
+            .unwrap();
+        assert_eq!(first, sender.create(5));
+        assert_eq!(second, sender.create(6));
+        assert_eq!(forked_evm.get_nonce(sender).unwrap(), 7);
+    }
+
+    #[tokio::test]
+    async fn test_writes_with_an_origin_use_the_origin_nonce() {
+        let sender = Address::repeat_byte(1);
+        let origin = Address::repeat_byte(2);
+        let mut forked_evm = evm_with_code(&[]).await;
+        forked_evm.set_nonce(origin, 5).unwrap();
+        let opts = CallOptions {
+            origin: Some(origin),
+            ..Default::default()
+        };
+        let result = forked_evm
+            .write_with_opts(
+                sender.as_slice(),
+                Address::repeat_byte(3).as_slice(),
+                &[],
+                U256::ZERO,
+                &opts,
+            )
+            .unwrap();
+        assert!(!result.reverted);
+        assert_eq!(forked_evm.get_nonce(origin).unwrap(), 6);
+        assert_eq!(forked_evm.get_nonce(sender).unwrap(), 0);
+    }
+}
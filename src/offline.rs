@@ -0,0 +1,187 @@
+use crate::{
+    forks::{copy_fetched_state, spawn_fork_backend},
+    ForkedEvm, ForkerError,
+};
+use alloy_json_rpc::RpcError;
+use alloy_transport::TransportError;
+use ethers::providers::JsonRpcError;
+use foundry_evm::{backend::DatabaseError, fork::ForkId};
+use revm::primitives::EVMError;
+use std::{error::Error, sync::atomic::Ordering};
+
+/// JSON-RPC error code relays answer with while offline, so the fork
+/// backend's errors caused by it can be told apart from the RPC's own.
+pub(crate) const OFFLINE_CODE: i64 = -32_098;
+
+/// Error response of a request refused while offline.
+pub(crate) fn offline_response() -> JsonRpcError {
+    JsonRpcError {
+        code: OFFLINE_CODE,
+        message: "offline, state not cached".to_string(),
+        data: None,
+    }
+}
+
+/// Whether `error` or one of its causes is a request a relay refused while
+/// offline.
+pub(crate) fn refused_offline(error: &(dyn Error + 'static)) -> bool {
+    let mut cause = Some(error);
+    while let Some(error) = cause {
+        let refused =
+            if let Some(RpcError::ErrorResp(payload)) = error.downcast_ref::<TransportError>() {
+                payload.code == OFFLINE_CODE
+            } else if let Some(error) = error.downcast_ref::<DatabaseError>() {
+                database_refused(error)
+            } else if let Some(EVMError::Database(error)) =
+                error.downcast_ref::<EVMError<DatabaseError>>()
+            {
+                database_refused(error)
+            } else {
+                false
+            };
+        if refused {
+            return true;
+        }
+        cause = error.source();
+    }
+    false
+}
+
+/// Reads of the fork backend keep their RPC error out of the source chain.
+fn database_refused(error: &DatabaseError) -> bool {
+    match error {
+        DatabaseError::GetAccount(_, error)
+        | DatabaseError::GetStorage(_, _, error)
+        | DatabaseError::GetBlockHash(_, error) => refused_offline(&***error),
+        _ => false,
+    }
+}
+
+impl ForkedEvm {
+    /// Strict offline mode: state missing from the forks' RPC caches fails
+    /// with [ForkerError::MissingState] instead of being fetched, e.g. for
+    /// hermetic test runs on a cache restored from
+    /// [crate::cache::CacheConfig::dir]. Going offline moves every fork that
+    /// talks to its RPC directly behind a relay, keeping its id and what it
+    /// fetched so far. New forks can't be created while offline. Clones
+    /// share the mode, as they share the relays.
+    pub async fn set_offline(&mut self, offline: bool) -> Result<(), ForkerError> {
+        if offline {
+            let direct: Vec<ForkId> = self
+                .forks
+                .iter()
+                .filter(|(_, info)| info.relay.is_none())
+                .map(|(fork_id, _)| fork_id.clone())
+                .collect();
+            for fork_id in direct {
+                self.relay_fork(&fork_id).await?;
+            }
+        }
+        self.offline.store(offline, Ordering::Relaxed);
+        Ok(())
+    }
+
+    pub fn is_offline(&self) -> bool {
+        self.offline.load(Ordering::Relaxed)
+    }
+
+    /// Serves a fork through a relay of its url from here on, swapping its
+    /// backend for one talking to the relay that starts with everything the
+    /// previous one fetched. Evicted forks are rebuilt from the relay when
+    /// selected again.
+    async fn relay_fork(&mut self, fork_id: &ForkId) -> Result<(), ForkerError> {
+        let Some(info) = self.forks.get(fork_id) else {
+            return Ok(());
+        };
+        let upstream = self.configured_upstream(vec![info.url.clone()]);
+        let relay = self.relay_for(upstream).await?;
+        let Some(info) = self.forks.get_mut(fork_id) else {
+            return Ok(());
+        };
+        info.url = relay.url.clone();
+        info.relay = Some(relay);
+        let Some(previous) = info.db.clone() else {
+            return Ok(());
+        };
+        let backend = spawn_fork_backend(info, false)?;
+        // state at the same block is the same whoever serves it
+        copy_fetched_state(&previous, &backend);
+        let local_id = info.local_id;
+        self.replace_fork_backend(local_id, backend.clone())?;
+        if let Some(info) = self.forks.get_mut(fork_id) {
+            info.db = Some(backend);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{refused_offline, OFFLINE_CODE};
+    use crate::{
+        failover::{FallbackClient, FallbackError},
+        testing::{evm_with_code, fixed_relay},
+        transport::{RelayPolicy, RpcRelay, Upstream},
+        ForkerError,
+    };
+    use alloy_json_rpc::ErrorPayload;
+    use alloy_transport::TransportError;
+    use ethers::providers::{Http, HttpClientError, JsonRpcClient};
+    use foundry_evm::backend::DatabaseError;
+    use revm::primitives::Address;
+    use serde_json::json;
+    use std::{
+        str::FromStr,
+        sync::{atomic::Ordering, Arc},
+    };
+
+    #[tokio::test]
+    async fn test_offline_relays_refuse_with_the_offline_code() {
+        let rpc = fixed_relay(Ok(json!("0x1"))).await;
+        let policy = RelayPolicy::default();
+        policy.offline.store(true, Ordering::Relaxed);
+        let upstream = Upstream::from(rpc.url.as_str());
+        let client = FallbackClient::connect(&upstream, Default::default(), policy.clone())
+            .await
+            .unwrap();
+        assert!(matches!(
+            client.request::<_, String>("eth_chainId", ()).await,
+            Err(FallbackError::Offline(error)) if error.code == OFFLINE_CODE
+        ));
+
+        let relay = RpcRelay::connect(upstream, policy.clone()).await.unwrap();
+        let client = Http::from_str(&relay.url).unwrap();
+        match client.request::<_, String>("eth_chainId", ()).await {
+            Err(HttpClientError::JsonRpcError(error)) => assert_eq!(error.code, OFFLINE_CODE),
+            other => panic!("expected the offline code, got {other:?}"),
+        }
+        policy.offline.store(false, Ordering::Relaxed);
+        let chain_id: String = client.request("eth_chainId", ()).await.unwrap();
+        assert_eq!(chain_id, "0x1");
+    }
+
+    #[tokio::test]
+    async fn test_refused_reads_are_missing_state() {
+        let refusal = |code: i64| {
+            let payload: ErrorPayload =
+                serde_json::from_value(json!({ "code": code, "message": "refused" })).unwrap();
+            let error = eyre::Report::new(TransportError::ErrorResp(payload));
+            DatabaseError::GetAccount(Address::ZERO, Arc::new(error))
+        };
+        assert!(refused_offline(&refusal(OFFLINE_CODE)));
+        assert!(matches!(
+            ForkerError::from(refusal(OFFLINE_CODE)),
+            ForkerError::MissingState(_)
+        ));
+        // the rpc's own errors are passed on as they are
+        assert!(matches!(
+            ForkerError::from(refusal(-32_000)),
+            ForkerError::Database(_)
+        ));
+
+        let mut forked_evm = evm_with_code(&[]).await;
+        forked_evm.set_offline(true).await.unwrap();
+        assert!(forked_evm.is_offline());
+        assert!(forked_evm.clone().is_offline());
+    }
+}
@@ -0,0 +1,91 @@
+use crate::{ForkedEvm, ForkerError};
+use foundry_evm::executors::RawCallResult;
+use revm::primitives::{Address, Env};
+
+impl ForkedEvm {
+    /// `tx.origin` applied to every call and write that doesn't set
+    /// [crate::CallOptions::origin], `None` to use the sender.
+    pub fn set_default_origin(&mut self, origin: Option<Address>) {
+        self.default_origin = origin;
+    }
+
+    pub fn default_origin(&self) -> Option<Address> {
+        self.default_origin
+    }
+
+    /// Executes the tx described by `env` without committing it, with
+    /// `origin` as `tx.origin` and `env.tx.caller` as `msg.sender`.
+    pub(crate) fn call_as_origin(
+        &self,
+        mut env: Env,
+        origin: Address,
+    ) -> Result<RawCallResult, ForkerError> {
+        match swap_origin(&mut env, origin) {
+            Some(sender) => self.call_inspected(env, Some(sender)),
+            None => self.call_with_env(env),
+        }
+    }
+
+    /// Like [ForkedEvm::call_as_origin] but commits the state changes. The
+    /// origin sends the tx, so its nonce is assigned unless one is set.
+    pub(crate) fn commit_as_origin(
+        &mut self,
+        mut env: Env,
+        origin: Address,
+    ) -> Result<RawCallResult, ForkerError> {
+        let sender = swap_origin(&mut env, origin);
+        self.assign_nonce(&mut env)?;
+        match sender {
+            Some(sender) => self.commit_inspected(env, Some(sender), None),
+            None => self.commit_with_env(env),
+        }
+    }
+}
+
+/// Makes `origin` the account sending the tx described by `env`, returning
+/// the caller it replaced, to run as the top level `msg.sender`, if it
+/// differs.
+pub(crate) fn swap_origin(env: &mut Env, origin: Address) -> Option<Address> {
+    (origin != env.tx.caller).then(|| std::mem::replace(&mut env.tx.caller, origin))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{testing::evm_with_code, CallOptions};
+    use revm::primitives::{Address, U256};
+
+    // returns tx.origin and msg.sender
+    const ORIGIN_CALLER_CODE: &str = "326000523360205260406000f3";
+
+    #[tokio::test]
+    async fn test_origin_differs_from_sender() {
+        let target = Address::repeat_byte(0x11);
+        let sender = Address::repeat_byte(0x22);
+        let origin = Address::repeat_byte(0x33);
+        let mut forked_evm = evm_with_code(&[(target, ORIGIN_CALLER_CODE)]).await;
+        let opts = CallOptions {
+            origin: Some(origin),
+            ..Default::default()
+        };
+
+        let result = forked_evm
+            .call_with_opts(sender.as_slice(), target.as_slice(), &[], &opts)
+            .unwrap();
+        assert_eq!(&result.result[12..32], origin.as_slice());
+        assert_eq!(&result.result[44..64], sender.as_slice());
+
+        forked_evm.set_default_origin(Some(origin));
+        let result = forked_evm
+            .write(sender.as_slice(), target.as_slice(), &[], U256::ZERO)
+            .unwrap();
+        assert!(!result.reverted);
+        assert_eq!(&result.result[12..32], origin.as_slice());
+        assert_eq!(&result.result[44..64], sender.as_slice());
+
+        forked_evm.set_default_origin(None);
+        let result = forked_evm
+            .call(sender.as_slice(), target.as_slice(), &[])
+            .unwrap();
+        assert_eq!(&result.result[12..32], sender.as_slice());
+    }
+}
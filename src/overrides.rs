@@ -0,0 +1,174 @@
+use crate::{ForkedEvm, ForkerError};
+use revm::{
+    db::AccountState,
+    primitives::{alloy_primitives::U64, Address, Bytes, B256, U256},
+};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// `eth_call` state override set, keyed by account.
+pub type StateOverride = BTreeMap<Address, AccountOverride>;
+
+/// Per account entry of an `eth_call` state override.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AccountOverride {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub balance: Option<U256>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub nonce: Option<U64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub code: Option<Bytes>,
+    /// Replaces the account's whole storage.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub state: Option<BTreeMap<B256, B256>>,
+    /// Replaces individual slots, leaving the rest untouched.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub state_diff: Option<BTreeMap<B256, B256>>,
+}
+
+/// Local changes to one account relative to the state fetched from the
+/// fork's RPC. Unchanged fields are `None`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ModifiedAccount {
+    pub balance: Option<U256>,
+    pub nonce: Option<u64>,
+    pub code: Option<Bytes>,
+    /// Whether the account's whole storage was replaced locally, e.g. by a
+    /// selfdestruct, in which case `slots` holds all of its storage.
+    pub storage_cleared: bool,
+    /// Slots whose local values differ from upstream.
+    pub slots: BTreeMap<U256, U256>,
+}
+
+impl ForkedEvm {
+    /// Accounts and slots of the active fork that differ from the state
+    /// fetched from its RPC, with their local values. Empty while the fork
+    /// is clean. Without a fork every local account is listed.
+    pub fn modified_accounts(&self) -> BTreeMap<Address, ModifiedAccount> {
+        let remote = self.fork_backend().ok().map(|db| db.data());
+        let remote_accounts = remote.as_ref().map(|data| data.accounts.read());
+        let remote_storage = remote.as_ref().map(|data| data.storage.read());
+
+        let mut modified = BTreeMap::new();
+        for (address, account) in self.local_accounts() {
+            if account.account_state == AccountState::NotExisting {
+                continue;
+            }
+            let remote_info = remote_accounts
+                .as_ref()
+                .and_then(|accounts| accounts.get(address));
+            let remote_slots = remote_storage
+                .as_ref()
+                .and_then(|storage| storage.get(address));
+            let info = &account.info;
+            let storage_cleared = account.account_state == AccountState::StorageCleared;
+
+            let modified_account = ModifiedAccount {
+                balance: (remote_info.map(|remote| remote.balance) != Some(info.balance))
+                    .then_some(info.balance),
+                nonce: (remote_info.map(|remote| remote.nonce) != Some(info.nonce))
+                    .then_some(info.nonce),
+                code: (remote_info.map(|remote| remote.code_hash) != Some(info.code_hash)).then(
+                    || {
+                        info.code
+                            .as_ref()
+                            .map(|code| code.original_bytes())
+                            .unwrap_or_default()
+                    },
+                ),
+                storage_cleared,
+                slots: account
+                    .storage
+                    .iter()
+                    .filter(|(slot, value)| {
+                        storage_cleared
+                            || remote_slots.and_then(|slots| slots.get(*slot)) != Some(*value)
+                    })
+                    .map(|(slot, value)| (*slot, *value))
+                    .collect(),
+            };
+            if modified_account != ModifiedAccount::default() {
+                modified.insert(*address, modified_account);
+            }
+        }
+        modified
+    }
+
+    /// Local modifications of the active fork relative to the state fetched
+    /// from its RPC, as a state override that reproduces them against a
+    /// real node, e.g. `serde_json::to_value(forked_evm.export_overrides()?)`.
+    /// Without a fork every local account is exported.
+    pub fn export_overrides(&self) -> Result<StateOverride, ForkerError> {
+        Ok(self
+            .modified_accounts()
+            .into_iter()
+            .map(|(address, account)| {
+                let slots = account
+                    .slots
+                    .into_iter()
+                    .map(|(slot, value)| (B256::from(slot), B256::from(value)))
+                    .collect::<BTreeMap<_, _>>();
+                let (state, state_diff) = if account.storage_cleared {
+                    (Some(slots), None)
+                } else {
+                    (None, (!slots.is_empty()).then_some(slots))
+                };
+                let account_override = AccountOverride {
+                    balance: account.balance,
+                    nonce: account.nonce.map(U64::from),
+                    code: account.code,
+                    state,
+                    state_diff,
+                };
+                (address, account_override)
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{AccountOverride, ModifiedAccount};
+    use crate::testing::evm_with_code;
+    use revm::primitives::{Address, Bytes, B256, U256};
+
+    #[tokio::test]
+    async fn test_export_overrides_without_fork() {
+        let address = Address::repeat_byte(0x11);
+        let mut forked_evm = evm_with_code(&[]).await;
+        forked_evm
+            .insert_account(
+                address,
+                U256::from(7),
+                2,
+                Bytes::from_static(&[0x00]),
+                [(U256::from(1), U256::from(3))],
+            )
+            .unwrap();
+        assert_eq!(
+            forked_evm.modified_accounts()[&address],
+            ModifiedAccount {
+                balance: Some(U256::from(7)),
+                nonce: Some(2),
+                code: Some(Bytes::from_static(&[0x00])),
+                storage_cleared: false,
+                slots: [(U256::from(1), U256::from(3))].into(),
+            }
+        );
+        let overrides = forked_evm.export_overrides().unwrap();
+        let account = &overrides[&address];
+        assert_eq!(account.balance, Some(U256::from(7)));
+        assert_eq!(account.code, Some(Bytes::from_static(&[0x00])));
+        assert_eq!(
+            account.state_diff.as_ref().unwrap()[&B256::from(U256::from(1))],
+            B256::from(U256::from(3))
+        );
+
+        let json = serde_json::to_value(account).unwrap();
+        assert!(json.get("stateDiff").is_some());
+        assert!(json.get("state").is_none());
+        let round_trip: AccountOverride = serde_json::from_value(json).unwrap();
+        assert_eq!(&round_trip, account);
+    }
+}
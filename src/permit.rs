@@ -0,0 +1,253 @@
+use crate::{ForkedEvm, ForkerError};
+use alloy_sol_types::{sol, SolStruct};
+use ethers::types::{Signature, H256};
+use foundry_evm::executors::RawCallResult;
+use revm::primitives::{
+    address, alloy_primitives::aliases::U160, keccak256, Address, Bytes, B256, U256,
+};
+
+/// Canonical Permit2 deployment, at the same address on every supported chain.
+pub const PERMIT2_ADDRESS: Address = address!("000000000022D473030F116dDEE9F6B43aC78BA3");
+
+sol! {
+    struct Permit {
+        address owner;
+        address spender;
+        uint256 value;
+        uint256 nonce;
+        uint256 deadline;
+    }
+
+    struct PermitDetails {
+        address token;
+        uint160 amount;
+        uint48 expiration;
+        uint48 nonce;
+    }
+
+    struct PermitSingle {
+        PermitDetails details;
+        address spender;
+        uint256 sigDeadline;
+    }
+
+    interface IERC20Permit {
+        function DOMAIN_SEPARATOR() external view returns (bytes32);
+        function nonces(address owner) external view returns (uint256);
+        function permit(address owner, address spender, uint256 value, uint256 deadline, uint8 v, bytes32 r, bytes32 s) external;
+    }
+
+    interface IPermit2 {
+        function DOMAIN_SEPARATOR() external view returns (bytes32);
+        function allowance(address user, address token, address spender) external view returns (uint160 amount, uint48 expiration, uint48 nonce);
+        function permit(address owner, PermitSingle memory permitSingle, bytes calldata signature) external;
+    }
+}
+
+impl ForkedEvm {
+    /// Signs an EIP-2612 permit for `token` with the registered signer of
+    /// `owner`, using the token's current domain separator and nonce.
+    pub fn sign_erc20_permit(
+        &self,
+        token: Address,
+        owner: Address,
+        spender: Address,
+        value: U256,
+        deadline: U256,
+    ) -> Result<Signature, ForkerError> {
+        let zero = Address::ZERO;
+        let domain_separator = self
+            .alloy_read(
+                zero.as_slice(),
+                token.as_slice(),
+                IERC20Permit::DOMAIN_SEPARATORCall {},
+            )?
+            ._0;
+        let nonce = self
+            .alloy_read(
+                zero.as_slice(),
+                token.as_slice(),
+                IERC20Permit::noncesCall { owner },
+            )?
+            ._0;
+        let permit = Permit {
+            owner,
+            spender,
+            value,
+            nonce,
+            deadline,
+        };
+        self.sign_typed_hash(owner, domain_separator, permit.eip712_hash_struct())
+    }
+
+    /// Signs and commits an EIP-2612 permit, submitted by `submitter`.
+    pub fn erc20_permit(
+        &mut self,
+        submitter: Address,
+        token: Address,
+        owner: Address,
+        spender: Address,
+        value: U256,
+        deadline: U256,
+    ) -> Result<RawCallResult, ForkerError> {
+        let signature = self.sign_erc20_permit(token, owner, spender, value, deadline)?;
+        let call = IERC20Permit::permitCall {
+            owner,
+            spender,
+            value,
+            deadline,
+            v: signature.v as u8,
+            r: B256::from(H256::from_uint(&signature.r).0),
+            s: B256::from(H256::from_uint(&signature.s).0),
+        };
+        Ok(self
+            .alloy_write(submitter.as_slice(), token.as_slice(), call, U256::ZERO)?
+            .0)
+    }
+
+    /// Signs a Permit2 `PermitSingle` for `token` with the registered signer
+    /// of `owner`, using Permit2's current nonce for the allowance.
+    pub fn sign_permit2(
+        &self,
+        token: Address,
+        owner: Address,
+        spender: Address,
+        amount: U160,
+        expiration: u64,
+        sig_deadline: U256,
+    ) -> Result<(PermitSingle, Signature), ForkerError> {
+        let zero = Address::ZERO;
+        let domain_separator = self
+            .alloy_read(
+                zero.as_slice(),
+                PERMIT2_ADDRESS.as_slice(),
+                IPermit2::DOMAIN_SEPARATORCall {},
+            )?
+            ._0;
+        let nonce = self
+            .alloy_read(
+                zero.as_slice(),
+                PERMIT2_ADDRESS.as_slice(),
+                IPermit2::allowanceCall {
+                    user: owner,
+                    token,
+                    spender,
+                },
+            )?
+            .nonce;
+        let permit = PermitSingle {
+            details: PermitDetails {
+                token,
+                amount,
+                expiration,
+                nonce,
+            },
+            spender,
+            sigDeadline: sig_deadline,
+        };
+        let signature =
+            self.sign_typed_hash(owner, domain_separator, permit.eip712_hash_struct())?;
+        Ok((permit, signature))
+    }
+
+    /// Signs and commits a Permit2 `permit`, submitted by `submitter`.
+    #[allow(clippy::too_many_arguments)]
+    pub fn permit2(
+        &mut self,
+        submitter: Address,
+        token: Address,
+        owner: Address,
+        spender: Address,
+        amount: U160,
+        expiration: u64,
+        sig_deadline: U256,
+    ) -> Result<RawCallResult, ForkerError> {
+        let (permit, signature) =
+            self.sign_permit2(token, owner, spender, amount, expiration, sig_deadline)?;
+        let call = IPermit2::permitCall {
+            owner,
+            permitSingle: permit,
+            signature: Bytes::from(signature.to_vec()),
+        };
+        Ok(self
+            .alloy_write(
+                submitter.as_slice(),
+                PERMIT2_ADDRESS.as_slice(),
+                call,
+                U256::ZERO,
+            )?
+            .0)
+    }
+
+    /// Signs the EIP-712 digest of `struct_hash` under `domain_separator`.
+    fn sign_typed_hash(
+        &self,
+        signer: Address,
+        domain_separator: B256,
+        struct_hash: B256,
+    ) -> Result<Signature, ForkerError> {
+        let wallet = self
+            .signers
+            .get(&signer)
+            .ok_or(ForkerError::MissingSigner(signer))?;
+        let digest = keccak256(
+            [
+                &[0x19, 0x01][..],
+                domain_separator.as_slice(),
+                struct_hash.as_slice(),
+            ]
+            .concat(),
+        );
+        wallet
+            .sign_hash(H256::from(digest.0))
+            .map_err(|e| ForkerError::SigningFailed(e.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{evm_with_code, returning_code};
+
+    #[tokio::test]
+    async fn test_erc20_permits_are_signed_by_the_owner() {
+        let token = Address::repeat_byte(0x11);
+        // answers both DOMAIN_SEPARATOR and nonces with the same word
+        let domain_separator = B256::repeat_byte(0xaa);
+        let code = returning_code(domain_separator.as_slice());
+        let mut forked_evm = evm_with_code(&[(token, &code)]).await;
+        let owner = forked_evm
+            .register_private_key(
+                "ac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80",
+            )
+            .unwrap();
+        let spender = Address::repeat_byte(0x22);
+
+        let signature = forked_evm
+            .sign_erc20_permit(token, owner, spender, U256::from(1), U256::MAX)
+            .unwrap();
+        let permit = Permit {
+            owner,
+            spender,
+            value: U256::from(1),
+            nonce: U256::from_be_bytes(domain_separator.0),
+            deadline: U256::MAX,
+        };
+        let digest = keccak256(
+            [
+                &[0x19, 0x01][..],
+                domain_separator.as_slice(),
+                permit.eip712_hash_struct().as_slice(),
+            ]
+            .concat(),
+        );
+        let recovered = signature.recover(H256::from(digest.0)).unwrap();
+        assert_eq!(Address::from(recovered.0), owner);
+
+        let stranger = Address::repeat_byte(0x33);
+        assert!(matches!(
+            forked_evm.sign_erc20_permit(token, stranger, spender, U256::from(1), U256::MAX),
+            Err(ForkerError::MissingSigner(address)) if address == stranger
+        ));
+    }
+}
@@ -0,0 +1,265 @@
+use crate::{
+    dynamic::{encode_args, parse_signature},
+    ForkedEvm, ForkerError,
+};
+use foundry_evm::executors::RawCallResult;
+use revm::primitives::{Address, Bytes, U256};
+use serde::{Deserialize, Serialize};
+
+/// Ordered deploys, calls and cheats to run on a fork, authored as json or
+/// yaml, e.g.
+///
+/// ```yaml
+/// steps:
+///   - kind: set_balance
+///     address: "0x70997970c51812dc3a010c7d01b50e0d17dc79c8"
+///     balance: "0xde0b6b3a7640000"
+///   - kind: call
+///     from: "0x70997970c51812dc3a010c7d01b50e0d17dc79c8"
+///     to: "0xa0b86991c6218b36c1d19d4a2e9eb0ce3606eb48"
+///     signature: "approve(address,uint256)"
+///     args: ["0x3c44cdddb6a900fa2b585dd299e03d12fa4293bc", "1000000"]
+/// ```
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ScriptPlan {
+    pub steps: Vec<PlanStep>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum PlanStep {
+    Deploy {
+        from: Address,
+        bytecode: Bytes,
+        #[serde(default)]
+        value: U256,
+    },
+    /// A call given either as raw `calldata` or as a `signature` and its
+    /// `args` in their human readable form.
+    Call {
+        from: Address,
+        to: Address,
+        #[serde(default)]
+        calldata: Option<Bytes>,
+        #[serde(default)]
+        signature: Option<String>,
+        #[serde(default)]
+        args: Vec<String>,
+        #[serde(default)]
+        value: U256,
+        /// Runs the call without committing it.
+        #[serde(default)]
+        read_only: bool,
+    },
+    SetBalance {
+        address: Address,
+        balance: U256,
+    },
+    SetNonce {
+        address: Address,
+        nonce: u64,
+    },
+    SetCode {
+        address: Address,
+        code: Bytes,
+    },
+    SetStorage {
+        address: Address,
+        slot: U256,
+        value: U256,
+    },
+    Warp {
+        timestamp: U256,
+    },
+    Roll {
+        block_number: U256,
+    },
+}
+
+/// What a [PlanStep] did.
+#[derive(Debug, Clone)]
+pub enum StepOutcome {
+    Deployed(Address),
+    Called(RawCallResult),
+    Applied,
+}
+
+impl ScriptPlan {
+    pub fn from_json(json: &str) -> Result<Self, ForkerError> {
+        Ok(serde_json::from_str(json)?)
+    }
+
+    pub fn from_yaml(yaml: &str) -> Result<Self, ForkerError> {
+        Ok(serde_yaml::from_str(yaml)?)
+    }
+}
+
+impl ForkedEvm {
+    /// Runs the steps of `plan` in order, returning each step's outcome.
+    /// Stops at the first failing step, whose error is the last entry.
+    /// Reverted calls aren't failures, their result says they reverted.
+    pub fn run_plan(&mut self, plan: &ScriptPlan) -> Vec<Result<StepOutcome, ForkerError>> {
+        let mut outcomes = vec![];
+        for step in &plan.steps {
+            let outcome = self.run_step(step);
+            let failed = outcome.is_err();
+            outcomes.push(outcome);
+            if failed {
+                break;
+            }
+        }
+        outcomes
+    }
+
+    fn run_step(&mut self, step: &PlanStep) -> Result<StepOutcome, ForkerError> {
+        match step {
+            PlanStep::Deploy {
+                from,
+                bytecode,
+                value,
+            } => Ok(StepOutcome::Deployed(self.deploy(
+                from.as_slice(),
+                bytecode,
+                *value,
+            )?)),
+            PlanStep::Call {
+                from,
+                to,
+                calldata,
+                signature,
+                args,
+                value,
+                read_only,
+            } => {
+                let calldata = match (calldata, signature) {
+                    (Some(calldata), _) => calldata.clone(),
+                    (None, Some(signature)) => {
+                        encode_args(&parse_signature(signature)?, args)?.into()
+                    }
+                    (None, None) => Bytes::new(),
+                };
+                let result = if *read_only {
+                    self.call_with_opts(
+                        from.as_slice(),
+                        to.as_slice(),
+                        &calldata,
+                        &crate::CallOptions {
+                            value: Some(*value),
+                            ..Default::default()
+                        },
+                    )?
+                } else {
+                    self.write(from.as_slice(), to.as_slice(), &calldata, *value)?
+                };
+                Ok(StepOutcome::Called(result))
+            }
+            PlanStep::SetBalance { address, balance } => {
+                self.update_account(*address, |account| account.balance = *balance)
+            }
+            PlanStep::SetNonce { address, nonce } => {
+                self.update_account(*address, |account| account.nonce = *nonce)
+            }
+            PlanStep::SetCode { address, code } => {
+                self.update_account(*address, |account| account.code = code.clone())
+            }
+            PlanStep::SetStorage {
+                address,
+                slot,
+                value,
+            } => self.update_account(*address, |account| account.storage.push((*slot, *value))),
+            PlanStep::Warp { timestamp } => {
+                self.executor.env.block.timestamp = *timestamp;
+                Ok(StepOutcome::Applied)
+            }
+            PlanStep::Roll { block_number } => {
+                self.executor.env.block.number = *block_number;
+                Ok(StepOutcome::Applied)
+            }
+        }
+    }
+
+    fn update_account(
+        &mut self,
+        address: Address,
+        update: impl FnOnce(&mut AccountUpdate),
+    ) -> Result<StepOutcome, ForkerError> {
+        let info = self.account_info(address)?.unwrap_or_default();
+        let mut account = AccountUpdate {
+            balance: info.balance,
+            nonce: info.nonce,
+            code: self.code_at(address)?,
+            storage: vec![],
+        };
+        update(&mut account);
+        self.insert_account(
+            address,
+            account.balance,
+            account.nonce,
+            account.code,
+            account.storage,
+        )?;
+        Ok(StepOutcome::Applied)
+    }
+}
+
+struct AccountUpdate {
+    balance: U256,
+    nonce: u64,
+    code: Bytes,
+    storage: Vec<(U256, U256)>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ScriptPlan, StepOutcome};
+    use crate::{testing::evm_with_code, ForkerError};
+    use revm::primitives::{Address, U256};
+
+    #[tokio::test]
+    async fn test_plans_run_in_order_and_stop_at_the_first_error() {
+        let sender = Address::repeat_byte(0x11);
+        let target = Address::repeat_byte(0x22);
+        // stores the first argument at slot 0
+        let mut forked_evm = evm_with_code(&[(target, "60043560005500")]).await;
+        let plan = ScriptPlan::from_yaml(&format!(
+            r#"
+steps:
+  - kind: set_balance
+    address: "{target}"
+    balance: "0x64"
+  - kind: call
+    from: "{sender}"
+    to: "{target}"
+    signature: "set(uint256)"
+    args: ["7"]
+  - kind: call
+    from: "{sender}"
+    to: "{target}"
+    signature: "set(uint256)"
+    args: []
+  - kind: warp
+    timestamp: "0x1000"
+"#
+        ))
+        .unwrap();
+        let outcomes = forked_evm.run_plan(&plan);
+        assert_eq!(outcomes.len(), 3);
+        assert!(matches!(outcomes[0], Ok(StepOutcome::Applied)));
+        assert!(matches!(outcomes[1], Ok(StepOutcome::Called(_))));
+        assert!(matches!(
+            outcomes[2],
+            Err(ForkerError::ArgumentCount {
+                expected: 1,
+                got: 0,
+                ..
+            })
+        ));
+        assert_eq!(forked_evm.balance_of(target).unwrap(), U256::from(100));
+        assert_eq!(
+            forked_evm.storage_at(target, U256::ZERO).unwrap(),
+            U256::from(7)
+        );
+        // the warp after the failing step never ran
+        assert_ne!(forked_evm.executor.env.block.timestamp, U256::from(0x1000));
+    }
+}
@@ -0,0 +1,26 @@
+use crate::ForkedEvm;
+use revm::primitives::{Address, Bytes};
+use std::sync::Arc;
+
+/// Gas used and output of a successful precompile call, or revert data.
+pub type PrecompileResult = Result<(u64, Bytes), Bytes>;
+
+/// Handler of a custom precompile, called with the input and gas limit.
+pub type Precompile = Arc<dyn Fn(&[u8], u64) -> PrecompileResult + Send + Sync>;
+
+impl ForkedEvm {
+    /// Emulates a precompile at `address` for all calls and writes, e.g. a
+    /// chain specific system contract missing from the plain evm. Calls to
+    /// `address` at any depth are answered by `handler` instead of code.
+    pub fn register_precompile(
+        &mut self,
+        address: Address,
+        handler: impl Fn(&[u8], u64) -> PrecompileResult + Send + Sync + 'static,
+    ) {
+        self.precompiles.insert(address, Arc::new(handler));
+    }
+
+    pub fn remove_precompile(&mut self, address: Address) -> bool {
+        self.precompiles.remove(&address).is_some()
+    }
+}
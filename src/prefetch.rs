@@ -0,0 +1,92 @@
+use crate::{ForkedEvm, ForkerError};
+use foundry_evm::fork::SharedBackend;
+use revm::{
+    primitives::{Address, U256},
+    DatabaseRef,
+};
+
+/// Number of worker threads issuing requests to the fork backend at once.
+const PREFETCH_CONCURRENCY: usize = 16;
+
+impl ForkedEvm {
+    /// Loads balances, nonces and code of `accounts` into the fork's RPC cache
+    /// ahead of time, fetching them in parallel.
+    pub fn prefetch(&self, accounts: &[Address]) -> Result<(), ForkerError> {
+        self.prefetch_with(accounts, |db, address| {
+            db.basic_ref(*address)?;
+            Ok(())
+        })
+    }
+
+    /// Loads the given storage slots into the fork's RPC cache ahead of time,
+    /// fetching them in parallel.
+    pub fn prefetch_slots(&self, slots: &[(Address, U256)]) -> Result<(), ForkerError> {
+        self.prefetch_with(slots, |db, (address, slot)| {
+            db.storage_ref(*address, *slot)?;
+            Ok(())
+        })
+    }
+
+    fn prefetch_with<T, F>(&self, items: &[T], fetch: F) -> Result<(), ForkerError>
+    where
+        T: Sync,
+        F: Fn(&SharedBackend, &T) -> Result<(), ForkerError> + Sync,
+    {
+        match self.executor.backend.active_fork_db() {
+            Some(fork_db) => fetch_parallel(&fork_db.db, items, fetch),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Runs `fetch` over `items` against the fork backend `db` from
+/// [PREFETCH_CONCURRENCY] threads, so the backend has that many requests in
+/// flight.
+pub(crate) fn fetch_parallel<T, F>(
+    db: &SharedBackend,
+    items: &[T],
+    fetch: F,
+) -> Result<(), ForkerError>
+where
+    T: Sync,
+    F: Fn(&SharedBackend, &T) -> Result<(), ForkerError> + Sync,
+{
+    if items.is_empty() {
+        return Ok(());
+    }
+    let chunk_size = items.len().div_ceil(PREFETCH_CONCURRENCY);
+    std::thread::scope(|scope| {
+        let handles: Vec<_> = items
+            .chunks(chunk_size)
+            .map(|chunk| {
+                let db = db.clone();
+                let fetch = &fetch;
+                scope.spawn(move || chunk.iter().try_for_each(|item| fetch(&db, item)))
+            })
+            .collect();
+        for handle in handles {
+            handle.join().map_err(|_| ForkerError::WorkerPanicked)??;
+        }
+        Ok(())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::evm_with_code;
+
+    #[tokio::test]
+    async fn test_prefetch_without_fork_is_a_noop() {
+        let forked_evm = evm_with_code(&[]).await;
+        let account = Address::repeat_byte(0x11);
+        forked_evm.prefetch(&[account]).unwrap();
+        forked_evm.prefetch_slots(&[(account, U256::ZERO)]).unwrap();
+        assert!(forked_evm
+            .executor
+            .backend
+            .basic_ref(account)
+            .unwrap()
+            .is_none());
+    }
+}
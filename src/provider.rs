@@ -0,0 +1,194 @@
+use crate::{rpc, ForkedEvm};
+use alloy_json_rpc::{
+    ErrorPayload, RequestPacket, Response, ResponsePacket, ResponsePayload, SerializedRequest,
+};
+use alloy_transport::{TransportError, TransportFut};
+use async_trait::async_trait;
+use ethers::providers::{JsonRpcClient, JsonRpcError, Provider, ProviderError, RpcError};
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::Value;
+use std::{
+    fmt,
+    sync::Arc,
+    task::{Context, Poll},
+};
+use tokio::sync::Mutex;
+use tower::Service;
+
+/// An alloy transport and ethers json-rpc client answered from a
+/// [ForkedEvm], so application code written against either's provider can
+/// run unmodified against the fork.
+#[derive(Clone)]
+pub struct ForkClient {
+    forked_evm: Arc<Mutex<ForkedEvm>>,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum ForkClientError {
+    #[error(transparent)]
+    JsonRpc(JsonRpcError),
+    #[error(transparent)]
+    Serde(#[from] serde_json::Error),
+}
+
+impl From<rpc::RpcError> for ForkClientError {
+    fn from(error: rpc::RpcError) -> Self {
+        ForkClientError::JsonRpc(JsonRpcError {
+            code: error.code,
+            message: error.message,
+            data: None,
+        })
+    }
+}
+
+impl RpcError for ForkClientError {
+    fn as_error_response(&self) -> Option<&JsonRpcError> {
+        match self {
+            ForkClientError::JsonRpc(error) => Some(error),
+            _ => None,
+        }
+    }
+
+    fn as_serde_error(&self) -> Option<&serde_json::Error> {
+        match self {
+            ForkClientError::Serde(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<ForkClientError> for ProviderError {
+    fn from(error: ForkClientError) -> Self {
+        ProviderError::JsonRpcClientError(Box::new(error))
+    }
+}
+
+impl fmt::Debug for ForkClient {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ForkClient").finish_non_exhaustive()
+    }
+}
+
+impl ForkClient {
+    pub fn new(forked_evm: ForkedEvm) -> Self {
+        Self {
+            forked_evm: Arc::new(Mutex::new(forked_evm)),
+        }
+    }
+
+    /// The underlying evm, for applying cheats between provider calls.
+    pub fn forked_evm(&self) -> Arc<Mutex<ForkedEvm>> {
+        self.forked_evm.clone()
+    }
+}
+
+#[async_trait]
+impl JsonRpcClient for ForkClient {
+    type Error = ForkClientError;
+
+    async fn request<T, R>(&self, method: &str, params: T) -> Result<R, Self::Error>
+    where
+        T: fmt::Debug + Serialize + Send + Sync,
+        R: DeserializeOwned + Send,
+    {
+        let params = serde_json::to_value(params)?;
+        let result = self.forked_evm.lock().await.handle_rpc(method, &params)?;
+        Ok(serde_json::from_value(result)?)
+    }
+}
+
+impl Service<RequestPacket> for ForkClient {
+    type Response = ResponsePacket;
+    type Error = TransportError;
+    type Future = TransportFut<'static>;
+
+    fn poll_ready(&mut self, _: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, request: RequestPacket) -> Self::Future {
+        let forked_evm = self.forked_evm.clone();
+        Box::pin(async move {
+            let mut forked_evm = forked_evm.lock().await;
+            Ok(match request {
+                RequestPacket::Single(request) => {
+                    ResponsePacket::Single(respond(&mut forked_evm, &request)?)
+                }
+                RequestPacket::Batch(requests) => ResponsePacket::Batch(
+                    requests
+                        .iter()
+                        .map(|request| respond(&mut forked_evm, request))
+                        .collect::<Result<_, _>>()?,
+                ),
+            })
+        })
+    }
+}
+
+/// Answers one request of an alloy request packet, returning rpc errors in
+/// the response like a node would.
+fn respond(
+    forked_evm: &mut ForkedEvm,
+    request: &SerializedRequest,
+) -> Result<Response, TransportError> {
+    let params = request
+        .params()
+        .map(|params| serde_json::from_str(params.get()))
+        .transpose()
+        .map_err(TransportError::ser_err)?
+        .unwrap_or(Value::Array(vec![]));
+    let payload = match forked_evm.handle_rpc(request.method(), &params) {
+        Ok(result) => ResponsePayload::Success(
+            serde_json::value::to_raw_value(&result).map_err(TransportError::ser_err)?,
+        ),
+        Err(error) => ResponsePayload::Failure(ErrorPayload {
+            code: error.code,
+            message: error.message,
+            data: None,
+        }),
+    };
+    Ok(Response {
+        id: request.id().clone(),
+        payload,
+    })
+}
+
+impl ForkedEvm {
+    /// Wraps the evm in an ethers `Provider` whose requests are answered from
+    /// the fork state, including local modifications.
+    pub fn into_provider(self) -> Provider<ForkClient> {
+        Provider::new(ForkClient::new(self))
+    }
+
+    /// Wraps the evm in an alloy `Provider` whose requests are answered from
+    /// the fork state, including local modifications.
+    pub fn into_alloy_provider(self) -> alloy_providers::provider::Provider<ForkClient> {
+        alloy_providers::provider::Provider::new(ForkClient::new(self))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::testing::evm_with_code;
+    use ethers::{providers::Middleware, types::Address};
+
+    #[tokio::test]
+    async fn test_provider_answers_from_the_fork() {
+        let target = revm::primitives::Address::repeat_byte(0x11);
+        let mut forked_evm = evm_with_code(&[(target, "00")]).await;
+        forked_evm
+            .executor
+            .set_balance(target, revm::primitives::U256::from(42))
+            .unwrap();
+        let provider = forked_evm.into_provider();
+
+        assert_eq!(provider.get_chainid().await.unwrap(), 1.into());
+        let target = Address::from(target.into_array());
+        assert_eq!(provider.get_balance(target, None).await.unwrap(), 42.into());
+        assert_eq!(
+            provider.get_code(target, None).await.unwrap().to_vec(),
+            vec![0x00]
+        );
+        assert!(provider.request::<_, ()>("eth_mine", ()).await.is_err());
+    }
+}
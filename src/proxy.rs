@@ -0,0 +1,183 @@
+use crate::{ForkedEvm, ForkerError};
+use alloy_sol_types::sol;
+use revm::primitives::{b256, Address, B256, U256};
+
+/// EIP-1967 implementation slot, `keccak256("eip1967.proxy.implementation") - 1`.
+pub const EIP1967_IMPLEMENTATION_SLOT: B256 =
+    b256!("360894a13ba1a3210667c828492db98dca3e2076cc3735a920a3ca505d382bbc");
+/// EIP-1967 beacon slot, `keccak256("eip1967.proxy.beacon") - 1`.
+pub const EIP1967_BEACON_SLOT: B256 =
+    b256!("a3f0ad74e5423aebfd80d3ef4346578335a9a72aeaee59ff6cb3582b35133d50");
+/// EIP-1822 (UUPS) slot, `keccak256("PROXIABLE")`.
+pub const EIP1822_PROXIABLE_SLOT: B256 =
+    b256!("c5f16f0fcc639fa48a6947836d9850f504798523bf8c9a3a87d5876cf622bcf7");
+/// Pre EIP-1967 OpenZeppelin slot, `keccak256("org.zeppelinos.proxy.implementation")`.
+pub const ZEPPELINOS_IMPLEMENTATION_SLOT: B256 =
+    b256!("7050c9e0f4ca769c69bd3a8ef740bc37934f8e2c036e5a723fd8ee048ed3f8c3");
+
+/// Proxies can point at other proxies, stop following them after this many hops.
+const MAX_PROXY_DEPTH: usize = 8;
+
+sol! {
+    interface IBeacon {
+        function implementation() external view returns (address);
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProxyKind {
+    Eip1967,
+    Beacon { beacon: Address },
+    Eip1822,
+    ZeppelinOs,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ProxyInfo {
+    pub kind: ProxyKind,
+    pub implementation: Address,
+}
+
+impl ForkedEvm {
+    /// Detects whether `address` is an EIP-1967, beacon, UUPS or legacy
+    /// OpenZeppelin proxy and reads its implementation from storage.
+    pub fn resolve_proxy(&self, address: Address) -> Result<Option<ProxyInfo>, ForkerError> {
+        if let Some(implementation) = self.address_at_slot(address, EIP1967_IMPLEMENTATION_SLOT)? {
+            return Ok(Some(ProxyInfo {
+                kind: ProxyKind::Eip1967,
+                implementation,
+            }));
+        }
+        if let Some(beacon) = self.address_at_slot(address, EIP1967_BEACON_SLOT)? {
+            let implementation = self
+                .alloy_read(
+                    Address::ZERO.as_slice(),
+                    beacon.as_slice(),
+                    IBeacon::implementationCall {},
+                )?
+                ._0;
+            return Ok(Some(ProxyInfo {
+                kind: ProxyKind::Beacon { beacon },
+                implementation,
+            }));
+        }
+        if let Some(implementation) = self.address_at_slot(address, EIP1822_PROXIABLE_SLOT)? {
+            return Ok(Some(ProxyInfo {
+                kind: ProxyKind::Eip1822,
+                implementation,
+            }));
+        }
+        if let Some(implementation) =
+            self.address_at_slot(address, ZEPPELINOS_IMPLEMENTATION_SLOT)?
+        {
+            return Ok(Some(ProxyInfo {
+                kind: ProxyKind::ZeppelinOs,
+                implementation,
+            }));
+        }
+        Ok(None)
+    }
+
+    /// Follows proxies starting at `address` and returns the contract whose
+    /// code and abi actually run, which is `address` itself for non proxies.
+    /// Calls should still go to `address`, the proxy owns the storage.
+    pub fn implementation_of(&self, address: Address) -> Result<Address, ForkerError> {
+        let mut current = address;
+        for _ in 0..MAX_PROXY_DEPTH {
+            match self.resolve_proxy(current)? {
+                Some(proxy) if proxy.implementation != current => current = proxy.implementation,
+                _ => break,
+            }
+        }
+        Ok(current)
+    }
+
+    fn address_at_slot(
+        &self,
+        address: Address,
+        slot: B256,
+    ) -> Result<Option<Address>, ForkerError> {
+        let value = self.storage_at(address, U256::from_be_bytes(slot.0))?;
+        let stored = Address::from_word(B256::from(value));
+        Ok((stored != Address::ZERO).then_some(stored))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{evm_with_code, returning_code};
+    use revm::primitives::Bytes;
+
+    fn set_slot(forked_evm: &mut ForkedEvm, address: Address, slot: B256, value: Address) {
+        forked_evm
+            .insert_account(
+                address,
+                U256::ZERO,
+                0,
+                Bytes::from_static(&[0x00]),
+                [(
+                    U256::from_be_bytes(slot.0),
+                    U256::from_be_bytes(value.into_word().0),
+                )],
+            )
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_resolve_proxy_kinds() {
+        let proxy = Address::repeat_byte(0x11);
+        let implementation = Address::repeat_byte(0x22);
+        let beacon = Address::repeat_byte(0x33);
+        let beacon_proxy = Address::repeat_byte(0x44);
+        let mut forked_evm = evm_with_code(&[(
+            beacon,
+            &returning_code(implementation.into_word().as_slice()),
+        )])
+        .await;
+        set_slot(
+            &mut forked_evm,
+            proxy,
+            EIP1967_IMPLEMENTATION_SLOT,
+            implementation,
+        );
+        set_slot(&mut forked_evm, beacon_proxy, EIP1967_BEACON_SLOT, beacon);
+
+        assert_eq!(
+            forked_evm.resolve_proxy(proxy).unwrap(),
+            Some(ProxyInfo {
+                kind: ProxyKind::Eip1967,
+                implementation,
+            })
+        );
+        assert_eq!(
+            forked_evm.resolve_proxy(beacon_proxy).unwrap(),
+            Some(ProxyInfo {
+                kind: ProxyKind::Beacon { beacon },
+                implementation,
+            })
+        );
+        assert_eq!(forked_evm.resolve_proxy(implementation).unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn test_implementation_of_follows_nested_proxies() {
+        let outer = Address::repeat_byte(0x11);
+        let inner = Address::repeat_byte(0x22);
+        let implementation = Address::repeat_byte(0x33);
+        let mut forked_evm = evm_with_code(&[]).await;
+        set_slot(&mut forked_evm, outer, EIP1822_PROXIABLE_SLOT, inner);
+        set_slot(
+            &mut forked_evm,
+            inner,
+            ZEPPELINOS_IMPLEMENTATION_SLOT,
+            implementation,
+        );
+
+        assert_eq!(forked_evm.implementation_of(outer).unwrap(), implementation);
+        assert_eq!(
+            forked_evm.implementation_of(implementation).unwrap(),
+            implementation
+        );
+    }
+}
@@ -0,0 +1,730 @@
+use crate::{ForkedEvm, ForkerError};
+use alloy_sol_types::{sol, SolCall, SolError, SolType};
+use foundry_evm::executors::RawCallResult;
+use revm::primitives::{keccak256, Address, Bytes, U256};
+use std::{fs, path::Path};
+
+sol! {
+    interface IInterpreterV1 {
+        function eval(address store, uint256 namespace, uint256 dispatch, uint256[][] calldata context)
+            external
+            view
+            returns (uint256[] memory stack, uint256[] memory kvs);
+    }
+
+    interface IInterpreterV2 {
+        function eval2(
+            address store,
+            uint256 namespace,
+            uint256 dispatch,
+            uint256[][] calldata context,
+            uint256[] calldata inputs
+        ) external view returns (uint256[] memory stack, uint256[] memory writes);
+    }
+}
+
+/// Which interpreter entrypoint to evaluate through.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EvalVersion {
+    /// `IInterpreterV1.eval`, inputs are ignored.
+    Eval,
+    /// `IInterpreterV2.eval2`.
+    Eval2,
+}
+
+/// Everything needed to evaluate a deployed expression on the fork.
+#[derive(Debug, Clone)]
+pub struct ForkEvalArgs {
+    pub version: EvalVersion,
+    pub interpreter: Address,
+    pub store: Address,
+    pub expression: Address,
+    /// Fully qualified namespace, see [qualify_namespace].
+    pub namespace: U256,
+    pub context: Vec<Vec<U256>>,
+    pub source_index: u16,
+    pub max_outputs: u16,
+    pub inputs: Vec<U256>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ForkEvalResult {
+    pub stack: Vec<U256>,
+    pub writes: Vec<U256>,
+    pub raw: RawCallResult,
+}
+
+/// Qualifies a state namespace by the sender the way the interpreter store
+/// does, i.e. `keccak256(stateNamespace . sender)`.
+pub fn qualify_namespace(state_namespace: U256, sender: Address) -> U256 {
+    let mut preimage = [0u8; 64];
+    preimage[..32].copy_from_slice(&state_namespace.to_be_bytes::<32>());
+    preimage[44..].copy_from_slice(sender.as_slice());
+    U256::from_be_bytes(keccak256(preimage).0)
+}
+
+/// Packs expression, source index and max outputs into an `EncodedDispatch`.
+pub fn encode_dispatch(expression: Address, source_index: u16, max_outputs: u16) -> U256 {
+    (U256::from_be_slice(expression.as_slice()) << 32)
+        | (U256::from(source_index) << 16)
+        | U256::from(max_outputs)
+}
+
+impl ForkedEvm {
+    /// Evaluates a deployed Rain expression against the current fork state
+    /// without committing anything, returning the final stack and the store
+    /// writes the caller would need to persist.
+    pub fn fork_eval(
+        &self,
+        from_address: &[u8],
+        args: ForkEvalArgs,
+    ) -> Result<ForkEvalResult, ForkerError> {
+        let dispatch = encode_dispatch(args.expression, args.source_index, args.max_outputs);
+        let calldata = match args.version {
+            EvalVersion::Eval => IInterpreterV1::evalCall {
+                store: args.store,
+                namespace: args.namespace,
+                dispatch,
+                context: args.context,
+            }
+            .abi_encode(),
+            EvalVersion::Eval2 => IInterpreterV2::eval2Call {
+                store: args.store,
+                namespace: args.namespace,
+                dispatch,
+                context: args.context,
+                inputs: args.inputs,
+            }
+            .abi_encode(),
+        };
+        let raw = self.call(from_address, args.interpreter.as_slice(), &calldata)?;
+        if raw.reverted {
+            return Err(ForkerError::reverted(&raw));
+        }
+        let (stack, writes) = match args.version {
+            EvalVersion::Eval => {
+                let decoded = IInterpreterV1::evalCall::abi_decode_returns(&raw.result, true)?;
+                (decoded.stack, decoded.kvs)
+            }
+            EvalVersion::Eval2 => {
+                let decoded = IInterpreterV2::eval2Call::abi_decode_returns(&raw.result, true)?;
+                (decoded.stack, decoded.writes)
+            }
+        };
+        Ok(ForkEvalResult { stack, writes, raw })
+    }
+}
+
+impl ForkedEvm {
+    /// Evaluates many expressions against the same fork state, keyed by
+    /// their fully qualified namespace. Evaluations never commit, so each one
+    /// sees exactly the state the batch started from, and a failing
+    /// evaluation doesn't affect the others.
+    pub fn fork_multi_eval(
+        &self,
+        from_address: &[u8],
+        evals: Vec<ForkEvalArgs>,
+    ) -> Vec<(U256, Result<ForkEvalResult, ForkerError>)> {
+        evals
+            .into_iter()
+            .map(|args| (args.namespace, self.fork_eval(from_address, args)))
+            .collect()
+    }
+}
+
+sol! {
+    interface IParserV1 {
+        function parse(bytes calldata data) external pure returns (bytes memory bytecode, uint256[] memory constants);
+    }
+
+    interface IParserV2 {
+        function parse2(bytes calldata data) external view returns (bytes memory bytecode);
+    }
+
+    error ParserOutOfBounds();
+    error MaxSources();
+    error DanglingSource();
+    error StackOverflow();
+    error StackUnderflow();
+    error ParenOverflow();
+    error UnexpectedOperand();
+    error ExpectedOperand();
+    error UnknownWord(uint256 offset);
+    error UnexpectedLHSChar(uint256 offset);
+    error UnexpectedRHSChar(uint256 offset);
+    error MissingFinalSemi(uint256 offset);
+    error UnclosedLeftParen(uint256 offset);
+    error UnexpectedRightParen(uint256 offset);
+    error UnexpectedComment(uint256 offset);
+    error UnclosedComment(uint256 offset);
+    error MalformedHexLiteral(uint256 offset);
+    error MalformedExponentDigits(uint256 offset);
+    error DecimalLiteralOverflow(uint256 offset);
+    error HexLiteralOverflow(uint256 offset);
+    error WordSize(string word);
+}
+
+/// Which parser entrypoint to call on the deployer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParseVersion {
+    /// `IParserV1.parse`, returning bytecode and constants separately.
+    Parse,
+    /// `IParserV2.parse2`, returning self contained bytecode.
+    Parse2,
+}
+
+#[derive(Debug, Clone)]
+pub struct ParseOutput {
+    pub bytecode: Bytes,
+    /// Always empty for [ParseVersion::Parse2].
+    pub constants: Vec<U256>,
+}
+
+/// A parser revert decoded against the known Rain parse errors.
+#[derive(Debug, Clone)]
+pub struct ParseError {
+    /// Error signature, or the raw revert data if it isn't a known error.
+    pub error: String,
+    /// Byte offset into the Rainlang source the parser stopped at, if any.
+    pub offset: Option<usize>,
+    /// The Rainlang source around `offset`.
+    pub context: Option<String>,
+}
+
+impl std::fmt::Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.error)?;
+        if let Some(offset) = self.offset {
+            write!(f, " at offset {offset}")?;
+        }
+        if let Some(context) = &self.context {
+            write!(f, ": {context:?}")?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+macro_rules! known_parse_errors {
+    ($($error:ident),* $(,)?) => {
+        [$((<$error as SolError>::SELECTOR, <$error as SolError>::SIGNATURE)),*]
+    };
+}
+
+impl ParseError {
+    fn decode(revert: &[u8], rainlang: &str) -> ParseError {
+        let known = known_parse_errors![
+            ParserOutOfBounds,
+            MaxSources,
+            DanglingSource,
+            StackOverflow,
+            StackUnderflow,
+            ParenOverflow,
+            UnexpectedOperand,
+            ExpectedOperand,
+            UnknownWord,
+            UnexpectedLHSChar,
+            UnexpectedRHSChar,
+            MissingFinalSemi,
+            UnclosedLeftParen,
+            UnexpectedRightParen,
+            UnexpectedComment,
+            UnclosedComment,
+            MalformedHexLiteral,
+            MalformedExponentDigits,
+            DecimalLiteralOverflow,
+            HexLiteralOverflow,
+            WordSize,
+        ];
+        let Some((_, signature)) = known
+            .iter()
+            .find(|(selector, _)| revert.len() >= 4 && revert[..4] == selector[..])
+        else {
+            return ParseError {
+                error: format!("unknown parse error: {}", Bytes::from(revert.to_vec())),
+                offset: None,
+                context: None,
+            };
+        };
+        let offset = (signature.ends_with("(uint256)") && revert.len() >= 36)
+            .then(|| U256::from_be_slice(&revert[4..36]))
+            .and_then(|offset| usize::try_from(offset).ok());
+        let context = offset.and_then(|offset| {
+            let start = offset.saturating_sub(16);
+            let end = offset.saturating_add(16).min(rainlang.len());
+            let context = rainlang.as_bytes().get(start..end)?;
+            Some(String::from_utf8_lossy(context).into_owned())
+        });
+        ParseError {
+            error: signature.to_string(),
+            offset,
+            context,
+        }
+    }
+}
+
+impl ForkedEvm {
+    /// Parses Rainlang with the parser of `deployer` on the active fork.
+    /// Parser reverts are decoded into a [ParseError].
+    pub fn fork_parse(
+        &self,
+        from_address: &[u8],
+        deployer: Address,
+        rainlang: &str,
+        version: ParseVersion,
+    ) -> Result<ParseOutput, ForkerError> {
+        let data = Bytes::from(rainlang.as_bytes().to_vec());
+        let calldata = match version {
+            ParseVersion::Parse => IParserV1::parseCall { data }.abi_encode(),
+            ParseVersion::Parse2 => IParserV2::parse2Call { data }.abi_encode(),
+        };
+        let raw = self.call(from_address, deployer.as_slice(), &calldata)?;
+        if raw.reverted {
+            return Err(ParseError::decode(&raw.result, rainlang).into());
+        }
+        Ok(match version {
+            ParseVersion::Parse => {
+                let decoded = IParserV1::parseCall::abi_decode_returns(&raw.result, true)?;
+                ParseOutput {
+                    bytecode: decoded.bytecode,
+                    constants: decoded.constants,
+                }
+            }
+            ParseVersion::Parse2 => ParseOutput {
+                bytecode: IParserV2::parse2Call::abi_decode_returns(&raw.result, true)?.bytecode,
+                constants: vec![],
+            },
+        })
+    }
+}
+
+sol! {
+    /// Construction config of deployers without a standalone parser.
+    struct DeployerConstructionConfig {
+        address interpreter;
+        address store;
+        bytes meta;
+    }
+
+    /// Construction config of deployers that take a standalone parser.
+    struct DeployerParserConstructionConfig {
+        address interpreter;
+        address store;
+        address parser;
+        bytes meta;
+    }
+}
+
+/// Creation bytecode for each contract of a Rain DISpair.
+#[derive(Debug, Clone)]
+pub struct DISPairArtifacts {
+    pub interpreter: Bytes,
+    pub store: Bytes,
+    /// Deployers that take a standalone parser in their construction config.
+    pub parser: Option<Bytes>,
+    /// Deployer creation code without constructor args, which are encoded
+    /// from the addresses of the other deployed contracts and `meta`.
+    pub deployer: Bytes,
+    /// Meta the deployer is constructed with, e.g. a `.rain.meta` file.
+    pub meta: Bytes,
+}
+
+impl DISPairArtifacts {
+    /// Reads the artifacts of an installed rain.interpreter package, e.g.
+    /// from its npm package, out of its forge `out` dir and the deployer
+    /// meta out of its `meta` dir. `suffix` selects the contract family,
+    /// e.g. `NPE2` for `RainterpreterNPE2` and friends. The parser is
+    /// optional, as older families have none.
+    pub fn from_package_dir(dir: impl AsRef<Path>, suffix: &str) -> Result<Self, ForkerError> {
+        let dir = dir.as_ref();
+        let creation_code = |name: &str| -> Result<Bytes, ForkerError> {
+            let path = dir.join(format!("out/{name}{suffix}.sol/{name}{suffix}.json"));
+            creation_code_from_artifact(&fs::read_to_string(path)?)
+        };
+        let parser_path = dir.join(format!(
+            "out/RainterpreterParser{suffix}.sol/RainterpreterParser{suffix}.json"
+        ));
+        let meta_path = dir.join(format!(
+            "meta/RainterpreterExpressionDeployer{suffix}.rain.meta"
+        ));
+        Ok(DISPairArtifacts {
+            interpreter: creation_code("Rainterpreter")?,
+            store: creation_code("RainterpreterStore")?,
+            parser: parser_path
+                .exists()
+                .then(|| creation_code("RainterpreterParser"))
+                .transpose()?,
+            deployer: creation_code("RainterpreterExpressionDeployer")?,
+            meta: Bytes::from(fs::read(meta_path)?),
+        })
+    }
+
+    /// The `NPE2` artifacts of the rain.interpreter package whose path was
+    /// in `FORKER_DISPAIR_PACKAGE` at build time, embedded in the binary so
+    /// tests need no artifacts at run time.
+    #[cfg(feature = "embedded-dispair")]
+    pub fn embedded() -> Result<Self, ForkerError> {
+        macro_rules! package_file {
+            ($path:literal) => {
+                concat!(env!("FORKER_DISPAIR_PACKAGE"), "/", $path)
+            };
+        }
+        Ok(DISPairArtifacts {
+            interpreter: creation_code_from_artifact(include_str!(package_file!(
+                "out/RainterpreterNPE2.sol/RainterpreterNPE2.json"
+            )))?,
+            store: creation_code_from_artifact(include_str!(package_file!(
+                "out/RainterpreterStoreNPE2.sol/RainterpreterStoreNPE2.json"
+            )))?,
+            parser: Some(creation_code_from_artifact(include_str!(package_file!(
+                "out/RainterpreterParserNPE2.sol/RainterpreterParserNPE2.json"
+            )))?),
+            deployer: creation_code_from_artifact(include_str!(package_file!(
+                "out/RainterpreterExpressionDeployerNPE2.sol/RainterpreterExpressionDeployerNPE2.json"
+            )))?,
+            meta: Bytes::from_static(include_bytes!(package_file!(
+                "meta/RainterpreterExpressionDeployerNPE2.rain.meta"
+            ))),
+        })
+    }
+
+    /// Abi encoded construction config of the deployer.
+    fn deployer_constructor_args(
+        &self,
+        interpreter: Address,
+        store: Address,
+        parser: Option<Address>,
+    ) -> Vec<u8> {
+        let meta = self.meta.clone();
+        match parser {
+            Some(parser) => {
+                DeployerParserConstructionConfig::abi_encode(&DeployerParserConstructionConfig {
+                    interpreter,
+                    store,
+                    parser,
+                    meta,
+                })
+            }
+            None => DeployerConstructionConfig::abi_encode(&DeployerConstructionConfig {
+                interpreter,
+                store,
+                meta,
+            }),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DISPair {
+    pub deployer: Address,
+    pub interpreter: Address,
+    pub store: Address,
+    pub parser: Option<Address>,
+}
+
+/// Reads the creation bytecode out of a forge/hardhat artifact json.
+pub fn creation_code_from_artifact(artifact: &str) -> Result<Bytes, ForkerError> {
+    let artifact: serde_json::Value = serde_json::from_str(artifact)?;
+    let bytecode = artifact
+        .pointer("/bytecode/object")
+        .or_else(|| artifact.get("bytecode"))
+        .and_then(serde_json::Value::as_str)
+        .ok_or_else(|| ForkerError::InvalidArtifact("no bytecode".to_string()))?;
+    bytecode
+        .parse()
+        .map_err(|e| ForkerError::InvalidArtifact(format!("invalid bytecode: {e}")))
+}
+
+impl ForkedEvm {
+    /// Deploys interpreter, store, optional parser and then the expression
+    /// deployer constructed with their addresses and the meta, committing
+    /// all of them.
+    pub fn deploy_dispair(
+        &mut self,
+        from_address: &[u8],
+        artifacts: DISPairArtifacts,
+    ) -> Result<DISPair, ForkerError> {
+        let interpreter = self.deploy(from_address, &artifacts.interpreter, U256::ZERO)?;
+        let store = self.deploy(from_address, &artifacts.store, U256::ZERO)?;
+        let parser = artifacts
+            .parser
+            .as_ref()
+            .map(|parser| self.deploy(from_address, parser, U256::ZERO))
+            .transpose()?;
+
+        let constructor_args = artifacts.deployer_constructor_args(interpreter, store, parser);
+        let deployer_code = [artifacts.deployer.to_vec(), constructor_args].concat();
+        let deployer = self.deploy(from_address, &deployer_code, U256::ZERO)?;
+
+        Ok(DISPair {
+            deployer,
+            interpreter,
+            store,
+            parser,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{evm_with_code, returning_code};
+
+    #[test]
+    fn test_encode_dispatch_packs_fields() {
+        let expression = Address::repeat_byte(0xab);
+        let dispatch = encode_dispatch(expression, 2, 3);
+        let bytes = dispatch.to_be_bytes::<32>();
+        assert_eq!(&bytes[8..28], expression.as_slice());
+        assert_eq!(&bytes[28..], &[0, 2, 0, 3]);
+    }
+
+    #[test]
+    fn test_qualify_namespace_hashes_namespace_and_sender() {
+        let sender = Address::repeat_byte(0x11);
+        let mut preimage = U256::from(5).to_be_bytes::<32>().to_vec();
+        preimage.extend([0u8; 12]);
+        preimage.extend_from_slice(sender.as_slice());
+        assert_eq!(
+            qualify_namespace(U256::from(5), sender),
+            U256::from_be_bytes(keccak256(preimage).0)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_fork_eval_decodes_stack_and_writes() {
+        let interpreter = Address::repeat_byte(0x11);
+        let returned = IInterpreterV2::eval2Call::abi_encode_returns(&(
+            vec![U256::from(1), U256::from(2)],
+            vec![U256::from(3)],
+        ));
+        let forked_evm = evm_with_code(&[(interpreter, &returning_code(&returned))]).await;
+
+        let result = forked_evm
+            .fork_eval(
+                &[0u8; 20],
+                ForkEvalArgs {
+                    version: EvalVersion::Eval2,
+                    interpreter,
+                    store: Address::ZERO,
+                    expression: Address::ZERO,
+                    namespace: U256::ZERO,
+                    context: vec![],
+                    source_index: 0,
+                    max_outputs: u16::MAX,
+                    inputs: vec![],
+                },
+            )
+            .unwrap();
+        assert_eq!(result.stack, vec![U256::from(1), U256::from(2)]);
+        assert_eq!(result.writes, vec![U256::from(3)]);
+    }
+
+    #[tokio::test]
+    async fn test_fork_eval_reports_reverts() {
+        let interpreter = Address::repeat_byte(0x11);
+        let forked_evm = evm_with_code(&[(interpreter, "60006000fd")]).await;
+        let args = ForkEvalArgs {
+            version: EvalVersion::Eval,
+            interpreter,
+            store: Address::ZERO,
+            expression: Address::ZERO,
+            namespace: U256::ZERO,
+            context: vec![],
+            source_index: 0,
+            max_outputs: 1,
+            inputs: vec![],
+        };
+        assert!(forked_evm.fork_eval(&[0u8; 20], args).is_err());
+    }
+
+    #[test]
+    fn test_parse_error_decodes_offset_and_context() {
+        let rainlang = "_: add(1 2), _: foo(3);";
+        let revert = UnknownWord {
+            offset: U256::from(16),
+        }
+        .abi_encode();
+        let error = ParseError::decode(&revert, rainlang);
+        assert_eq!(error.error, UnknownWord::SIGNATURE);
+        assert_eq!(error.offset, Some(16));
+        assert_eq!(error.context.as_deref(), Some("_: add(1 2), _: foo(3);"));
+    }
+
+    #[test]
+    fn test_parse_error_offset_past_the_source() {
+        let revert = UnknownWord { offset: U256::MAX }.abi_encode();
+        let error = ParseError::decode(&revert, "_: 1;");
+        assert_eq!(error.offset, None);
+        assert_eq!(error.context, None);
+
+        let revert = UnknownWord {
+            offset: U256::from(usize::MAX),
+        }
+        .abi_encode();
+        let error = ParseError::decode(&revert, "_: 1;");
+        assert_eq!(error.offset, Some(usize::MAX));
+        assert_eq!(error.context, None);
+    }
+
+    #[test]
+    fn test_parse_error_keeps_unknown_reverts() {
+        let error = ParseError::decode(&[0xde, 0xad, 0xbe, 0xef], "");
+        assert_eq!(error.error, "unknown parse error: 0xdeadbeef");
+        assert_eq!(error.offset, None);
+    }
+
+    #[tokio::test]
+    async fn test_fork_parse_decodes_parser_reverts() {
+        let deployer = Address::repeat_byte(0x11);
+        // reverts with MissingFinalSemi(3)
+        let revert = MissingFinalSemi {
+            offset: U256::from(3),
+        }
+        .abi_encode();
+        let len = revert.len() as u8;
+        let mut code = vec![
+            0x60, len, 0x60, 0x0c, 0x60, 0x00, 0x39, 0x60, len, 0x60, 0x00, 0xfd,
+        ];
+        code.extend(revert);
+        let forked_evm = evm_with_code(&[(deployer, &revm::primitives::hex::encode(code))]).await;
+
+        let error = forked_evm
+            .fork_parse(&[0u8; 20], deployer, "_: 1", ParseVersion::Parse2)
+            .unwrap_err();
+        let error = error.downcast_ref::<ParseError>().unwrap();
+        assert_eq!(error.error, MissingFinalSemi::SIGNATURE);
+        assert_eq!(error.offset, Some(3));
+        assert_eq!(error.context.as_deref(), Some("_: 1"));
+    }
+
+    #[tokio::test]
+    async fn test_fork_parse_decodes_bytecode() {
+        let deployer = Address::repeat_byte(0x11);
+        let returned =
+            IParserV2::parse2Call::abi_encode_returns(&(Bytes::from_static(&[1, 2, 3]),));
+        let forked_evm = evm_with_code(&[(deployer, &returning_code(&returned))]).await;
+
+        let output = forked_evm
+            .fork_parse(&[0u8; 20], deployer, "_: 1;", ParseVersion::Parse2)
+            .unwrap();
+        assert_eq!(output.bytecode, Bytes::from_static(&[1, 2, 3]));
+        assert!(output.constants.is_empty());
+    }
+    #[test]
+    fn test_deployer_config_is_a_dynamic_struct() {
+        let artifacts = DISPairArtifacts {
+            interpreter: Bytes::new(),
+            store: Bytes::new(),
+            parser: None,
+            deployer: Bytes::new(),
+            meta: Bytes::from_static(&[0xff; 3]),
+        };
+        let args = artifacts.deployer_constructor_args(
+            Address::repeat_byte(1),
+            Address::repeat_byte(2),
+            Some(Address::repeat_byte(3)),
+        );
+        // offset of the struct, its 4 head words, then the meta's length
+        // and its padded data
+        assert_eq!(args.len(), 32 * 7);
+        assert_eq!(U256::from_be_slice(&args[..32]), U256::from(32));
+        assert_eq!(&args[32 + 12..64], Address::repeat_byte(1).as_slice());
+        assert_eq!(U256::from_be_slice(&args[32 * 4..32 * 5]), U256::from(128));
+        assert_eq!(U256::from_be_slice(&args[32 * 5..32 * 6]), U256::from(3));
+        assert_eq!(&args[32 * 6..32 * 6 + 3], &[0xff; 3]);
+    }
+
+    #[tokio::test]
+    async fn test_deploy_dispair_deploys_every_contract() {
+        // creation code returning a single STOP as runtime code
+        let creation = Bytes::from_static(&[
+            0x60, 0x01, 0x60, 0x0c, 0x60, 0x00, 0x39, 0x60, 0x01, 0x60, 0x00, 0xf3, 0x00,
+        ]);
+        let mut forked_evm = evm_with_code(&[]).await;
+        let dispair = forked_evm
+            .deploy_dispair(
+                Address::repeat_byte(0x11).as_slice(),
+                DISPairArtifacts {
+                    interpreter: creation.clone(),
+                    store: creation.clone(),
+                    parser: Some(creation.clone()),
+                    deployer: creation,
+                    meta: Bytes::new(),
+                },
+            )
+            .unwrap();
+
+        let addresses = [
+            dispair.interpreter,
+            dispair.store,
+            dispair.parser.unwrap(),
+            dispair.deployer,
+        ];
+        for (i, address) in addresses.iter().enumerate() {
+            assert!(!addresses[i + 1..].contains(address));
+            let info = revm::DatabaseRef::basic_ref(&forked_evm.executor.backend, *address)
+                .unwrap()
+                .unwrap();
+            assert_eq!(
+                info.code.unwrap().original_bytes(),
+                Bytes::from_static(&[0x00])
+            );
+        }
+    }
+
+    #[test]
+    fn test_creation_code_from_artifact() {
+        let forge = r#"{ "bytecode": { "object": "0x6001" } }"#;
+        let hardhat = r#"{ "bytecode": "0x6002" }"#;
+        assert_eq!(
+            creation_code_from_artifact(forge).unwrap(),
+            Bytes::from_static(&[0x60, 0x01])
+        );
+        assert_eq!(
+            creation_code_from_artifact(hardhat).unwrap(),
+            Bytes::from_static(&[0x60, 0x02])
+        );
+        assert!(creation_code_from_artifact("{}").is_err());
+    }
+    #[tokio::test]
+    async fn test_fork_multi_eval_keeps_failures_separate() {
+        let interpreter = Address::repeat_byte(0x11);
+        let reverting = Address::repeat_byte(0x22);
+        let returned = IInterpreterV1::evalCall::abi_encode_returns(&(vec![U256::from(9)], vec![]));
+        let forked_evm = evm_with_code(&[
+            (interpreter, &returning_code(&returned)),
+            (reverting, "60006000fd"),
+        ])
+        .await;
+        let args = |interpreter, namespace: u64| ForkEvalArgs {
+            version: EvalVersion::Eval,
+            interpreter,
+            store: Address::ZERO,
+            expression: Address::ZERO,
+            namespace: U256::from(namespace),
+            context: vec![],
+            source_index: 0,
+            max_outputs: 1,
+            inputs: vec![],
+        };
+
+        let results = forked_evm.fork_multi_eval(
+            &[0u8; 20],
+            vec![
+                args(interpreter, 1),
+                args(reverting, 2),
+                args(interpreter, 3),
+            ],
+        );
+        let namespaces: Vec<_> = results.iter().map(|(namespace, _)| *namespace).collect();
+        assert_eq!(
+            namespaces,
+            vec![U256::from(1), U256::from(2), U256::from(3)]
+        );
+        assert_eq!(results[0].1.as_ref().unwrap().stack, vec![U256::from(9)]);
+        assert!(results[1].1.is_err());
+        assert_eq!(results[2].1.as_ref().unwrap().stack, vec![U256::from(9)]);
+    }
+}
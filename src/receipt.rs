@@ -0,0 +1,220 @@
+use crate::ForkedEvm;
+use foundry_evm::executors::RawCallResult;
+use revm::primitives::{keccak256, Address, Bytes, Env, Output, TransactTo, B256, U256};
+use serde::{Deserialize, Serialize};
+
+/// Receipt-like summary of a locally committed transaction, shaped after
+/// `eth_getTransactionReceipt` responses.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TxReceipt {
+    pub transaction_hash: B256,
+    pub transaction_index: u64,
+    pub block_number: U256,
+    pub from: Address,
+    pub to: Option<Address>,
+    /// `true` if the transaction succeeded.
+    pub status: bool,
+    pub gas_used: u64,
+    /// Gas used by local commits within the block up to and including this
+    /// transaction.
+    pub cumulative_gas_used: u64,
+    pub effective_gas_price: U256,
+    pub contract_address: Option<Address>,
+    pub logs: Vec<ReceiptLog>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReceiptLog {
+    pub address: Address,
+    pub topics: Vec<B256>,
+    pub data: Bytes,
+    pub log_index: u64,
+    pub transaction_index: u64,
+    pub block_number: U256,
+}
+
+/// What is kept of each local commit to build its receipt.
+#[derive(Debug, Clone)]
+pub(crate) struct LocalTx {
+    hash: B256,
+    block_number: U256,
+    from: Address,
+    to: Option<Address>,
+    status: bool,
+    gas_used: u64,
+    cumulative_gas_used: u64,
+    effective_gas_price: U256,
+    contract_address: Option<Address>,
+}
+
+impl LocalTx {
+    pub(crate) fn new(hash: B256, result: &RawCallResult, cumulative_gas_used: u64) -> Self {
+        let env = &result.env;
+        let effective_gas_price = match env.tx.gas_priority_fee {
+            Some(priority_fee) => env
+                .tx
+                .gas_price
+                .min(env.block.basefee.saturating_add(priority_fee)),
+            None => env.tx.gas_price,
+        };
+        let contract_address = match result.out {
+            Some(Output::Create(_, address)) if !result.reverted => address,
+            _ => None,
+        };
+        LocalTx {
+            hash,
+            block_number: env.block.number,
+            from: env.tx.caller,
+            to: match env.tx.transact_to {
+                TransactTo::Call(to) => Some(to),
+                TransactTo::Create(_) => None,
+            },
+            status: !result.reverted,
+            gas_used: result.gas_used,
+            cumulative_gas_used,
+            effective_gas_price,
+            contract_address,
+        }
+    }
+}
+
+/// Hash identifying an unsigned local commit, e.g. a [ForkedEvm::write]:
+/// the keccak of its index among local commits, sender, target, value and
+/// calldata.
+pub(crate) fn local_tx_hash(transaction_index: u64, env: &Env) -> B256 {
+    let to = match env.tx.transact_to {
+        TransactTo::Call(to) => to,
+        TransactTo::Create(_) => Address::ZERO,
+    };
+    keccak256(
+        [
+            &transaction_index.to_be_bytes()[..],
+            env.tx.caller.as_slice(),
+            to.as_slice(),
+            &env.tx.value.to_be_bytes::<32>()[..],
+            env.tx.data.as_ref(),
+        ]
+        .concat(),
+    )
+}
+
+impl ForkedEvm {
+    /// Hash of the latest local commit, e.g. to pass to
+    /// [ForkedEvm::to_receipt] right after a [ForkedEvm::write]. Signed
+    /// transactions keep their own hash, other commits get a local one.
+    pub fn last_tx_hash(&self) -> Option<B256> {
+        self.local_txs.last().map(|local_tx| local_tx.hash)
+    }
+
+    /// Builds the receipt of the transaction committed locally with `hash`
+    /// through [ForkedEvm::write], [ForkedEvm::alloy_write],
+    /// [ForkedEvm::deploy] or the signer helpers. `None` if there is no
+    /// such commit, or it was dropped by reverting a snapshot.
+    pub fn to_receipt(&self, hash: B256) -> Option<TxReceipt> {
+        let (index, local_tx) = self
+            .local_txs
+            .iter()
+            .enumerate()
+            .rev()
+            .find(|(_, local_tx)| local_tx.hash == hash)?;
+        let transaction_index = index as u64;
+        let logs = self
+            .local_logs
+            .iter()
+            .filter(|local_log| local_log.transaction_index == transaction_index)
+            .map(|local_log| ReceiptLog {
+                address: local_log.log.address,
+                topics: local_log.log.topics.clone(),
+                data: local_log.log.data.clone(),
+                log_index: local_log.log_index,
+                transaction_index,
+                block_number: local_log.block_number,
+            })
+            .collect();
+        Some(TxReceipt {
+            transaction_hash: local_tx.hash,
+            transaction_index,
+            block_number: local_tx.block_number,
+            from: local_tx.from,
+            to: local_tx.to,
+            status: local_tx.status,
+            gas_used: local_tx.gas_used,
+            cumulative_gas_used: local_tx.cumulative_gas_used,
+            effective_gas_price: local_tx.effective_gas_price,
+            contract_address: local_tx.contract_address,
+            logs,
+        })
+    }
+
+    /// Gas used by local commits within block `block_number`.
+    pub(crate) fn local_block_gas_used(&self, block_number: U256) -> u64 {
+        self.local_txs
+            .iter()
+            .rev()
+            .take_while(|local_tx| local_tx.block_number == block_number)
+            .map(|local_tx| local_tx.gas_used)
+            .sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::testing::evm_with_code;
+    use revm::primitives::{Address, B256, U256};
+
+    // PUSH1 1 PUSH1 0 PUSH1 0 LOG1 STOP, a log with topic 1
+    const LOG_CODE: &str = "600160006000a100";
+
+    #[tokio::test]
+    async fn test_receipts_are_looked_up_by_hash() {
+        let emitter = Address::repeat_byte(0x11);
+        let sender = Address::repeat_byte(0x22);
+        let mut forked_evm = evm_with_code(&[(emitter, LOG_CODE)]).await;
+        assert!(forked_evm.last_tx_hash().is_none());
+
+        let first = forked_evm
+            .write(sender.as_slice(), emitter.as_slice(), &[], U256::ZERO)
+            .unwrap();
+        let first_hash = forked_evm.last_tx_hash().unwrap();
+        forked_evm
+            .write(sender.as_slice(), emitter.as_slice(), &[], U256::ZERO)
+            .unwrap();
+        let second_hash = forked_evm.last_tx_hash().unwrap();
+        assert_ne!(first_hash, second_hash);
+
+        // the first receipt is still found after later commits
+        let receipt = forked_evm.to_receipt(first_hash).unwrap();
+        assert_eq!(receipt.transaction_hash, first_hash);
+        assert_eq!(receipt.transaction_index, 0);
+        assert!(receipt.status);
+        assert_eq!(receipt.from, sender);
+        assert_eq!(receipt.to, Some(emitter));
+        assert_eq!(receipt.gas_used, first.gas_used);
+        assert_eq!(receipt.cumulative_gas_used, first.gas_used);
+        assert_eq!(receipt.logs.len(), 1);
+        assert_eq!(receipt.logs[0].topics, vec![B256::with_last_byte(1)]);
+
+        let receipt = forked_evm.to_receipt(second_hash).unwrap();
+        assert_eq!(receipt.transaction_index, 1);
+        assert_eq!(receipt.cumulative_gas_used, 2 * first.gas_used);
+        assert_eq!(receipt.logs[0].log_index, 1);
+
+        assert!(forked_evm.to_receipt(B256::ZERO).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_revert_drops_receipts() {
+        let emitter = Address::repeat_byte(0x11);
+        let mut forked_evm = evm_with_code(&[(emitter, LOG_CODE)]).await;
+        let snapshot_id = forked_evm.snapshot();
+        forked_evm
+            .write(&[0u8; 20], emitter.as_slice(), &[], U256::ZERO)
+            .unwrap();
+        let hash = forked_evm.last_tx_hash().unwrap();
+        assert!(forked_evm.revert(snapshot_id));
+        assert!(forked_evm.to_receipt(hash).is_none());
+        assert!(forked_evm.last_tx_hash().is_none());
+    }
+}
@@ -0,0 +1,458 @@
+use crate::{console::console_logs, ForkedEvm, ForkerError};
+use foundry_evm::executors::RawCallResult;
+use revm::primitives::{hex, Address, Bytes, U256};
+use std::fmt::Write;
+
+/// Output format of a rendered report.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReportFormat {
+    /// Colored plain text for terminals.
+    Ansi,
+    Markdown,
+    Html,
+}
+
+const GREEN: &str = "\x1b[32m";
+const RED: &str = "\x1b[31m";
+const BOLD: &str = "\x1b[1m";
+const DIM: &str = "\x1b[2m";
+const RESET: &str = "\x1b[0m";
+
+/// One call frame of a trace.
+struct TraceLine {
+    depth: usize,
+    address: Address,
+    input: String,
+    gas_used: u64,
+    success: bool,
+}
+
+/// What a call changed on one account, each field as `(before, after)`
+/// and only set when it changed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AccountDiff {
+    pub address: Address,
+    pub balance: Option<(U256, U256)>,
+    pub nonce: Option<(u64, u64)>,
+    pub code: Option<(Bytes, Bytes)>,
+    /// Changed storage slots as `(slot, before, after)`.
+    pub slots: Vec<(U256, U256, U256)>,
+}
+
+impl AccountDiff {
+    /// `(field, before, after)` rows of every change, in the order reports
+    /// render them.
+    fn rows(&self) -> Vec<(String, String, String)> {
+        let mut rows = vec![];
+        if let Some((before, after)) = self.balance {
+            rows.push(("balance".to_string(), before.to_string(), after.to_string()));
+        }
+        if let Some((before, after)) = self.nonce {
+            rows.push(("nonce".to_string(), before.to_string(), after.to_string()));
+        }
+        if let Some((before, after)) = &self.code {
+            rows.push(("code".to_string(), short_hex(before), short_hex(after)));
+        }
+        for (slot, before, after) in &self.slots {
+            rows.push((
+                format!("{slot:#x}"),
+                format!("{before:#x}"),
+                format!("{after:#x}"),
+            ));
+        }
+        rows
+    }
+}
+
+impl ForkedEvm {
+    /// Changes of `result` against the evm's current state. Balances,
+    /// nonces and code before the call are read from the evm, so this must
+    /// run before the result is committed, i.e. on results of
+    /// [ForkedEvm::call] or of a write made on a snapshot.
+    pub fn state_diff(&self, result: &RawCallResult) -> Result<Vec<AccountDiff>, ForkerError> {
+        let Some(state) = &result.state_changeset else {
+            return Ok(vec![]);
+        };
+        let mut diffs = vec![];
+        for (address, account) in state {
+            let before = self.account_info(*address)?.unwrap_or_default();
+            let after = &account.info;
+            let code = if before.code_hash != after.code_hash {
+                let code = after
+                    .code
+                    .as_ref()
+                    .map(|code| code.original_bytes())
+                    .unwrap_or_default();
+                Some((self.code_at(*address)?, code))
+            } else {
+                None
+            };
+            let mut slots: Vec<_> = account
+                .storage
+                .iter()
+                .filter(|(_, slot)| slot.is_changed())
+                .map(|(key, slot)| (*key, slot.original_value(), slot.present_value()))
+                .collect();
+            slots.sort();
+            let diff = AccountDiff {
+                address: *address,
+                balance: (before.balance != after.balance)
+                    .then_some((before.balance, after.balance)),
+                nonce: (before.nonce != after.nonce).then_some((before.nonce, after.nonce)),
+                code,
+                slots,
+            };
+            if diff.balance.is_some()
+                || diff.nonce.is_some()
+                || diff.code.is_some()
+                || !diff.slots.is_empty()
+            {
+                diffs.push(diff);
+            }
+        }
+        diffs.sort_by_key(|diff| diff.address);
+        Ok(diffs)
+    }
+}
+
+/// Renders the outcome of a call or write: status, gas, the call trace if
+/// it was recorded (see [crate::stack::InspectorConfig]), the `diffs` from
+/// [ForkedEvm::state_diff], logs and console output.
+pub fn render_call(
+    title: &str,
+    result: &RawCallResult,
+    diffs: &[AccountDiff],
+    format: ReportFormat,
+) -> String {
+    let status = if result.reverted {
+        match alloy_sol_types::decode_revert_reason(&result.result) {
+            Some(reason) => format!("reverted: {reason}"),
+            None => format!("reverted ({:?})", result.exit_reason),
+        }
+    } else {
+        "success".to_string()
+    };
+    let traces: Vec<TraceLine> = result
+        .traces
+        .as_ref()
+        .map(|arena| {
+            arena
+                .nodes()
+                .iter()
+                .map(|node| TraceLine {
+                    depth: node.trace.depth,
+                    address: node.trace.address,
+                    input: short_hex(&node.trace.data),
+                    gas_used: node.trace.gas_used,
+                    success: node.trace.success,
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    let logs: Vec<String> = result
+        .logs
+        .iter()
+        .map(|log| {
+            let topic = log
+                .topics
+                .first()
+                .map(|topic| short_hex(topic.as_slice()))
+                .unwrap_or_else(|| "anonymous".to_string());
+            format!("{} {topic} {}", log.address, short_hex(&log.data))
+        })
+        .collect();
+    let console = console_logs(result);
+    let gas = format!("{} used, {} refunded", result.gas_used, result.gas_refunded);
+
+    let mut out = String::new();
+    match format {
+        ReportFormat::Ansi => {
+            let color = if result.reverted { RED } else { GREEN };
+            let _ = writeln!(out, "{BOLD}{title}{RESET} {color}{status}{RESET}");
+            let _ = writeln!(out, "{DIM}gas{RESET} {gas}");
+            if !traces.is_empty() {
+                let _ = writeln!(out, "{BOLD}trace{RESET}");
+                for line in &traces {
+                    let color = if line.success { GREEN } else { RED };
+                    let _ = writeln!(
+                        out,
+                        "{}{DIM}[{}]{RESET} {color}{}{RESET} {}",
+                        "  ".repeat(line.depth + 1),
+                        line.gas_used,
+                        line.address,
+                        line.input
+                    );
+                }
+            }
+            if !diffs.is_empty() {
+                let _ = writeln!(out, "{BOLD}state diff{RESET}");
+                for diff in diffs {
+                    let _ = writeln!(out, "  {}", diff.address);
+                    for (field, before, after) in diff.rows() {
+                        let _ = writeln!(
+                            out,
+                            "    {field}: {RED}{before}{RESET} -> {GREEN}{after}{RESET}"
+                        );
+                    }
+                }
+            }
+            for (heading, lines) in [("logs", &logs), ("console", &console)] {
+                if !lines.is_empty() {
+                    let _ = writeln!(out, "{BOLD}{heading}{RESET}");
+                    for line in lines {
+                        let _ = writeln!(out, "  {line}");
+                    }
+                }
+            }
+        }
+        ReportFormat::Markdown => {
+            let _ = writeln!(
+                out,
+                "### {}\n\n**{}**, gas {gas}\n",
+                escape_markdown(title),
+                escape_markdown(&status)
+            );
+            if !traces.is_empty() {
+                let _ = writeln!(out, "#### Trace\n\n```");
+                for line in &traces {
+                    let _ = writeln!(
+                        out,
+                        "{}[{}] {} {}{}",
+                        "  ".repeat(line.depth),
+                        line.gas_used,
+                        line.address,
+                        line.input,
+                        if line.success { "" } else { " (reverted)" }
+                    );
+                }
+                let _ = writeln!(out, "```\n");
+            }
+            if !diffs.is_empty() {
+                let _ = writeln!(
+                    out,
+                    "#### State diff\n\n| account | field | before | after |\n| --- | --- | --- | --- |"
+                );
+                for diff in diffs {
+                    for (field, before, after) in diff.rows() {
+                        let _ = writeln!(
+                            out,
+                            "| `{}` | `{field}` | `{before}` | `{after}` |",
+                            diff.address
+                        );
+                    }
+                }
+                let _ = writeln!(out);
+            }
+            for (heading, lines) in [("Logs", &logs), ("Console", &console)] {
+                if !lines.is_empty() {
+                    let _ = writeln!(out, "#### {heading}\n");
+                    for line in lines {
+                        let _ = writeln!(out, "- {}", escape_markdown(line));
+                    }
+                    let _ = writeln!(out);
+                }
+            }
+        }
+        ReportFormat::Html => {
+            let class = if result.reverted {
+                "reverted"
+            } else {
+                "success"
+            };
+            let _ = writeln!(
+                out,
+                "<section><h3>{}</h3><p class=\"{class}\">{}, gas {gas}</p>",
+                escape_html(title),
+                escape_html(&status)
+            );
+            if !traces.is_empty() {
+                let _ = writeln!(out, "<h4>Trace</h4><pre>");
+                for line in &traces {
+                    let _ = writeln!(
+                        out,
+                        "{}[{}] {} {}{}",
+                        "  ".repeat(line.depth),
+                        line.gas_used,
+                        line.address,
+                        line.input,
+                        if line.success { "" } else { " (reverted)" }
+                    );
+                }
+                let _ = writeln!(out, "</pre>");
+            }
+            if !diffs.is_empty() {
+                let _ = writeln!(
+                    out,
+                    "<h4>State diff</h4><table><tr><th>account</th><th>field</th><th>before</th><th>after</th></tr>"
+                );
+                for diff in diffs {
+                    for (field, before, after) in diff.rows() {
+                        let _ = writeln!(
+                            out,
+                            "<tr><td>{}</td><td>{field}</td><td>{before}</td><td>{after}</td></tr>",
+                            diff.address
+                        );
+                    }
+                }
+                let _ = writeln!(out, "</table>");
+            }
+            for (heading, lines) in [("Logs", &logs), ("Console", &console)] {
+                if !lines.is_empty() {
+                    let _ = writeln!(out, "<h4>{heading}</h4><ul>");
+                    for line in lines {
+                        let _ = writeln!(out, "<li><code>{}</code></li>", escape_html(line));
+                    }
+                    let _ = writeln!(out, "</ul>");
+                }
+            }
+            let _ = writeln!(out, "</section>");
+        }
+    }
+    out
+}
+
+/// Renders a gas table of named calls or writes.
+pub fn render_gas_report(results: &[(&str, &RawCallResult)], format: ReportFormat) -> String {
+    let mut out = String::new();
+    match format {
+        ReportFormat::Ansi => {
+            let width = results
+                .iter()
+                .map(|(name, _)| name.len())
+                .max()
+                .unwrap_or(0);
+            let _ = writeln!(
+                out,
+                "{BOLD}{:width$}  {:>12}  {:>12}{RESET}",
+                "call", "used", "refunded"
+            );
+            for (name, result) in results {
+                let color = if result.reverted { RED } else { GREEN };
+                let _ = writeln!(
+                    out,
+                    "{color}{name:width$}{RESET}  {:>12}  {:>12}",
+                    result.gas_used, result.gas_refunded
+                );
+            }
+        }
+        ReportFormat::Markdown => {
+            let _ = writeln!(out, "| call | used | refunded |\n| --- | ---: | ---: |");
+            for (name, result) in results {
+                let _ = writeln!(
+                    out,
+                    "| {}{} | {} | {} |",
+                    escape_markdown(name),
+                    if result.reverted { " (reverted)" } else { "" },
+                    result.gas_used,
+                    result.gas_refunded
+                );
+            }
+        }
+        ReportFormat::Html => {
+            let _ = writeln!(
+                out,
+                "<table><tr><th>call</th><th>used</th><th>refunded</th></tr>"
+            );
+            for (name, result) in results {
+                let _ = writeln!(
+                    out,
+                    "<tr class=\"{}\"><td>{}</td><td>{}</td><td>{}</td></tr>",
+                    if result.reverted {
+                        "reverted"
+                    } else {
+                        "success"
+                    },
+                    escape_html(name),
+                    result.gas_used,
+                    result.gas_refunded
+                );
+            }
+            let _ = writeln!(out, "</table>");
+        }
+    }
+    out
+}
+
+/// Hex of `bytes`, cut to a selector sized prefix for long inputs.
+fn short_hex(bytes: &[u8]) -> String {
+    if bytes.len() <= 36 {
+        hex::encode_prefixed(bytes)
+    } else {
+        format!(
+            "{}…({} bytes)",
+            hex::encode_prefixed(&bytes[..4]),
+            bytes.len()
+        )
+    }
+}
+
+/// Escapes text for markdown outside code spans, keeping table cells and
+/// list items on one line.
+fn escape_markdown(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\\' | '`' | '|' | '*' | '_' | '[' | ']' | '<' | '>' | '#' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            '\r' => {}
+            '\n' => escaped.push_str("<br>"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{escape_markdown, render_call, render_gas_report, ReportFormat};
+    use crate::testing::evm_with_code;
+    use revm::primitives::{Address, U256};
+
+    #[test]
+    fn test_markdown_escapes_table_breaking_text() {
+        assert_eq!(escape_markdown("a|b`c\nd"), "a\\|b\\`c<br>d");
+    }
+
+    #[tokio::test]
+    async fn test_reports_diff_balances_nonces_and_storage() {
+        let sender = Address::repeat_byte(0x11);
+        let target = Address::repeat_byte(0x22);
+        // stores CALLVALUE at slot 0
+        let mut forked_evm = evm_with_code(&[(target, "34600055")]).await;
+        forked_evm
+            .insert_account(sender, U256::from(10), 0, Default::default(), [])
+            .unwrap();
+        let result = forked_evm
+            .tx()
+            .from(sender)
+            .to(target)
+            .value(U256::from(3))
+            .simulate()
+            .unwrap();
+        let diffs = forked_evm.state_diff(&result).unwrap();
+        let sender_diff = diffs.iter().find(|diff| diff.address == sender).unwrap();
+        assert_eq!(sender_diff.balance, Some((U256::from(10), U256::from(7))));
+        let target_diff = diffs.iter().find(|diff| diff.address == target).unwrap();
+        assert_eq!(target_diff.balance, Some((U256::ZERO, U256::from(3))));
+        assert_eq!(target_diff.code, None);
+        assert_eq!(
+            target_diff.slots,
+            vec![(U256::ZERO, U256::ZERO, U256::from(3))]
+        );
+
+        let report = render_call("deposit | 3", &result, &diffs, ReportFormat::Markdown);
+        assert!(report.starts_with("### deposit \\| 3\n"));
+        assert!(report.contains(&format!("| `{target}` | `balance` | `0` | `3` |")));
+        let gas = render_gas_report(&[("a|b", &result)], ReportFormat::Markdown);
+        assert!(gas.contains("| a\\|b | "));
+    }
+}
@@ -0,0 +1,135 @@
+use crate::{ForkedEvm, ForkerError};
+use alloy_rpc_types::TransactionRequest;
+use foundry_evm::executors::RawCallResult;
+use revm::primitives::{Address, CreateScheme, Env, TransactTo, U256};
+
+/// EIP-2718 type of legacy transactions, which pay a flat gas price.
+const LEGACY_TX_TYPE: u8 = 0;
+
+impl ForkedEvm {
+    /// Runs an alloy transaction request on top of the current fork state
+    /// without committing it, like `eth_call`, sent by the default origin
+    /// if one is set.
+    pub fn simulate_request(&self, tx: &TransactionRequest) -> Result<RawCallResult, ForkerError> {
+        let env = self.env_from_request(tx)?;
+        let origin = self.default_origin.unwrap_or(env.tx.caller);
+        self.call_as_origin(env, origin)
+    }
+
+    /// Runs an alloy transaction request and commits it like
+    /// [ForkedEvm::write_with_opts]: requests without a gas price pay the
+    /// auto fees, the default origin sends the tx if one is set, and without
+    /// a nonce in the request the sender's current nonce is used.
+    pub fn commit_request(
+        &mut self,
+        tx: &TransactionRequest,
+    ) -> Result<RawCallResult, ForkerError> {
+        let mut env = self.env_from_request(tx)?;
+        let priced = tx.gas_price.is_some() || tx.max_fee_per_gas.is_some();
+        if let Some(fees) = self.auto_fees.filter(|_| !priced) {
+            fees.apply(&mut env);
+        }
+        let origin = self.default_origin.unwrap_or(env.tx.caller);
+        self.commit_as_origin(env, origin)
+    }
+
+    /// Builds a tx env from the populated fields of `tx` on top of the
+    /// executor's env, a missing `to` deploying the request's data.
+    pub(crate) fn env_from_request(&self, tx: &TransactionRequest) -> Result<Env, ForkerError> {
+        let mut env = self.executor.env.clone();
+        env.tx.caller = tx.from.unwrap_or(Address::ZERO);
+        env.tx.transact_to = match tx.to {
+            Some(to) => TransactTo::Call(to),
+            None => TransactTo::Create(CreateScheme::Create),
+        };
+        env.tx.data = tx.input.input().cloned().unwrap_or_default();
+        env.tx.value = tx.value.unwrap_or_default();
+        env.tx.gas_limit = tx.gas.unwrap_or(self.executor.gas_limit).saturating_to();
+        if let Some(nonce) = tx.nonce {
+            env.tx.nonce = Some(u64::try_from(nonce).map_err(|_| {
+                ForkerError::InvalidTransaction(format!("nonce {nonce} out of range"))
+            })?);
+        }
+        if let Some(chain_id) = tx.chain_id {
+            env.tx.chain_id = Some(u64::try_from(chain_id).map_err(|_| {
+                ForkerError::InvalidTransaction(format!("chain id {chain_id} out of range"))
+            })?);
+        }
+        if let Some(gas_price) = tx.max_fee_per_gas.or(tx.gas_price) {
+            env.tx.gas_price = gas_price;
+        }
+        let tx_type = tx
+            .transaction_type
+            .map(|ty| {
+                u8::try_from(ty).map_err(|_| {
+                    ForkerError::InvalidTransaction(format!("unknown transaction type {ty}"))
+                })
+            })
+            .transpose()?;
+        let legacy = tx_type == Some(LEGACY_TX_TYPE);
+        env.tx.gas_priority_fee = tx.max_priority_fee_per_gas.filter(|_| !legacy);
+        if let Some(access_list) = &tx.access_list {
+            env.tx.access_list = access_list
+                .0
+                .iter()
+                .map(|item| {
+                    let keys = item
+                        .storage_keys
+                        .iter()
+                        .map(|key| U256::from_be_bytes(key.0));
+                    (item.address, keys.collect())
+                })
+                .collect();
+        }
+        if let Some(blob_hashes) = &tx.blob_versioned_hashes {
+            env.tx.blob_hashes = blob_hashes.clone();
+        }
+        env.tx.max_fee_per_blob_gas = tx.max_fee_per_blob_gas;
+        Ok(env)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{fees::FeeSuggestion, testing::evm_with_code};
+    use alloy_rpc_types::TransactionRequest;
+    use revm::primitives::{AccountInfo, Address, U256};
+
+    #[tokio::test]
+    async fn test_requests_use_auto_fees_and_origin() {
+        let sender = Address::repeat_byte(0x11);
+        let origin = Address::repeat_byte(0x33);
+        let target = Address::repeat_byte(0x22);
+        let mut forked_evm = evm_with_code(&[(target, "00")]).await;
+        forked_evm.executor.backend.insert_account_info(
+            origin,
+            AccountInfo {
+                balance: U256::from(10).pow(U256::from(18)),
+                ..Default::default()
+            },
+        );
+        forked_evm.set_auto_fees(Some(FeeSuggestion {
+            base_fee: U256::ZERO,
+            max_fee_per_gas: U256::from(7),
+            max_priority_fee_per_gas: U256::from(2),
+        }));
+        forked_evm.set_default_origin(Some(origin));
+
+        let mut tx = TransactionRequest {
+            from: Some(sender),
+            to: Some(target),
+            gas: Some(U256::from(100_000)),
+            ..Default::default()
+        };
+        let result = forked_evm.commit_request(&tx).unwrap();
+        assert_eq!(result.env.tx.gas_price, U256::from(7));
+        assert_eq!(result.env.tx.gas_priority_fee, Some(U256::from(2)));
+        assert_eq!(result.env.tx.caller, origin);
+        assert_eq!(forked_evm.nonce_of(origin).unwrap(), 1);
+        assert_eq!(forked_evm.nonce_of(sender).unwrap(), 0);
+
+        tx.gas_price = Some(U256::from(3));
+        let result = forked_evm.commit_request(&tx).unwrap();
+        assert_eq!(result.env.tx.gas_price, U256::from(3));
+    }
+}
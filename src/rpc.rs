@@ -0,0 +1,452 @@
+use crate::{ForkedEvm, ForkerError};
+use alloy_rpc_types::BlockTransactions;
+use foundry_evm::executors::RawCallResult;
+use hyper::{
+    service::{make_service_fn, service_fn},
+    Body, Request, Response, Server,
+};
+use revm::primitives::{
+    alloy_primitives::{Bloom, B64},
+    b256, Address, Bytes, CreateScheme, Env, TransactTo, B256, U256,
+};
+use serde_json::{json, Value};
+use std::{convert::Infallible, net::SocketAddr, sync::Arc};
+use tokio::sync::Mutex;
+
+/// Blocks an `eth_feeHistory` request can ask for, as on most nodes.
+const MAX_FEE_HISTORY_BLOCKS: usize = 1024;
+
+/// `keccak256(rlp([]))`, the ommers hash of every post-merge block.
+const EMPTY_OMMERS_HASH: B256 =
+    b256!("1dcc4de8dec75d7aab85b567b6ccd41ad312451b948a7413f0a142fd40d49347");
+
+/// A json-rpc error object as returned to clients.
+#[derive(Debug, Clone)]
+pub struct RpcError {
+    pub code: i64,
+    pub message: String,
+}
+
+impl RpcError {
+    fn invalid_params(message: impl Into<String>) -> Self {
+        Self {
+            code: -32602,
+            message: message.into(),
+        }
+    }
+
+    fn internal(message: impl ToString) -> Self {
+        Self {
+            code: -32603,
+            message: message.to_string(),
+        }
+    }
+}
+
+impl ForkedEvm {
+    /// Answers a single `eth_*`/`evm_*` json-rpc method from the fork state.
+    pub fn handle_rpc(&mut self, method: &str, params: &Value) -> Result<Value, RpcError> {
+        match method {
+            "eth_chainId" => Ok(json!(U256::from(self.executor.env.cfg.chain_id))),
+            "eth_blockNumber" => Ok(json!(self.executor.env.block.number)),
+            "eth_getBalance" => {
+                let address = param::<Address>(params, 0)?;
+                self.check_block_tag(params, 1)?;
+                Ok(json!(self
+                    .balance_of(address)
+                    .map_err(RpcError::internal)?))
+            }
+            "eth_getTransactionCount" => {
+                let address = param::<Address>(params, 0)?;
+                self.check_block_tag(params, 1)?;
+                let nonce = self.nonce_of(address).map_err(RpcError::internal)?;
+                Ok(json!(U256::from(nonce)))
+            }
+            "eth_getCode" => {
+                let address = param::<Address>(params, 0)?;
+                self.check_block_tag(params, 1)?;
+                Ok(json!(self.code_at(address).map_err(RpcError::internal)?))
+            }
+            "eth_getStorageAt" => {
+                let address = param::<Address>(params, 0)?;
+                let slot = param::<U256>(params, 1)?;
+                self.check_block_tag(params, 2)?;
+                Ok(json!(self
+                    .storage_at(address, slot)
+                    .map_err(RpcError::internal)?))
+            }
+            "eth_call" => {
+                let tx = params
+                    .get(0)
+                    .ok_or_else(|| RpcError::invalid_params("missing call object"))?;
+                self.check_block_tag(params, 1)?;
+                if field::<Address>(tx, "to")?.is_none() {
+                    return Err(RpcError::invalid_params("missing `to`"));
+                }
+                let result = self.rpc_call(self.env_from_rpc_call(tx)?)?;
+                Ok(json!(result.result))
+            }
+            "eth_sendRawTransaction" => {
+                let raw = param::<Bytes>(params, 0)?;
+                let (hash, result) = self.send_raw_transaction(&raw).map_err(|e| match e {
+                    ForkerError::InvalidTransaction(_) | ForkerError::ChainIdMismatch { .. } => {
+                        RpcError::invalid_params(e.to_string())
+                    }
+                    ForkerError::NonceMismatch { .. } => RpcError {
+                        code: -32000,
+                        message: e.to_string(),
+                    },
+                    e => RpcError::internal(e),
+                })?;
+                if result.reverted {
+                    return Err(RpcError {
+                        code: 3,
+                        message: format!("execution reverted: {}", result.result),
+                    });
+                }
+                Ok(json!(hash))
+            }
+            "eth_gasPrice" => Ok(json!(self.executor.env.block.basefee)),
+            "eth_maxPriorityFeePerGas" => Ok(json!(U256::ZERO)),
+            "eth_feeHistory" => {
+                let block_count = param::<U256>(params, 0)?
+                    .saturating_to::<usize>()
+                    .clamp(1, MAX_FEE_HISTORY_BLOCKS);
+                let percentiles = params.get(2).and_then(Value::as_array).map_or(0, Vec::len);
+                let newest: u64 = self.executor.env.block.number.saturating_to();
+                let oldest = newest.saturating_sub(block_count as u64 - 1);
+                // the fork runs every block at the current base fee and
+                // without competing transactions
+                Ok(json!({
+                    "oldestBlock": U256::from(oldest),
+                    "baseFeePerGas": vec![self.executor.env.block.basefee; block_count + 1],
+                    "gasUsedRatio": vec![0.0; block_count],
+                    "reward": vec![vec![U256::ZERO; percentiles]; block_count],
+                }))
+            }
+            "eth_estimateGas" => {
+                let tx = params
+                    .get(0)
+                    .ok_or_else(|| RpcError::invalid_params("missing call object"))?;
+                self.check_block_tag(params, 1)?;
+                let result = self.rpc_call(self.env_from_rpc_call(tx)?)?;
+                // the limit has to cover the gas spent before refunds
+                Ok(json!(U256::from(result.gas_used + result.gas_refunded)))
+            }
+            "eth_getBlockByNumber" => {
+                let full = params.get(1).and_then(Value::as_bool).unwrap_or(false);
+                self.block_by_number(params.get(0).unwrap_or(&Value::Null), full)
+            }
+            "evm_snapshot" => Ok(json!(self.snapshot())),
+            "evm_revert" => Ok(json!(self.revert(param::<U256>(params, 0)?))),
+            _ => Err(RpcError {
+                code: -32601,
+                message: format!("method not found: {method}"),
+            }),
+        }
+    }
+
+    /// Serves the fork as a json-rpc endpoint on `addr` until the server fails.
+    pub async fn serve(self, addr: SocketAddr) -> Result<(), ForkerError> {
+        let forked_evm = Arc::new(Mutex::new(self));
+        let make_service = make_service_fn(move |_| {
+            let forked_evm = forked_evm.clone();
+            async move {
+                Ok::<_, Infallible>(service_fn(move |request| {
+                    handle_http(forked_evm.clone(), request)
+                }))
+            }
+        });
+        Server::try_bind(&addr)?.serve(make_service).await?;
+        Ok(())
+    }
+
+    /// Tx env of an `eth_call`/`eth_estimateGas` call object on top of the
+    /// current block env, creating a contract when `to` is missing.
+    fn env_from_rpc_call(&self, tx: &Value) -> Result<Env, RpcError> {
+        let mut env = self.executor.env.clone();
+        env.tx.caller = field::<Address>(tx, "from")?.unwrap_or_default();
+        env.tx.transact_to = match field::<Address>(tx, "to")? {
+            Some(to) => TransactTo::Call(to),
+            None => TransactTo::Create(CreateScheme::Create),
+        };
+        env.tx.data = match field::<Bytes>(tx, "input")? {
+            Some(data) => data,
+            None => field::<Bytes>(tx, "data")?.unwrap_or_default(),
+        };
+        env.tx.value = field::<U256>(tx, "value")?.unwrap_or_default();
+        env.tx.gas_limit = match field::<U256>(tx, "gas")? {
+            Some(gas) => gas
+                .try_into()
+                .map_err(|_| RpcError::invalid_params("`gas` exceeds u64"))?,
+            None => self.executor.gas_limit.saturating_to(),
+        };
+        env.tx.nonce = None;
+        Ok(env)
+    }
+
+    /// Runs `env` without committing it, turning a revert into the error
+    /// nodes return for it.
+    fn rpc_call(&self, env: Env) -> Result<RawCallResult, RpcError> {
+        let result = self
+            .executor
+            .call_raw_with_env(env)
+            .map_err(RpcError::internal)?;
+        self.record_coverage(result.coverage.clone());
+        if result.reverted {
+            return Err(RpcError {
+                code: 3,
+                message: format!("execution reverted: {}", result.result),
+            });
+        }
+        Ok(result)
+    }
+
+    /// The fork's current block as the fork sees it, or an earlier block
+    /// fetched through the fork's RPC.
+    fn block_by_number(&self, tag: &Value, full: bool) -> Result<Value, RpcError> {
+        let block = &self.executor.env.block;
+        let current: u64 = block.number.saturating_to();
+        let number = match tag.as_str() {
+            Some("latest" | "pending" | "safe" | "finalized") => current,
+            Some("earliest") => 0,
+            _ if tag.is_null() => current,
+            _ => serde_json::from_value::<U256>(tag.clone())
+                .map_err(|e| RpcError::invalid_params(e.to_string()))?
+                .saturating_to(),
+        };
+        if number > current {
+            return Ok(Value::Null);
+        }
+        if number < current {
+            let mut block = self.get_block(number).map_err(RpcError::internal)?;
+            if let (false, BlockTransactions::Full(transactions)) = (full, &block.transactions) {
+                let hashes = transactions.iter().map(|tx| tx.hash).collect();
+                block.transactions = BlockTransactions::Hashes(hashes);
+            }
+            return Ok(json!(block));
+        }
+        Ok(json!({
+            "hash": B256::ZERO,
+            "parentHash": B256::ZERO,
+            "sha3Uncles": EMPTY_OMMERS_HASH,
+            "miner": block.coinbase,
+            "stateRoot": B256::ZERO,
+            "transactionsRoot": B256::ZERO,
+            "receiptsRoot": B256::ZERO,
+            "logsBloom": Bloom::ZERO,
+            "difficulty": block.difficulty,
+            "totalDifficulty": U256::ZERO,
+            "number": block.number,
+            "gasLimit": block.gas_limit,
+            "gasUsed": U256::ZERO,
+            "timestamp": block.timestamp,
+            "extraData": Bytes::new(),
+            "mixHash": block.prevrandao.unwrap_or_default(),
+            "nonce": B64::ZERO,
+            "baseFeePerGas": block.basefee,
+            "size": U256::ZERO,
+            "uncles": Vec::<B256>::new(),
+            "transactions": Vec::<B256>::new(),
+        }))
+    }
+
+    /// Only the fork's current block is served, other block tags are
+    /// rejected rather than silently answered from the current state.
+    fn check_block_tag(&self, params: &Value, index: usize) -> Result<(), RpcError> {
+        let Some(tag) = params.get(index).filter(|tag| !tag.is_null()) else {
+            return Ok(());
+        };
+        if matches!(tag.as_str(), Some("latest" | "pending")) {
+            return Ok(());
+        }
+        let current = self.executor.env.block.number;
+        let requested = match tag {
+            Value::Object(object) => object.get("blockNumber").cloned(),
+            tag => Some(tag.clone()),
+        }
+        .and_then(|number| serde_json::from_value::<U256>(number).ok());
+        if requested == Some(current) {
+            return Ok(());
+        }
+        Err(RpcError::invalid_params(format!(
+            "only the fork's current block {current} is available, got {tag}"
+        )))
+    }
+}
+
+async fn handle_http(
+    forked_evm: Arc<Mutex<ForkedEvm>>,
+    request: Request<Body>,
+) -> Result<Response<Body>, Infallible> {
+    let body = hyper::body::to_bytes(request.into_body())
+        .await
+        .unwrap_or_default();
+    let response = match serde_json::from_slice::<Value>(&body) {
+        Ok(Value::Array(requests)) => {
+            let mut forked_evm = forked_evm.lock().await;
+            Value::Array(
+                requests
+                    .iter()
+                    .map(|request| dispatch(&mut forked_evm, request))
+                    .collect(),
+            )
+        }
+        Ok(request) => dispatch(&mut *forked_evm.lock().await, &request),
+        Err(e) => json!({
+            "jsonrpc": "2.0",
+            "id": Value::Null,
+            "error": { "code": -32700, "message": e.to_string() },
+        }),
+    };
+    Ok(Response::builder()
+        .header("content-type", "application/json")
+        .body(Body::from(response.to_string()))
+        .unwrap())
+}
+
+fn dispatch(forked_evm: &mut ForkedEvm, request: &Value) -> Value {
+    let id = request.get("id").cloned().unwrap_or(Value::Null);
+    let method = request
+        .get("method")
+        .and_then(Value::as_str)
+        .unwrap_or_default();
+    let params = request
+        .get("params")
+        .cloned()
+        .unwrap_or(Value::Array(vec![]));
+    match forked_evm.handle_rpc(method, &params) {
+        Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+        Err(e) => json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "error": { "code": e.code, "message": e.message },
+        }),
+    }
+}
+
+fn param<T: serde::de::DeserializeOwned>(params: &Value, index: usize) -> Result<T, RpcError> {
+    let value = params
+        .get(index)
+        .ok_or_else(|| RpcError::invalid_params(format!("missing param {index}")))?;
+    serde_json::from_value(value.clone()).map_err(|e| RpcError::invalid_params(e.to_string()))
+}
+
+fn field<T: serde::de::DeserializeOwned>(
+    object: &Value,
+    name: &str,
+) -> Result<Option<T>, RpcError> {
+    object
+        .get(name)
+        .filter(|value| !value.is_null())
+        .map(|value| serde_json::from_value(value.clone()))
+        .transpose()
+        .map_err(|e| RpcError::invalid_params(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::evm_with_code;
+
+    // returns 1 + 2 as a single word
+    const ADD_CODE: &str = "600160020160005260206000f3";
+    // returns callvalue as a single word
+    const CALLVALUE_CODE: &str = "3460005260206000f3";
+
+    #[tokio::test]
+    async fn test_eth_call_honours_value_and_gas() {
+        let target = Address::repeat_byte(0x11);
+        let mut forked_evm = evm_with_code(&[(target, CALLVALUE_CODE)]).await;
+        let caller = Address::repeat_byte(0x22);
+        forked_evm
+            .executor
+            .set_balance(caller, U256::from(10))
+            .unwrap();
+
+        let result = forked_evm
+            .handle_rpc(
+                "eth_call",
+                &json!([{ "from": caller, "to": target, "value": "0x5" }, "latest"]),
+            )
+            .unwrap();
+        assert_eq!(
+            serde_json::from_value::<Bytes>(result).unwrap(),
+            Bytes::from(U256::from(5).to_be_bytes::<32>().to_vec())
+        );
+
+        let error = forked_evm
+            .handle_rpc("eth_call", &json!([{ "to": target, "gas": "0x5208" }]))
+            .unwrap_err();
+        assert_eq!(error.code, 3);
+    }
+
+    #[tokio::test]
+    async fn test_block_tags_other_than_the_fork_block_are_rejected() {
+        let target = Address::repeat_byte(0x11);
+        let mut forked_evm = evm_with_code(&[(target, ADD_CODE)]).await;
+
+        for tag in [json!("latest"), json!("pending"), json!("0x0"), Value::Null] {
+            forked_evm
+                .handle_rpc("eth_call", &json!([{ "to": target }, tag]))
+                .unwrap();
+        }
+        let error = forked_evm
+            .handle_rpc("eth_getBalance", &json!([target, "0x1"]))
+            .unwrap_err();
+        assert_eq!(error.code, -32602);
+        assert!(forked_evm
+            .handle_rpc("eth_call", &json!([{ "to": target }, "earliest"]))
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_serves_the_methods_a_provider_sends_with() {
+        let mut forked_evm = evm_with_code(&[]).await;
+        let block = forked_evm
+            .handle_rpc("eth_getBlockByNumber", &json!(["latest", false]))
+            .unwrap();
+        assert_eq!(block["number"], json!(U256::ZERO));
+        assert!(forked_evm
+            .handle_rpc("eth_getBlockByNumber", &json!(["0xffffffff", false]))
+            .unwrap()
+            .is_null());
+
+        let history = forked_evm
+            .handle_rpc("eth_feeHistory", &json!(["0x3", "latest", [50.0]]))
+            .unwrap();
+        assert_eq!(history["baseFeePerGas"].as_array().unwrap().len(), 4);
+        assert_eq!(history["reward"][0].as_array().unwrap().len(), 1);
+        forked_evm.handle_rpc("eth_gasPrice", &json!([])).unwrap();
+
+        // deploys code emitting a LOG0
+        let gas = forked_evm
+            .handle_rpc(
+                "eth_estimateGas",
+                &json!([{ "from": Address::repeat_byte(0x11), "data": "0x60006000a000" }]),
+            )
+            .unwrap();
+        let gas: U256 = serde_json::from_value(gas).unwrap();
+        assert!(gas > U256::from(53_000));
+    }
+
+    #[tokio::test]
+    async fn test_dispatch_reports_unknown_methods() {
+        let mut forked_evm = evm_with_code(&[]).await;
+        let response = dispatch(
+            &mut forked_evm,
+            &json!({ "jsonrpc": "2.0", "id": 7, "method": "eth_mine" }),
+        );
+        assert_eq!(response["id"], 7);
+        assert_eq!(response["error"]["code"], -32601);
+    }
+
+    #[tokio::test]
+    async fn test_send_raw_transaction_rejects_garbage() {
+        let mut forked_evm = evm_with_code(&[]).await;
+        let error = forked_evm
+            .handle_rpc("eth_sendRawTransaction", &json!(["0x1234"]))
+            .unwrap_err();
+        assert_eq!(error.code, -32602);
+    }
+}
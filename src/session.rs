@@ -0,0 +1,216 @@
+use crate::{anvil::SerializableState, forks::RpcConfig, ForkedEvm, ForkerError};
+use foundry_evm::{backend::Backend, fork::ForkId};
+use revm::{
+    primitives::{Address, Env, SpecId, B256, U256},
+    JournaledState,
+};
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, fs, path::Path};
+
+/// Everything needed to resume a [ForkedEvm] in another process: its forks,
+/// their pinned blocks and local state, the executor env, the default origin
+/// and seeded block hashes. Signers and precompiles can't be saved.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Session {
+    pub gas_limit: u64,
+    /// Executor env at save time, including any warps, rolls or spec change.
+    pub env: Env,
+    pub forks: Vec<SessionFork>,
+    /// Index into `forks` of the fork that was active.
+    pub active: Option<usize>,
+    /// See [ForkedEvm::set_default_origin].
+    #[serde(default)]
+    pub origin: Option<Address>,
+    /// See [ForkedEvm::set_block_hash].
+    #[serde(default)]
+    pub block_hashes: BTreeMap<U256, B256>,
+    /// See [ForkedEvm::set_rpc_config].
+    #[serde(default)]
+    pub rpc_config: RpcConfig,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionFork {
+    /// Key the fork was tracked under, see [ForkedEvm::forks].
+    pub fork_id: String,
+    pub url: String,
+    pub block_number: Option<u64>,
+    pub pinned_block: u64,
+    /// Env of the fork at its pinned block.
+    pub env: Env,
+    /// Accounts held locally by the fork.
+    pub state: SerializableState,
+    #[serde(default)]
+    pub rpc_config: RpcConfig,
+    /// RPCs of a fork served through a relay, whose `url` is only valid in
+    /// the process that saved it, so the relay is started again on load.
+    /// Headers are saved with them, credentials included.
+    #[cfg(feature = "native")]
+    #[serde(default)]
+    pub upstream: Option<crate::transport::Upstream>,
+}
+
+impl ForkedEvm {
+    /// Captures the session, selecting each fork in turn to read its local
+    /// state. The active fork and env are restored before returning. Fails
+    /// with [ForkerError::SessionUnsupported] while signers or precompiles
+    /// are registered, as keys aren't written to disk and closures can't be.
+    pub fn session(&mut self) -> Result<Session, ForkerError> {
+        if !self.signers.is_empty() {
+            return Err(ForkerError::SessionUnsupported("signers"));
+        }
+        if !self.precompiles.is_empty() {
+            return Err(ForkerError::SessionUnsupported("precompiles"));
+        }
+        let env = self.executor.env.clone();
+        let previous = self.executor.backend.active_fork_id();
+        let mut forks: Vec<_> = self
+            .forks
+            .iter()
+            .map(|(fork_id, info)| (fork_id.clone(), info.clone()))
+            .collect();
+        // restore in creation order, so local ids come back in the same order
+        forks.sort_by_key(|(_, info)| info.local_id);
+
+        let mut session_forks = Vec::with_capacity(forks.len());
+        let mut active = None;
+        for (fork_id, info) in forks {
+            if Some(info.local_id) == previous {
+                active = Some(session_forks.len());
+            }
+            self.select_local_fork(info.local_id)?;
+            session_forks.push(SessionFork {
+                fork_id: fork_id.0,
+                url: info.url,
+                block_number: info.block_number,
+                pinned_block: info.pinned_block,
+                env: info.env,
+                state: self.dump_anvil_state(),
+                rpc_config: info.rpc_config,
+                #[cfg(feature = "native")]
+                upstream: info.relay.map(|relay| relay.upstream),
+            });
+        }
+        if let Some(previous) = previous {
+            self.select_local_fork(previous)?;
+        }
+        self.executor.env = env.clone();
+        Ok(Session {
+            gas_limit: self.executor.gas_limit.saturating_to(),
+            env,
+            forks: session_forks,
+            active,
+            origin: self.default_origin,
+            block_hashes: self.block_hashes.clone().into_iter().collect(),
+            rpc_config: self.rpc_config.clone(),
+        })
+    }
+
+    /// Writes [ForkedEvm::session] to `path` as json.
+    pub fn save_session(&mut self, path: impl AsRef<Path>) -> Result<(), ForkerError> {
+        fs::write(path, serde_json::to_vec(&self.session()?)?)?;
+        Ok(())
+    }
+
+    /// Recreates an evm from a [Session], forking every saved fork at its
+    /// pinned block and reapplying its local state. Relayed forks get a new
+    /// relay, whose url replaces the saved one in their url and fork id.
+    pub async fn from_session(session: Session) -> Result<ForkedEvm, ForkerError> {
+        let backend = Backend::spawn(None).await;
+        let mut forked_evm =
+            Self::new_with_shared_backend(backend, session.env.clone(), Some(session.gas_limit));
+        let mut local_ids = Vec::with_capacity(session.forks.len());
+        for fork in session.forks {
+            #[cfg(feature = "native")]
+            let (fork_id, url) = match fork.upstream {
+                Some(upstream) => {
+                    let url = forked_evm.relay_for(upstream).await?.url;
+                    // ids start with the url, branch suffixes are kept
+                    (ForkId(fork.fork_id.replacen(&fork.url, &url, 1)), url)
+                }
+                None => (ForkId(fork.fork_id), fork.url),
+            };
+            #[cfg(not(feature = "native"))]
+            let (fork_id, url) = (ForkId(fork.fork_id), fork.url);
+            let fork_opts =
+                Self::create_fork_opts(&url, Some(fork.pinned_block), &fork.rpc_config).await?;
+            forked_evm.executor.env = fork.env.clone();
+            let mut journaled_state = JournaledState::new(SpecId::LATEST, vec![]);
+            let executor = &mut *forked_evm.executor;
+            let local_id = executor
+                .backend
+                .create_select_fork(fork_opts, &mut executor.env, &mut journaled_state)
+                .map_err(|e| ForkerError::rpc(&url, e))?;
+            forked_evm.executor.env = fork.env;
+            forked_evm.track_fork(fork_id.clone(), &url, fork.block_number, local_id);
+            if let Some(info) = forked_evm.forks.get_mut(&fork_id) {
+                info.rpc_config = fork.rpc_config;
+            }
+            forked_evm.load_anvil_state(SerializableState {
+                block: None,
+                ..fork.state
+            })?;
+            local_ids.push(local_id);
+        }
+        if let Some(&local_id) = session.active.and_then(|active| local_ids.get(active)) {
+            forked_evm.select_local_fork(local_id)?;
+        }
+        forked_evm.executor.env = session.env;
+        forked_evm.default_origin = session.origin;
+        forked_evm.block_hashes = session.block_hashes.into_iter().collect();
+        forked_evm.rpc_config = session.rpc_config;
+        Ok(forked_evm)
+    }
+
+    /// Resumes a session written by [ForkedEvm::save_session].
+    pub async fn load_session(path: impl AsRef<Path>) -> Result<ForkedEvm, ForkerError> {
+        let session: Session = serde_json::from_slice(&fs::read(path)?)?;
+        Self::from_session(session).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{forks::RpcConfig, testing::evm_with_code, ForkedEvm, ForkerError};
+    use revm::primitives::{Address, Bytes, B256, U256};
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_sessions_keep_env_and_origin() {
+        let mut forked_evm = evm_with_code(&[]).await;
+        let origin = Address::repeat_byte(0x11);
+        forked_evm.set_default_origin(Some(origin));
+        forked_evm.set_block_hash(5, B256::repeat_byte(0xab));
+        forked_evm.executor.env.block.timestamp = U256::from(1_000);
+        let rpc_config = RpcConfig {
+            max_retries: Some(2),
+            initial_backoff: Some(Duration::from_millis(50)),
+            ..Default::default()
+        };
+        forked_evm.set_rpc_config(rpc_config.clone());
+
+        let session = forked_evm.session().unwrap();
+        assert_eq!(session.origin, Some(origin));
+        let json = serde_json::to_vec(&session).unwrap();
+        let resumed = ForkedEvm::from_session(serde_json::from_slice(&json).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(resumed.default_origin, Some(origin));
+        assert_eq!(
+            resumed.block_hashes.get(&U256::from(5)),
+            Some(&B256::repeat_byte(0xab))
+        );
+        assert_eq!(resumed.executor.env.block.timestamp, U256::from(1_000));
+        assert_eq!(resumed.rpc_config(), &rpc_config);
+    }
+
+    #[tokio::test]
+    async fn test_sessions_reject_precompiles() {
+        let mut forked_evm = evm_with_code(&[]).await;
+        forked_evm.register_precompile(Address::repeat_byte(3), |_, _| Ok((0, Bytes::new())));
+        assert!(matches!(
+            forked_evm.session(),
+            Err(ForkerError::SessionUnsupported("precompiles"))
+        ));
+    }
+}
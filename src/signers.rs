@@ -0,0 +1,268 @@
+use crate::{ForkedEvm, ForkerError};
+use alloy_rpc_types::TransactionRequest;
+use ethers::{
+    signers::LocalWallet,
+    types::{
+        transaction::eip2718::TypedTransaction, Eip1559TransactionRequest,
+        TransactionRequest as EthersTransactionRequest, H160, U256 as EthersU256,
+    },
+    utils::rlp::Rlp,
+};
+use foundry_evm::executors::RawCallResult;
+use revm::primitives::{Address, Bytes, CreateScheme, Env, TransactTo, B256, U256};
+
+/// EIP-2718 type of EIP-1559 transactions.
+const EIP1559_TX_TYPE: u8 = 2;
+
+impl ForkedEvm {
+    /// Registers a wallet whose address can then be used as `from` in
+    /// [ForkedEvm::send_transaction].
+    pub fn register_signer(&mut self, wallet: LocalWallet) -> Address {
+        let address = Address::from(ethers::signers::Signer::address(&wallet).0);
+        self.signers.insert(address, wallet);
+        address
+    }
+
+    /// Registers a signer from a hex encoded private key.
+    pub fn register_private_key(&mut self, private_key: &str) -> Result<Address, ForkerError> {
+        let wallet = private_key
+            .trim_start_matches("0x")
+            .parse::<LocalWallet>()
+            .map_err(|e| ForkerError::InvalidPrivateKey(e.to_string()))?;
+        Ok(self.register_signer(wallet))
+    }
+
+    /// Fills nonce, chain id, gas and fees of `tx` from the fork state where
+    /// unset, signs it with the registered signer of its `from` and executes
+    /// the signed transaction on the fork. Returns the tx hash.
+    pub fn send_transaction(
+        &mut self,
+        tx: &TransactionRequest,
+    ) -> Result<(B256, RawCallResult), ForkerError> {
+        let from = tx.from.ok_or_else(|| {
+            ForkerError::InvalidTransaction("transaction has no `from`".to_string())
+        })?;
+        let wallet = self
+            .signers
+            .get(&from)
+            .ok_or(ForkerError::MissingSigner(from))?;
+        let tx = self.typed_tx_from_request(tx, from)?;
+        let signature = wallet
+            .sign_transaction_sync(&tx)
+            .map_err(|e| ForkerError::SigningFailed(e.to_string()))?;
+        self.send_raw_transaction(&tx.rlp_signed(&signature))
+    }
+
+    /// Decodes a signed raw transaction, recovers its sender and executes it
+    /// on the fork, committing the result. Returns the tx hash. Transactions
+    /// signed for another chain or with another nonce than the sender's are
+    /// rejected.
+    pub fn send_raw_transaction(
+        &mut self,
+        raw: &[u8],
+    ) -> Result<(B256, RawCallResult), ForkerError> {
+        let (tx, signature) = TypedTransaction::decode_signed(&Rlp::new(raw))
+            .map_err(|e| ForkerError::InvalidTransaction(e.to_string()))?;
+        let from = signature
+            .recover(tx.sighash())
+            .map_err(|e| ForkerError::InvalidTransaction(e.to_string()))?;
+        let env = self.env_from_typed_tx(&tx, Address::from(from.0))?;
+        if let Some(chain_id) = env.tx.chain_id {
+            if chain_id != self.executor.env.cfg.chain_id {
+                return Err(ForkerError::ChainIdMismatch {
+                    expected: self.executor.env.cfg.chain_id,
+                    got: chain_id,
+                });
+            }
+        }
+        let expected_nonce = self.nonce_of(env.tx.caller)?;
+        let nonce = env.tx.nonce.unwrap_or_default();
+        if nonce != expected_nonce {
+            return Err(ForkerError::NonceMismatch {
+                expected: expected_nonce,
+                got: nonce,
+            });
+        }
+        let hash = B256::from(tx.hash(&signature).0);
+        let result = self.commit_tx(env, Some(hash))?;
+        Ok((hash, result))
+    }
+
+    /// Fills the unset fields of `tx` from the fork state and encodes it as
+    /// a legacy or, with any EIP-1559 fee set, an EIP-1559 transaction.
+    fn typed_tx_from_request(
+        &self,
+        tx: &TransactionRequest,
+        from: Address,
+    ) -> Result<TypedTransaction, ForkerError> {
+        let env = &self.executor.env;
+        let nonce = match tx.nonce {
+            Some(nonce) => nonce.to::<u64>(),
+            None => self.nonce_of(from)?,
+        };
+        let chain_id = tx.chain_id.map_or(env.cfg.chain_id, |id| id.to::<u64>());
+        let gas = tx
+            .gas
+            .unwrap_or(U256::from(self.executor.gas_limit.saturating_to::<u64>()));
+        let data = tx.input.input().cloned().unwrap_or_default();
+        let eip1559 = tx.transaction_type.map(|ty| ty.to::<u8>()) == Some(EIP1559_TX_TYPE)
+            || tx.max_fee_per_gas.is_some()
+            || tx.max_priority_fee_per_gas.is_some();
+        let mut typed_tx: TypedTransaction = if eip1559 {
+            let mut request = Eip1559TransactionRequest::new()
+                .from(to_h160(from))
+                .max_fee_per_gas(to_ethers_u256(
+                    tx.max_fee_per_gas.unwrap_or(env.block.basefee),
+                ))
+                .max_priority_fee_per_gas(to_ethers_u256(
+                    tx.max_priority_fee_per_gas.unwrap_or_default(),
+                ))
+                .chain_id(chain_id);
+            if let Some(to) = tx.to {
+                request = request.to(to_h160(to));
+            }
+            request.into()
+        } else {
+            let mut request = EthersTransactionRequest::new()
+                .from(to_h160(from))
+                .gas_price(to_ethers_u256(tx.gas_price.unwrap_or(env.block.basefee)))
+                .chain_id(chain_id);
+            if let Some(to) = tx.to {
+                request = request.to(to_h160(to));
+            }
+            request.into()
+        };
+        typed_tx.set_nonce(nonce);
+        typed_tx.set_gas(to_ethers_u256(gas));
+        typed_tx.set_value(to_ethers_u256(tx.value.unwrap_or_default()));
+        typed_tx.set_data(data.to_vec().into());
+        Ok(typed_tx)
+    }
+
+    fn env_from_typed_tx(&self, tx: &TypedTransaction, from: Address) -> Result<Env, ForkerError> {
+        let to_u64 = |value: &EthersU256, field: &str| {
+            u64::try_from(*value)
+                .map_err(|_| ForkerError::InvalidTransaction(format!("`{field}` exceeds u64")))
+        };
+        let mut env = self.executor.env.clone();
+        env.tx.caller = from;
+        env.tx.transact_to = match tx.to_addr() {
+            Some(to) => TransactTo::Call(Address::from(to.0)),
+            None => TransactTo::Create(CreateScheme::Create),
+        };
+        env.tx.data = tx
+            .data()
+            .map(|data| Bytes::from(data.to_vec()))
+            .unwrap_or_default();
+        env.tx.value = tx.value().map(from_ethers_u256).unwrap_or_default();
+        env.tx.gas_limit = match tx.gas() {
+            Some(gas) => to_u64(gas, "gas")?,
+            None => self.executor.gas_limit.saturating_to(),
+        };
+        env.tx.nonce = tx.nonce().map(|nonce| to_u64(nonce, "nonce")).transpose()?;
+        env.tx.chain_id = tx.chain_id().map(|chain_id| chain_id.as_u64());
+        if let Some(gas_price) = tx.gas_price() {
+            env.tx.gas_price = from_ethers_u256(&gas_price);
+        }
+        if let TypedTransaction::Eip1559(inner) = tx {
+            env.tx.gas_priority_fee = inner
+                .max_priority_fee_per_gas
+                .as_ref()
+                .map(from_ethers_u256);
+        }
+        Ok(env)
+    }
+}
+
+fn to_h160(address: Address) -> H160 {
+    H160::from(address.0 .0)
+}
+
+pub(crate) fn from_ethers_u256(value: &EthersU256) -> U256 {
+    let mut bytes = [0u8; 32];
+    value.to_big_endian(&mut bytes);
+    U256::from_be_bytes(bytes)
+}
+
+pub(crate) fn to_ethers_u256(value: U256) -> EthersU256 {
+    EthersU256::from_big_endian(&value.to_be_bytes::<32>())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::evm_with_code;
+    use alloy_rpc_types::TransactionInput;
+    use revm::primitives::alloy_primitives::U64;
+
+    // the first default anvil account
+    const PRIVATE_KEY: &str = "ac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80";
+
+    fn request(from: Address, to: Address) -> TransactionRequest {
+        TransactionRequest {
+            from: Some(from),
+            to: Some(to),
+            input: TransactionInput::default(),
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_send_transaction_signs_with_the_registered_key() {
+        let target = Address::repeat_byte(0x11);
+        let mut forked_evm = evm_with_code(&[(target, "600160020160005260206000f3")]).await;
+        let from = forked_evm.register_private_key(PRIVATE_KEY).unwrap();
+        assert_eq!(
+            from,
+            revm::primitives::address!("f39Fd6e51aad88F6F4ce6aB8827279cffFb92266")
+        );
+
+        let (hash, result) = forked_evm.send_transaction(&request(from, target)).unwrap();
+        assert_ne!(hash, B256::ZERO);
+        assert_eq!(U256::from_be_slice(&result.result), U256::from(3));
+        assert_eq!(forked_evm.nonce_of(from).unwrap(), 1);
+
+        let eip1559 = TransactionRequest {
+            max_priority_fee_per_gas: Some(U256::ZERO),
+            ..request(from, target)
+        };
+        forked_evm.send_transaction(&eip1559).unwrap();
+        assert_eq!(forked_evm.nonce_of(from).unwrap(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_send_transaction_rejects_bad_input() {
+        let target = Address::repeat_byte(0x11);
+        let mut forked_evm = evm_with_code(&[(target, "00")]).await;
+        let from = forked_evm.register_private_key(PRIVATE_KEY).unwrap();
+
+        let unknown = Address::repeat_byte(0x22);
+        assert!(matches!(
+            forked_evm.send_transaction(&request(unknown, target)),
+            Err(ForkerError::MissingSigner(address)) if address == unknown
+        ));
+        let stale = TransactionRequest {
+            nonce: Some(U64::from(5)),
+            ..request(from, target)
+        };
+        assert!(matches!(
+            forked_evm.send_transaction(&stale),
+            Err(ForkerError::NonceMismatch {
+                expected: 0,
+                got: 5
+            })
+        ));
+        let other_chain = TransactionRequest {
+            chain_id: Some(U64::from(5)),
+            ..request(from, target)
+        };
+        assert!(matches!(
+            forked_evm.send_transaction(&other_chain),
+            Err(ForkerError::ChainIdMismatch { got: 5, .. })
+        ));
+        assert!(matches!(
+            forked_evm.send_raw_transaction(&[0xde, 0xad]),
+            Err(ForkerError::InvalidTransaction(_))
+        ));
+    }
+}
@@ -0,0 +1,48 @@
+use crate::{ForkedEvm, ForkerError};
+
+impl ForkedEvm {
+    /// Rolls the active fork through `start..=end` every `step` blocks and
+    /// runs `f` at each block, with the block env taken from the real
+    /// header, see [ForkedEvm::roll_to_block]. Local writes don't carry over
+    /// between blocks, and the fork is left pinned at the last visited block.
+    pub fn simulate_over_blocks<T>(
+        &mut self,
+        start: u64,
+        end: u64,
+        step: u64,
+        mut f: impl FnMut(&mut Self, u64) -> Result<T, ForkerError>,
+    ) -> Result<Vec<(u64, T)>, ForkerError> {
+        if step == 0 {
+            return Err(ForkerError::ZeroBlockStep);
+        }
+        let mut results = vec![];
+        for block_number in (start..=end).step_by(step as usize) {
+            self.roll_to_block(block_number, false)?;
+            results.push((block_number, f(self, block_number)?));
+        }
+        Ok(results)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{testing::evm_with_code, ForkerError};
+
+    #[tokio::test]
+    async fn test_simulate_over_blocks_checks_its_input() {
+        let mut forked_evm = evm_with_code(&[]).await;
+        assert!(matches!(
+            forked_evm.simulate_over_blocks(1, 10, 0, |_, block| Ok(block)),
+            Err(ForkerError::ZeroBlockStep)
+        ));
+        assert!(matches!(
+            forked_evm.simulate_over_blocks(1, 10, 1, |_, block| Ok(block)),
+            Err(ForkerError::NoActiveFork)
+        ));
+        // an empty range visits nothing
+        assert!(forked_evm
+            .simulate_over_blocks(10, 1, 1, |_, block| Ok(block))
+            .unwrap()
+            .is_empty());
+    }
+}
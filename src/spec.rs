@@ -0,0 +1,103 @@
+use crate::{ForkedEvm, ForkerError};
+use revm::primitives::SpecId;
+
+/// Activation blocks of mainnet hardforks, latest first.
+const MAINNET_SPECS: &[(u64, SpecId)] = &[
+    (19_426_587, SpecId::CANCUN),
+    (17_034_870, SpecId::SHANGHAI),
+    (15_537_394, SpecId::MERGE),
+    (15_050_000, SpecId::GRAY_GLACIER),
+    (13_773_000, SpecId::ARROW_GLACIER),
+    (12_965_000, SpecId::LONDON),
+    (12_244_000, SpecId::BERLIN),
+    (9_200_000, SpecId::MUIR_GLACIER),
+    (9_069_000, SpecId::ISTANBUL),
+    (7_280_000, SpecId::PETERSBURG),
+    (4_370_000, SpecId::BYZANTIUM),
+    (2_675_000, SpecId::SPURIOUS_DRAGON),
+    (2_463_000, SpecId::TANGERINE),
+    (1_150_000, SpecId::HOMESTEAD),
+    (0, SpecId::FRONTIER),
+];
+
+/// Activation blocks of sepolia hardforks, latest first.
+const SEPOLIA_SPECS: &[(u64, SpecId)] = &[
+    (5_187_023, SpecId::CANCUN),
+    (2_990_908, SpecId::SHANGHAI),
+    (1_735_371, SpecId::MERGE),
+    (0, SpecId::LONDON),
+];
+
+/// Hardfork active at `block_number` on `chain_id`, [SpecId::LATEST] for
+/// chains without a known schedule.
+pub fn spec_for_block(chain_id: u64, block_number: u64) -> SpecId {
+    let specs = match chain_id {
+        1 => MAINNET_SPECS,
+        11155111 => SEPOLIA_SPECS,
+        _ => return SpecId::LATEST,
+    };
+    specs
+        .iter()
+        .find(|(activation, _)| block_number >= *activation)
+        .map_or(SpecId::LATEST, |(_, spec_id)| *spec_id)
+}
+
+impl ForkedEvm {
+    /// Hardfork the active fork executes with.
+    pub fn spec_id(&self) -> SpecId {
+        self.executor.env.cfg.spec_id
+    }
+
+    /// Overrides the hardfork of the active fork, kept across fork switches
+    /// and rolls to other blocks.
+    pub fn set_spec_id(&mut self, spec_id: SpecId) -> Result<(), ForkerError> {
+        let local_id = self
+            .active_fork()
+            .map(|info| info.local_id)
+            .ok_or(ForkerError::NoActiveFork)?;
+        self.executor.env.cfg.spec_id = spec_id;
+        if let Some(info) = self
+            .forks
+            .values_mut()
+            .find(|info| info.local_id == local_id)
+        {
+            info.env.cfg.spec_id = spec_id;
+            info.spec_id = Some(spec_id);
+        }
+        Ok(())
+    }
+
+    /// Hardfork detected for the executor's current chain and block.
+    pub(crate) fn detect_spec_id(&self) -> SpecId {
+        spec_for_block(
+            self.executor.env.cfg.chain_id,
+            self.executor.env.block.number.saturating_to(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::spec_for_block;
+    use crate::{testing::evm_with_code, ForkerError};
+    use revm::primitives::SpecId;
+
+    #[test]
+    fn test_specs_activate_at_their_block() {
+        assert_eq!(spec_for_block(1, 0), SpecId::FRONTIER);
+        assert_eq!(spec_for_block(1, 17_034_869), SpecId::MERGE);
+        assert_eq!(spec_for_block(1, 17_034_870), SpecId::SHANGHAI);
+        assert_eq!(spec_for_block(1, 30_000_000), SpecId::CANCUN);
+        assert_eq!(spec_for_block(11155111, 0), SpecId::LONDON);
+        assert_eq!(spec_for_block(137, 1), SpecId::LATEST);
+    }
+
+    #[tokio::test]
+    async fn test_set_spec_id_requires_fork() {
+        let mut forked_evm = evm_with_code(&[]).await;
+        assert!(matches!(
+            forked_evm.set_spec_id(SpecId::SHANGHAI),
+            Err(ForkerError::NoActiveFork)
+        ));
+    }
+}
@@ -0,0 +1,145 @@
+use crate::{origin::swap_origin, ForkedEvm, ForkerError};
+use foundry_evm::{executors::RawCallResult, inspectors::InspectorStack};
+
+/// Inspectors run alongside every call and write. Each one adds overhead,
+/// so read heavy workloads like quoting loops can turn them off.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InspectorConfig {
+    /// Collect logs, including hardhat console logs.
+    pub logs: bool,
+    /// Record call traces, e.g. for [crate::report::render_call].
+    pub traces: bool,
+}
+
+impl Default for InspectorConfig {
+    fn default() -> Self {
+        Self {
+            logs: true,
+            traces: false,
+        }
+    }
+}
+
+impl InspectorConfig {
+    /// No inspectors at all, for the fastest possible execution.
+    pub fn none() -> Self {
+        Self {
+            logs: false,
+            traces: false,
+        }
+    }
+
+    /// Logs and traces.
+    pub fn all() -> Self {
+        Self {
+            logs: true,
+            traces: true,
+        }
+    }
+
+    pub(crate) fn apply(&self, stack: &mut InspectorStack) {
+        stack.collect_logs(self.logs);
+        stack.tracing(self.traces);
+    }
+}
+
+impl ForkedEvm {
+    pub fn inspector_config(&self) -> InspectorConfig {
+        self.inspector_config
+    }
+
+    /// Sets the inspectors run by every subsequent call and write.
+    pub fn set_inspector_config(&mut self, config: InspectorConfig) {
+        // memoized reads were recorded with the previous inspectors
+        self.bump_state_version();
+        config.apply(&mut self.executor.inspector);
+        self.inspector_config = config;
+    }
+
+    /// Like [ForkedEvm::call] without any inspectors, so the result has no
+    /// logs or traces.
+    pub fn call_fast(
+        &self,
+        from_address: &[u8],
+        to_address: &[u8],
+        calldata: &[u8],
+    ) -> Result<RawCallResult, ForkerError> {
+        self.call_with_inspectors(from_address, to_address, calldata, InspectorConfig::none())
+    }
+
+    /// Like [ForkedEvm::call] with logs and call traces recorded.
+    pub fn call_traced(
+        &self,
+        from_address: &[u8],
+        to_address: &[u8],
+        calldata: &[u8],
+    ) -> Result<RawCallResult, ForkerError> {
+        self.call_with_inspectors(from_address, to_address, calldata, InspectorConfig::all())
+    }
+
+    fn call_with_inspectors(
+        &self,
+        from_address: &[u8],
+        to_address: &[u8],
+        calldata: &[u8],
+        config: InspectorConfig,
+    ) -> Result<RawCallResult, ForkerError> {
+        if config == self.inspector_config {
+            return self.call(from_address, to_address, calldata);
+        }
+        let mut stack = self.executor.inspector.clone();
+        config.apply(&mut stack);
+        let mut env = self.build_env(from_address, to_address, calldata)?;
+        // origin, precompile and block hash overrides run the stack inside
+        // their own inspector
+        let sender = self
+            .default_origin
+            .and_then(|origin| swap_origin(&mut env, origin));
+        if sender.is_some() || self.needs_fork_inspector() {
+            return self.call_inspected_with(env, sender, stack);
+        }
+        let mut executor = self.executor.clone();
+        executor.inspector = stack;
+        let probe = self.start_probe();
+        let result = executor
+            .call_raw_with_env(env)
+            .map_err(ForkerError::executor);
+        self.finish_probe(probe, &result, false);
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::InspectorConfig;
+    use crate::testing::evm_with_code;
+    use revm::primitives::{Address, Bytes};
+
+    #[tokio::test]
+    async fn test_call_traced_keeps_traces_with_overrides() {
+        let sender = Address::repeat_byte(0x11);
+        let target = Address::repeat_byte(0x22);
+        let mut forked_evm = evm_with_code(&[(target, "00")]).await;
+        let traced = forked_evm
+            .call_traced(sender.as_slice(), target.as_slice(), &[])
+            .unwrap();
+        assert!(traced.traces.is_some());
+        let plain = forked_evm
+            .call(sender.as_slice(), target.as_slice(), &[])
+            .unwrap();
+        assert!(plain.traces.is_none());
+
+        forked_evm.register_precompile(Address::repeat_byte(3), |_, _| Ok((0, Bytes::new())));
+        forked_evm.set_default_origin(Some(Address::repeat_byte(0x33)));
+        let traced = forked_evm
+            .call_traced(sender.as_slice(), target.as_slice(), &[])
+            .unwrap();
+        assert!(traced.traces.is_some());
+
+        forked_evm.set_inspector_config(InspectorConfig::all());
+        let traced = forked_evm
+            .call(sender.as_slice(), target.as_slice(), &[])
+            .unwrap();
+        assert!(traced.traces.is_some());
+    }
+}
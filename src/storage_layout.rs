@@ -0,0 +1,424 @@
+use crate::{ForkedEvm, ForkerError};
+use alloy_dyn_abi::{DynSolType, DynSolValue};
+use revm::primitives::{alloy_primitives::I256, keccak256, Address, B256, U256};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Longest dynamic array, in elements, or bytes value, in bytes, decoded
+/// from storage. Lengths are read from the chain and bound the allocation.
+pub const MAX_STORAGE_LENGTH: usize = 1 << 20;
+
+/// `storageLayout` output of solc, as found in solc and forge artifacts.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct StorageLayout {
+    pub storage: Vec<StorageEntry>,
+    /// solc writes `null` for contracts without storage.
+    #[serde(default)]
+    pub types: Option<HashMap<String, StorageType>>,
+}
+
+/// A state variable or struct member.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StorageEntry {
+    pub label: String,
+    pub offset: usize,
+    /// Decimal slot number, relative to the parent struct for members.
+    pub slot: String,
+    #[serde(rename = "type")]
+    pub ty: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StorageType {
+    /// One of `inplace`, `mapping`, `dynamic_array` or `bytes`.
+    pub encoding: String,
+    pub label: String,
+    pub number_of_bytes: String,
+    pub key: Option<String>,
+    pub value: Option<String>,
+    pub base: Option<String>,
+    pub members: Option<Vec<StorageEntry>>,
+}
+
+impl StorageLayout {
+    pub fn entry(&self, label: &str) -> Result<&StorageEntry, ForkerError> {
+        self.storage
+            .iter()
+            .find(|entry| entry.label == label)
+            .ok_or_else(|| ForkerError::StorageLayout(format!("no variable named {label}")))
+    }
+
+    pub fn ty(&self, id: &str) -> Result<&StorageType, ForkerError> {
+        self.types
+            .as_ref()
+            .and_then(|types| types.get(id))
+            .ok_or_else(|| ForkerError::StorageLayout(format!("unknown type {id}")))
+    }
+}
+
+impl StorageEntry {
+    fn slot(&self) -> Result<U256, ForkerError> {
+        self.slot
+            .parse()
+            .map_err(|_| ForkerError::StorageLayout(format!("invalid slot {}", self.slot)))
+    }
+}
+
+impl StorageType {
+    fn size(&self) -> Result<usize, ForkerError> {
+        self.number_of_bytes.parse().map_err(|_| {
+            ForkerError::StorageLayout(format!("invalid size {}", self.number_of_bytes))
+        })
+    }
+}
+
+impl ForkedEvm {
+    /// Reads and decodes the state variable `name` of the contract at
+    /// `address` according to its storage layout.
+    pub fn read_variable(
+        &self,
+        address: Address,
+        layout: &StorageLayout,
+        name: &str,
+    ) -> Result<DynSolValue, ForkerError> {
+        let entry = layout.entry(name)?;
+        LayoutReader::new(self, address, layout).read(entry.slot()?, entry.offset, &entry.ty)
+    }
+
+    /// Reads and decodes `name[keys[0]][keys[1]]...` of the contract at
+    /// `address`, one key per level of nested mappings.
+    pub fn read_mapping(
+        &self,
+        address: Address,
+        layout: &StorageLayout,
+        name: &str,
+        keys: &[DynSolValue],
+    ) -> Result<DynSolValue, ForkerError> {
+        let entry = layout.entry(name)?;
+        let mut slot = entry.slot()?;
+        let mut ty_id = &entry.ty;
+        for key in keys {
+            let ty = layout.ty(ty_id)?;
+            let (true, Some(value)) = (ty.encoding == "mapping", &ty.value) else {
+                return Err(ForkerError::StorageLayout(format!(
+                    "{} is not a mapping",
+                    ty.label
+                )));
+            };
+            slot = mapping_slot(slot, key)?;
+            ty_id = value;
+        }
+        LayoutReader::new(self, address, layout).read(slot, 0, ty_id)
+    }
+}
+
+/// Slot of `mapping[key]` for a mapping rooted at `slot`.
+pub fn mapping_slot(slot: U256, key: &DynSolValue) -> Result<U256, ForkerError> {
+    let key = match key {
+        DynSolValue::String(key) => key.as_bytes().to_vec(),
+        DynSolValue::Bytes(key) => key.clone(),
+        key => key
+            .as_word()
+            .ok_or_else(|| ForkerError::StorageLayout(format!("unsupported key {key:?}")))?
+            .to_vec(),
+    };
+    Ok(U256::from_be_bytes(
+        keccak256([key.as_slice(), &slot.to_be_bytes::<32>()].concat()).0,
+    ))
+}
+
+struct LayoutReader<'a> {
+    forked_evm: &'a ForkedEvm,
+    address: Address,
+    layout: &'a StorageLayout,
+}
+
+impl<'a> LayoutReader<'a> {
+    fn new(forked_evm: &'a ForkedEvm, address: Address, layout: &'a StorageLayout) -> Self {
+        Self {
+            forked_evm,
+            address,
+            layout,
+        }
+    }
+
+    fn storage(&self, slot: U256) -> Result<U256, ForkerError> {
+        self.forked_evm.storage_at(self.address, slot)
+    }
+
+    /// Checks a length stored at `slot` against [MAX_STORAGE_LENGTH].
+    fn length(&self, slot: U256, len: U256) -> Result<usize, ForkerError> {
+        usize::try_from(len)
+            .ok()
+            .filter(|len| *len <= MAX_STORAGE_LENGTH)
+            .ok_or(ForkerError::StorageLengthTooLarge {
+                slot,
+                len,
+                max: MAX_STORAGE_LENGTH,
+            })
+    }
+
+    fn read(&self, slot: U256, offset: usize, ty_id: &str) -> Result<DynSolValue, ForkerError> {
+        let ty = self.layout.ty(ty_id)?;
+        match ty.encoding.as_str() {
+            "inplace" => {
+                if let Some(members) = &ty.members {
+                    let values = members
+                        .iter()
+                        .map(|member| self.read(slot + member.slot()?, member.offset, &member.ty))
+                        .collect::<Result<_, _>>()?;
+                    Ok(DynSolValue::Tuple(values))
+                } else if let Some(base) = &ty.base {
+                    let len = fixed_array_len(&ty.label)?;
+                    Ok(DynSolValue::FixedArray(self.read_array(slot, base, len)?))
+                } else {
+                    self.read_value(slot, offset, ty)
+                }
+            }
+            "dynamic_array" => {
+                let base = ty.base.as_deref().ok_or_else(|| {
+                    ForkerError::StorageLayout(format!("{} has no base type", ty.label))
+                })?;
+                let len = self.length(slot, self.storage(slot)?)?;
+                Ok(DynSolValue::Array(self.read_array(
+                    data_slot(slot),
+                    base,
+                    len,
+                )?))
+            }
+            "bytes" => {
+                let bytes = self.read_bytes(slot)?;
+                Ok(if ty.label == "string" {
+                    DynSolValue::String(String::from_utf8_lossy(&bytes).into_owned())
+                } else {
+                    DynSolValue::Bytes(bytes)
+                })
+            }
+            "mapping" => Err(ForkerError::StorageLayout(format!(
+                "{} is a mapping, read it with read_mapping",
+                ty.label
+            ))),
+            encoding => Err(ForkerError::StorageLayout(format!(
+                "unsupported encoding {encoding}"
+            ))),
+        }
+    }
+
+    /// Arrays pack elements smaller than a slot, bigger ones start on a new slot.
+    fn read_array(
+        &self,
+        start: U256,
+        base: &str,
+        len: usize,
+    ) -> Result<Vec<DynSolValue>, ForkerError> {
+        let size = self.layout.ty(base)?.size()?;
+        (0..len)
+            .map(|i| {
+                if size < 32 {
+                    let per_slot = 32 / size;
+                    self.read(
+                        start + U256::from(i / per_slot),
+                        (i % per_slot) * size,
+                        base,
+                    )
+                } else {
+                    self.read(start + U256::from(i * size.div_ceil(32)), 0, base)
+                }
+            })
+            .collect()
+    }
+
+    fn read_bytes(&self, slot: U256) -> Result<Vec<u8>, ForkerError> {
+        let word = self.storage(slot)?;
+        // short values live in the slot itself with `len * 2` in the lowest
+        // byte, long ones store `len * 2 + 1` and their data at keccak(slot)
+        if !word.bit(0) {
+            let len = (word & U256::from(0xff)) >> 1;
+            return word
+                .to_be_bytes::<32>()
+                .get(..len.to::<usize>())
+                .filter(|bytes| bytes.len() < 32)
+                .map(<[u8]>::to_vec)
+                .ok_or(ForkerError::StorageLengthTooLarge { slot, len, max: 31 });
+        }
+        let len = self.length(slot, word >> 1)?;
+        let start = data_slot(slot);
+        let mut bytes = Vec::with_capacity(len.div_ceil(32) * 32);
+        for i in 0..len.div_ceil(32) {
+            bytes.extend_from_slice(&self.storage(start + U256::from(i))?.to_be_bytes::<32>());
+        }
+        bytes.truncate(len);
+        Ok(bytes)
+    }
+
+    fn read_value(
+        &self,
+        slot: U256,
+        offset: usize,
+        ty: &StorageType,
+    ) -> Result<DynSolValue, ForkerError> {
+        let size = ty.size()?;
+        let mut value = self.storage(slot)? >> (offset * 8);
+        if size < 32 {
+            value &= (U256::from(1) << (size * 8)) - U256::from(1);
+        }
+        Ok(match value_type(&ty.label, size)? {
+            DynSolType::Address => DynSolValue::Address(Address::from_word(B256::from(value))),
+            DynSolType::Bool => DynSolValue::Bool(value != U256::ZERO),
+            DynSolType::Uint(bits) => DynSolValue::Uint(value, bits),
+            DynSolType::Int(bits) => {
+                if bits < 256 && value.bit(bits - 1) {
+                    value |= U256::MAX << bits;
+                }
+                DynSolValue::Int(I256::from_raw(value), bits)
+            }
+            DynSolType::FixedBytes(len) => {
+                let mut word = B256::ZERO;
+                word[..len].copy_from_slice(&value.to_be_bytes::<32>()[32 - len..]);
+                DynSolValue::FixedBytes(word, len)
+            }
+            ty => {
+                return Err(ForkerError::StorageLayout(format!(
+                    "unsupported value type {ty}"
+                )))
+            }
+        })
+    }
+}
+
+/// Solidity type of an inplace value, mapping the layout's contract and
+/// enum labels to what they are stored as.
+fn value_type(label: &str, size: usize) -> Result<DynSolType, ForkerError> {
+    if label.starts_with("contract ") || label == "address payable" {
+        return Ok(DynSolType::Address);
+    }
+    if label.starts_with("enum ") {
+        return Ok(DynSolType::Uint(size * 8));
+    }
+    DynSolType::parse(label)
+        .map_err(|e| ForkerError::StorageLayout(format!("unsupported type {label}: {e}")))
+}
+
+/// Length of a fixed array from its label, e.g. `3` for `uint8[3]`.
+fn fixed_array_len(label: &str) -> Result<usize, ForkerError> {
+    label
+        .rsplit_once('[')
+        .and_then(|(_, len)| len.strip_suffix(']'))
+        .and_then(|len| len.parse().ok())
+        .ok_or_else(|| ForkerError::StorageLayout(format!("not a fixed array {label}")))
+}
+
+/// First slot of the data of a dynamic array or long bytes rooted at `slot`.
+fn data_slot(slot: U256) -> U256 {
+    U256::from_be_bytes(keccak256(slot.to_be_bytes::<32>()).0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::evm_with_code;
+    use revm::primitives::Bytes;
+
+    const LAYOUT: &str = r#"{
+        "storage": [
+            {"label": "total", "offset": 0, "slot": "0", "type": "t_uint256"},
+            {"label": "small", "offset": 0, "slot": "1", "type": "t_uint8"},
+            {"label": "packed", "offset": 1, "slot": "1", "type": "t_int8"},
+            {"label": "name", "offset": 0, "slot": "2", "type": "t_string_storage"},
+            {"label": "items", "offset": 0, "slot": "3", "type": "t_array(t_uint256)dyn_storage"},
+            {"label": "balances", "offset": 0, "slot": "4", "type": "t_mapping(t_address,t_uint256)"}
+        ],
+        "types": {
+            "t_address": {"encoding": "inplace", "label": "address", "numberOfBytes": "20"},
+            "t_int8": {"encoding": "inplace", "label": "int8", "numberOfBytes": "1"},
+            "t_uint8": {"encoding": "inplace", "label": "uint8", "numberOfBytes": "1"},
+            "t_uint256": {"encoding": "inplace", "label": "uint256", "numberOfBytes": "32"},
+            "t_string_storage": {"encoding": "bytes", "label": "string", "numberOfBytes": "32"},
+            "t_array(t_uint256)dyn_storage": {
+                "encoding": "dynamic_array", "label": "uint256[]", "numberOfBytes": "32",
+                "base": "t_uint256"
+            },
+            "t_mapping(t_address,t_uint256)": {
+                "encoding": "mapping", "label": "mapping(address => uint256)",
+                "numberOfBytes": "32", "key": "t_address", "value": "t_uint256"
+            }
+        }
+    }"#;
+
+    async fn evm_with_storage(slots: Vec<(U256, U256)>) -> (ForkedEvm, Address) {
+        let address = Address::repeat_byte(0x11);
+        let mut forked_evm = evm_with_code(&[]).await;
+        forked_evm
+            .insert_account(address, U256::ZERO, 0, Bytes::from_static(&[0x00]), slots)
+            .unwrap();
+        (forked_evm, address)
+    }
+
+    #[tokio::test]
+    async fn test_read_variables_and_mappings() {
+        let layout: StorageLayout = serde_json::from_str(LAYOUT).unwrap();
+        let holder = Address::repeat_byte(0x22);
+        let balance_slot = mapping_slot(U256::from(4), &DynSolValue::Address(holder)).unwrap();
+        let mut name = [0u8; 32];
+        name[..5].copy_from_slice(b"forks");
+        name[31] = 10;
+        let (forked_evm, address) = evm_with_storage(vec![
+            (U256::from(0), U256::from(42)),
+            // small = 7, packed = -1
+            (U256::from(1), U256::from(0xff07)),
+            (U256::from(2), U256::from_be_bytes(name)),
+            (U256::from(3), U256::from(2)),
+            (data_slot(U256::from(3)), U256::from(5)),
+            (data_slot(U256::from(3)) + U256::from(1), U256::from(6)),
+            (balance_slot, U256::from(100)),
+        ])
+        .await;
+
+        let read = |name| forked_evm.read_variable(address, &layout, name).unwrap();
+        assert_eq!(read("total"), DynSolValue::Uint(U256::from(42), 256));
+        assert_eq!(read("small"), DynSolValue::Uint(U256::from(7), 8));
+        assert_eq!(read("packed"), DynSolValue::Int(I256::MINUS_ONE, 8));
+        assert_eq!(read("name"), DynSolValue::String("forks".to_string()));
+        assert_eq!(
+            read("items"),
+            DynSolValue::Array(vec![
+                DynSolValue::Uint(U256::from(5), 256),
+                DynSolValue::Uint(U256::from(6), 256),
+            ])
+        );
+        assert_eq!(
+            forked_evm
+                .read_mapping(
+                    address,
+                    &layout,
+                    "balances",
+                    &[DynSolValue::Address(holder)]
+                )
+                .unwrap(),
+            DynSolValue::Uint(U256::from(100), 256)
+        );
+        assert!(matches!(
+            forked_evm.read_variable(address, &layout, "balances"),
+            Err(ForkerError::StorageLayout(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_garbage_lengths_are_rejected() {
+        let layout: StorageLayout = serde_json::from_str(LAYOUT).unwrap();
+        let (forked_evm, address) = evm_with_storage(vec![
+            // a long string claiming U256::MAX / 2 bytes
+            (U256::from(2), U256::MAX),
+            (U256::from(3), U256::MAX),
+        ])
+        .await;
+
+        for name in ["name", "items"] {
+            assert!(matches!(
+                forked_evm.read_variable(address, &layout, name),
+                Err(ForkerError::StorageLengthTooLarge { .. })
+            ));
+        }
+    }
+}
@@ -0,0 +1,93 @@
+//! Helpers shared by the unit tests.
+
+use crate::ForkedEvm;
+use foundry_evm::backend::Backend;
+use revm::primitives::{hex, AccountInfo, Address, Bytecode, Bytes, Env};
+
+/// Fork-less evm with each `(address, hex runtime code)` deployed, so tests
+/// can run without an RPC endpoint.
+pub(crate) async fn evm_with_code(contracts: &[(Address, &str)]) -> ForkedEvm {
+    let backend = Backend::spawn(None).await;
+    let mut forked_evm = ForkedEvm::new_with_shared_backend(backend, Env::default(), None);
+    for (address, code) in contracts {
+        let code = Bytecode::new_raw(Bytes::from(hex::decode(code).unwrap()));
+        forked_evm.executor.backend.insert_account_info(
+            *address,
+            AccountInfo {
+                code_hash: code.hash_slow(),
+                code: Some(code),
+                ..Default::default()
+            },
+        );
+    }
+    forked_evm
+}
+
+/// Runtime code that returns `data` for any calldata.
+pub(crate) fn returning_code(data: &[u8]) -> String {
+    let len = u16::try_from(data.len()).unwrap().to_be_bytes();
+    let mut code = vec![0x61, len[0], len[1], 0x60, 0x0e, 0x60, 0x00, 0x39];
+    code.extend([0x61, len[0], len[1], 0x60, 0x00, 0xf3]);
+    code.extend_from_slice(data);
+    hex::encode(code)
+}
+
+/// Runtime code that reverts with `data` for any calldata.
+pub(crate) fn reverting_code(data: &[u8]) -> String {
+    let mut code = returning_code(data);
+    // swap the RETURN after the copy for a REVERT
+    code.replace_range(26..28, "fd");
+    code
+}
+
+/// Json-rpc client answering every request with a fixed value, or with a
+/// json-rpc error of the given code.
+#[cfg(feature = "native")]
+#[derive(Debug)]
+pub(crate) struct FixedClient(pub(crate) Result<serde_json::Value, i64>);
+
+#[cfg(feature = "native")]
+#[async_trait::async_trait]
+impl ethers::providers::JsonRpcClient for FixedClient {
+    type Error = ethers::providers::HttpClientError;
+
+    async fn request<T, R>(&self, _method: &str, _params: T) -> Result<R, Self::Error>
+    where
+        T: std::fmt::Debug + serde::Serialize + Send + Sync,
+        R: serde::de::DeserializeOwned + Send,
+    {
+        use ethers::providers::{HttpClientError, JsonRpcError};
+        match &self.0 {
+            Ok(value) => {
+                serde_json::from_value(value.clone()).map_err(|err| HttpClientError::SerdeJson {
+                    err,
+                    text: value.to_string(),
+                })
+            }
+            Err(code) => Err(HttpClientError::JsonRpcError(JsonRpcError {
+                code: *code,
+                message: format!("error {code}"),
+                data: None,
+            })),
+        }
+    }
+}
+
+/// Relay serving a [FixedClient].
+#[cfg(feature = "native")]
+pub(crate) async fn fixed_relay(
+    answer: Result<serde_json::Value, i64>,
+) -> crate::transport::RpcRelay {
+    let upstream = crate::transport::Upstream {
+        urls: vec!["fixed".to_string()],
+        ..Default::default()
+    };
+    let serving = std::sync::Arc::new(std::sync::atomic::AtomicUsize::new(0));
+    crate::transport::RpcRelay::spawn(
+        upstream,
+        serving,
+        move || async move { Ok(FixedClient(answer)) },
+    )
+    .await
+    .unwrap()
+}
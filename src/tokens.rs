@@ -0,0 +1,171 @@
+use crate::{ForkedEvm, ForkerError};
+use alloy_sol_types::sol;
+use foundry_evm::executors::RawCallResult;
+use revm::primitives::{Address, U256};
+
+sol! {
+    interface IERC20 {
+        event Transfer(address indexed from, address indexed to, uint256 value);
+        event Approval(address indexed owner, address indexed spender, uint256 value);
+
+        function name() external view returns (string memory);
+        function symbol() external view returns (string memory);
+        function decimals() external view returns (uint8);
+        function totalSupply() external view returns (uint256);
+        function balanceOf(address account) external view returns (uint256);
+        function allowance(address owner, address spender) external view returns (uint256);
+        function transfer(address to, uint256 amount) external returns (bool);
+        function approve(address spender, uint256 amount) external returns (bool);
+        function transferFrom(address from, address to, uint256 amount) external returns (bool);
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Erc20Info {
+    pub name: String,
+    pub symbol: String,
+    pub decimals: u8,
+    pub total_supply: U256,
+}
+
+impl ForkedEvm {
+    pub fn erc20_info(&self, token: Address) -> Result<Erc20Info, ForkerError> {
+        let from = Address::ZERO;
+        let token = token.as_slice();
+        Ok(Erc20Info {
+            name: self
+                .alloy_read(from.as_slice(), token, IERC20::nameCall {})?
+                ._0,
+            symbol: self
+                .alloy_read(from.as_slice(), token, IERC20::symbolCall {})?
+                ._0,
+            decimals: self
+                .alloy_read(from.as_slice(), token, IERC20::decimalsCall {})?
+                ._0,
+            total_supply: self
+                .alloy_read(from.as_slice(), token, IERC20::totalSupplyCall {})?
+                ._0,
+        })
+    }
+
+    pub fn erc20_balance_of(&self, token: Address, account: Address) -> Result<U256, ForkerError> {
+        Ok(self
+            .alloy_read(
+                Address::ZERO.as_slice(),
+                token.as_slice(),
+                IERC20::balanceOfCall { account },
+            )?
+            ._0)
+    }
+
+    pub fn erc20_allowance(
+        &self,
+        token: Address,
+        owner: Address,
+        spender: Address,
+    ) -> Result<U256, ForkerError> {
+        Ok(self
+            .alloy_read(
+                Address::ZERO.as_slice(),
+                token.as_slice(),
+                IERC20::allowanceCall { owner, spender },
+            )?
+            ._0)
+    }
+
+    /// Commits `from` transferring `amount` of `token` to `to`.
+    pub fn erc20_transfer(
+        &mut self,
+        token: Address,
+        from: Address,
+        to: Address,
+        amount: U256,
+    ) -> Result<RawCallResult, ForkerError> {
+        self.erc20_write(from, token, IERC20::transferCall { to, amount })
+    }
+
+    /// Commits `owner` approving `spender` for `amount` of `token`.
+    pub fn erc20_approve(
+        &mut self,
+        token: Address,
+        owner: Address,
+        spender: Address,
+        amount: U256,
+    ) -> Result<RawCallResult, ForkerError> {
+        self.erc20_write(owner, token, IERC20::approveCall { spender, amount })
+    }
+
+    /// Commits `spender` moving `amount` of `token` from `from` to `to`.
+    pub fn erc20_transfer_from(
+        &mut self,
+        token: Address,
+        spender: Address,
+        from: Address,
+        to: Address,
+        amount: U256,
+    ) -> Result<RawCallResult, ForkerError> {
+        self.erc20_write(
+            spender,
+            token,
+            IERC20::transferFromCall { from, to, amount },
+        )
+    }
+
+    /// Commits a bool returning erc20 call, treating an explicit `false` as a
+    /// failure. Tokens that return nothing are accepted.
+    fn erc20_write<C: alloy_sol_types::SolCall>(
+        &mut self,
+        from: Address,
+        token: Address,
+        call: C,
+    ) -> Result<RawCallResult, ForkerError> {
+        let result = self.write(
+            from.as_slice(),
+            token.as_slice(),
+            &call.abi_encode(),
+            U256::ZERO,
+        )?;
+        if result.reverted {
+            return Err(ForkerError::reverted(&result));
+        }
+        if !result.result.is_empty() && result.result.iter().all(|byte| *byte == 0) {
+            return Err(ForkerError::Erc20ReturnedFalse { token });
+        }
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{evm_with_code, returning_code};
+
+    #[tokio::test]
+    async fn test_erc20_writes_check_the_returned_bool() {
+        let (succeeding, failing, silent) = (
+            Address::repeat_byte(0x11),
+            Address::repeat_byte(0x12),
+            Address::repeat_byte(0x13),
+        );
+        let one = returning_code(&U256::from(1).to_be_bytes::<32>());
+        let zero = returning_code(&[0u8; 32]);
+        let mut forked_evm =
+            evm_with_code(&[(succeeding, &one), (failing, &zero), (silent, "00")]).await;
+        let (owner, to) = (Address::repeat_byte(0x21), Address::repeat_byte(0x22));
+
+        assert_eq!(
+            forked_evm.erc20_balance_of(succeeding, owner).unwrap(),
+            U256::from(1)
+        );
+        forked_evm
+            .erc20_transfer(succeeding, owner, to, U256::from(1))
+            .unwrap();
+        forked_evm
+            .erc20_approve(silent, owner, to, U256::from(1))
+            .unwrap();
+        assert!(matches!(
+            forked_evm.erc20_transfer(failing, owner, to, U256::from(1)),
+            Err(ForkerError::Erc20ReturnedFalse { token }) if token == failing
+        ));
+    }
+}
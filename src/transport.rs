@@ -0,0 +1,685 @@
+use crate::{failover::FallbackClient, ForkedEvm, ForkerError};
+use async_trait::async_trait;
+use ethers::providers::{Http, Ipc, JsonRpcClient, ProviderError, RpcError, Ws};
+use hyper::{
+    service::{make_service_fn, service_fn},
+    Body, Request, Response, Server, StatusCode,
+};
+use rand::{distributions::Alphanumeric, Rng};
+use revm::primitives::{keccak256, Env, B256};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::{
+    collections::BTreeMap,
+    convert::Infallible,
+    fmt,
+    future::Future,
+    net::SocketAddr,
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+use tokio::sync::oneshot;
+
+/// RPCs a relayed fork is served from, kept so its relay can be started
+/// again, e.g. by [ForkedEvm::from_session].
+#[derive(Clone, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Upstream {
+    /// RPCs of the same chain in order of preference, http(s), ws(s) or
+    /// ipc, see [ForkedEvm::add_or_select].
+    pub urls: Vec<String>,
+    /// Sent on every request to each of the http RPCs.
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+    /// Deadline of each request to one of the RPCs, see
+    /// [ForkedEvm::set_rpc_config].
+    #[serde(default)]
+    pub timeout: Option<Duration>,
+    /// Cap on requests per second to each of the RPCs, see
+    /// [ForkedEvm::set_rpc_config].
+    #[serde(default)]
+    pub requests_per_second: Option<u32>,
+}
+
+impl fmt::Debug for Upstream {
+    /// Header values are left out, as they usually are credentials.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Upstream")
+            .field("urls", &self.urls)
+            .field("headers", &self.headers.keys().collect::<Vec<_>>())
+            .field("timeout", &self.timeout)
+            .field("requests_per_second", &self.requests_per_second)
+            .finish()
+    }
+}
+
+/// Identifies the relay of an upstream, so its forks share one relay. Holds
+/// a digest of the headers instead of the headers themselves.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub(crate) struct RelayKey {
+    urls: Vec<String>,
+    headers: B256,
+    timeout: Option<Duration>,
+    requests_per_second: Option<u32>,
+}
+
+impl RelayKey {
+    pub(crate) fn new(upstream: &Upstream) -> Self {
+        // length prefixed, so no two header maps encode the same
+        let mut headers = vec![];
+        for (name, value) in &upstream.headers {
+            for part in [name, value] {
+                headers.extend_from_slice(&(part.len() as u64).to_be_bytes());
+                headers.extend_from_slice(part.as_bytes());
+            }
+        }
+        Self {
+            urls: upstream.urls.clone(),
+            headers: keccak256(headers),
+            timeout: upstream.timeout,
+            requests_per_second: upstream.requests_per_second,
+        }
+    }
+}
+
+/// What a relay enforces on top of forwarding requests, shared with the
+/// evm that started it.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct RelayPolicy {
+    /// Budgets of the upstream RPCs, in the same order, empty if they
+    /// aren't rate limited.
+    pub(crate) limiters: Vec<Arc<RateLimiter>>,
+    /// Refuses every request while set, see [ForkedEvm::set_offline].
+    pub(crate) offline: Arc<AtomicBool>,
+}
+
+/// Requests per second budget of one provider, spacing requests evenly.
+/// Shared by every relay sending to the provider, whatever runtime it runs
+/// on.
+#[derive(Debug)]
+pub(crate) struct RateLimiter {
+    interval: Duration,
+    next: tokio::sync::Mutex<Instant>,
+}
+
+impl RateLimiter {
+    /// Budget of `requests_per_second`, at least one.
+    pub(crate) fn new(requests_per_second: u32) -> Self {
+        Self {
+            interval: Duration::from_secs(1) / requests_per_second.max(1),
+            next: tokio::sync::Mutex::new(Instant::now()),
+        }
+    }
+
+    /// Waits for the next free slot of the budget.
+    pub(crate) async fn acquire(&self) {
+        let slot = {
+            let mut next = self.next.lock().await;
+            let slot = (*next).max(Instant::now());
+            *next = slot + self.interval;
+            slot
+        };
+        tokio::time::sleep_until(slot.into()).await;
+    }
+}
+
+/// Local http endpoint forwarding json-rpc requests to an upstream client,
+/// for forks the backend can't serve from a single url. It runs on a thread
+/// and runtime of its own, as the fork backend blocks on it from sync calls
+/// that may be running inside the caller's runtime, and only answers
+/// requests to the random path of its url. Stops once the last clone is
+/// dropped.
+#[derive(Clone)]
+pub(crate) struct RpcRelay {
+    pub(crate) url: String,
+    pub(crate) upstream: Upstream,
+    /// Index into the upstream urls of the RPC that last answered.
+    pub(crate) serving: Arc<AtomicUsize>,
+    _shutdown: Arc<Shutdown>,
+}
+
+struct Shutdown(Option<oneshot::Sender<()>>);
+
+impl Drop for Shutdown {
+    fn drop(&mut self) {
+        if let Some(shutdown) = self.0.take() {
+            let _ = shutdown.send(());
+        }
+    }
+}
+
+impl RpcRelay {
+    /// Starts the relay thread, which builds its client with `connect` and
+    /// serves it until the relay is dropped. Resolves once the relay listens.
+    pub(crate) async fn spawn<C, F, Fut>(
+        upstream: Upstream,
+        serving: Arc<AtomicUsize>,
+        connect: F,
+    ) -> Result<Self, ForkerError>
+    where
+        C: JsonRpcClient + 'static,
+        F: FnOnce() -> Fut + Send + 'static,
+        Fut: Future<Output = Result<C, ForkerError>>,
+    {
+        let path = format!("/{}", random_token());
+        let (ready, listening) = oneshot::channel();
+        let (shutdown, stopped) = oneshot::channel::<()>();
+        let served_path = Arc::new(path.clone());
+        std::thread::Builder::new()
+            .name("forker-rpc-relay".to_string())
+            .spawn(move || {
+                let runtime = match tokio::runtime::Builder::new_current_thread()
+                    .enable_all()
+                    .build()
+                {
+                    Ok(runtime) => runtime,
+                    Err(e) => {
+                        let _ = ready.send(Err(ForkerError::from(e)));
+                        return Ok(());
+                    }
+                };
+                let served = runtime.block_on(async move {
+                    let client = match connect().await {
+                        Ok(client) => Arc::new(client),
+                        Err(e) => {
+                            let _ = ready.send(Err(e));
+                            return Ok(());
+                        }
+                    };
+                    let make_service = make_service_fn(move |_| {
+                        let client = client.clone();
+                        let path = served_path.clone();
+                        async move {
+                            Ok::<_, Infallible>(service_fn(move |request| {
+                                relay_http(client.clone(), path.clone(), request)
+                            }))
+                        }
+                    });
+                    let server = match Server::try_bind(&SocketAddr::from(([127, 0, 0, 1], 0))) {
+                        Ok(builder) => builder.serve(make_service),
+                        Err(e) => {
+                            let _ = ready.send(Err(ForkerError::from(e)));
+                            return Ok(());
+                        }
+                    };
+                    let _ = ready.send(Ok(server.local_addr()));
+                    server
+                        .with_graceful_shutdown(async {
+                            let _ = stopped.await;
+                        })
+                        .await
+                });
+                #[cfg(feature = "tracing")]
+                if let Err(e) = &served {
+                    tracing::warn!(error = %e, "rpc relay stopped");
+                }
+                served
+            })?;
+        let addr = listening.await.map_err(|_| ForkerError::WorkerPanicked)??;
+        Ok(Self {
+            url: format!("http://{addr}{path}"),
+            upstream,
+            serving,
+            _shutdown: Arc::new(Shutdown(Some(shutdown))),
+        })
+    }
+
+    /// Relay failing over between the RPCs of `upstream`, connected on the
+    /// relay's thread.
+    pub(crate) async fn connect(
+        upstream: Upstream,
+        policy: RelayPolicy,
+    ) -> Result<Self, ForkerError> {
+        let preferred = Arc::new(AtomicUsize::new(0));
+        let serving = preferred.clone();
+        let client_upstream = upstream.clone();
+        Self::spawn(upstream, preferred, move || async move {
+            FallbackClient::connect(&client_upstream, serving, policy).await
+        })
+        .await
+    }
+
+    /// Upstream RPC that last answered a request.
+    pub(crate) fn serving_url(&self) -> Option<&str> {
+        self.upstream
+            .urls
+            .get(self.serving.load(Ordering::Relaxed))
+            .map(String::as_str)
+    }
+}
+
+/// Client of one upstream RPC, of any transport the relay can serve.
+#[derive(Debug)]
+pub(crate) enum UpstreamClient {
+    Http(Http),
+    Ws(Ws),
+    Ipc(Ipc),
+}
+
+impl UpstreamClient {
+    /// Connects to `url`, an http(s), ws(s) or `ipc://` url or a path to an
+    /// `.ipc` socket. Ws and ipc clients run on the runtime this is called
+    /// on.
+    pub(crate) async fn connect(url: &str, upstream: &Upstream) -> Result<Self, ForkerError> {
+        if is_http(url) {
+            Ok(Self::Http(http_client(url, upstream)?))
+        } else if url.starts_with("ws://") || url.starts_with("wss://") {
+            let client = Ws::connect(url)
+                .await
+                .map_err(|e| ForkerError::rpc(url, e))?;
+            Ok(Self::Ws(client))
+        } else if let Some(path) = url
+            .strip_prefix("ipc://")
+            .or_else(|| url.ends_with(".ipc").then_some(url))
+        {
+            let client = Ipc::connect(path)
+                .await
+                .map_err(|e| ForkerError::rpc(url, e))?;
+            Ok(Self::Ipc(client))
+        } else {
+            Err(ForkerError::InvalidRpcUrl(url.to_string()))
+        }
+    }
+}
+
+#[async_trait]
+impl JsonRpcClient for UpstreamClient {
+    type Error = ProviderError;
+
+    async fn request<T, R>(&self, method: &str, params: T) -> Result<R, Self::Error>
+    where
+        T: fmt::Debug + Serialize + Send + Sync,
+        R: DeserializeOwned + Send,
+    {
+        match self {
+            Self::Http(client) => client.request(method, params).await.map_err(Into::into),
+            Self::Ws(client) => client.request(method, params).await.map_err(Into::into),
+            Self::Ipc(client) => client.request(method, params).await.map_err(Into::into),
+        }
+    }
+}
+
+impl ForkedEvm {
+    /// Url the fork backend reaches `fork_url` at: the url itself for http
+    /// RPCs and relays, and a relay, started on first use, for ws and ipc
+    /// RPCs, which the backend can't speak, and for http ones while a
+    /// request timeout or rate limit is set or while offline, which the
+    /// backend can't enforce.
+    pub(crate) async fn serving_url(&mut self, fork_url: &str) -> Result<String, ForkerError> {
+        let upstream = self.configured_upstream(vec![fork_url.to_string()]);
+        let relayed = self.rpc_relays.values().any(|relay| relay.url == fork_url);
+        let plain = upstream.timeout.is_none()
+            && upstream.requests_per_second.is_none()
+            && !self.is_offline();
+        if relayed || (is_http(fork_url) && plain) {
+            return Ok(fork_url.to_string());
+        }
+        Ok(self.relay_for(upstream).await?.url)
+    }
+
+    /// Upstream of `urls` under the current [ForkedEvm::rpc_config].
+    pub(crate) fn configured_upstream(&self, urls: Vec<String>) -> Upstream {
+        Upstream {
+            urls,
+            headers: BTreeMap::new(),
+            timeout: self.rpc_config.timeout,
+            requests_per_second: self.rpc_config.requests_per_second,
+        }
+    }
+
+    /// Like [ForkedEvm::add_or_select] with `headers` sent on every request
+    /// to `fork_url`, e.g. api keys, cookies or [bearer_auth]. The fork is
+    /// served through a local relay, whose url stands in for `fork_url` in
+    /// [ForkedEvm::forks].
+    pub async fn add_or_select_with_headers(
+        &mut self,
+        fork_url: &str,
+        headers: &BTreeMap<String, String>,
+        fork_block_number: Option<u64>,
+        env: Option<Env>,
+    ) -> Result<(), ForkerError> {
+        let upstream = Upstream {
+            headers: headers.clone(),
+            ..self.configured_upstream(vec![fork_url.to_string()])
+        };
+        let relay = self.relay_for(upstream).await?;
+        self.add_or_select(&relay.url, fork_block_number, env).await
+    }
+
+    /// Relay of `upstream`, started on first use and shared by its forks.
+    pub(crate) async fn relay_for(&mut self, upstream: Upstream) -> Result<RpcRelay, ForkerError> {
+        let key = RelayKey::new(&upstream);
+        if let Some(relay) = self.rpc_relays.get(&key) {
+            return Ok(relay.clone());
+        }
+        // one budget per provider, shared by all relays sending to it
+        let limiters = match upstream.requests_per_second {
+            Some(requests_per_second) => upstream
+                .urls
+                .iter()
+                .map(|url| {
+                    self.rpc_limiters
+                        .entry((url.clone(), requests_per_second))
+                        .or_insert_with(|| Arc::new(RateLimiter::new(requests_per_second)))
+                        .clone()
+                })
+                .collect(),
+            None => vec![],
+        };
+        let policy = RelayPolicy {
+            limiters,
+            offline: self.offline.clone(),
+        };
+        let relay = RpcRelay::connect(upstream, policy).await?;
+        self.rpc_relays.insert(key, relay.clone());
+        Ok(relay)
+    }
+
+    /// Registers a relay started before this evm, attaching it to the forks
+    /// of its url.
+    pub(crate) fn adopt_relay(&mut self, relay: RpcRelay) {
+        for info in self.forks.values_mut() {
+            if info.url == relay.url {
+                info.relay = Some(relay.clone());
+            }
+        }
+        self.rpc_relays
+            .insert(RelayKey::new(&relay.upstream), relay);
+    }
+}
+
+impl From<&str> for Upstream {
+    fn from(url: &str) -> Self {
+        Self {
+            urls: vec![url.to_string()],
+            ..Default::default()
+        }
+    }
+}
+
+pub(crate) fn is_http(url: &str) -> bool {
+    url.starts_with("http://") || url.starts_with("https://")
+}
+
+/// `Authorization` header for bearer token auth.
+pub fn bearer_auth(token: &str) -> (String, String) {
+    ("Authorization".to_string(), format!("Bearer {token}"))
+}
+
+/// Http client for `url` sending the headers of `upstream`.
+pub(crate) fn http_client(url: &str, upstream: &Upstream) -> Result<Http, ForkerError> {
+    let mut header_map = reqwest::header::HeaderMap::new();
+    for (name, value) in &upstream.headers {
+        let invalid = || ForkerError::InvalidHeader(name.clone());
+        let header_name =
+            reqwest::header::HeaderName::from_bytes(name.as_bytes()).map_err(|_| invalid())?;
+        let mut value = reqwest::header::HeaderValue::from_str(value).map_err(|_| invalid())?;
+        value.set_sensitive(true);
+        header_map.insert(header_name, value);
+    }
+    let client = reqwest::Client::builder()
+        .default_headers(header_map)
+        .build()
+        .map_err(|e| ForkerError::rpc(url, e))?;
+    let url = reqwest::Url::parse(url).map_err(|_| ForkerError::InvalidRpcUrl(url.to_string()))?;
+    Ok(Http::new_with_client(url, client))
+}
+
+fn random_token() -> String {
+    rand::thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(32)
+        .map(char::from)
+        .collect()
+}
+
+async fn relay_http<C: JsonRpcClient>(
+    client: Arc<C>,
+    path: Arc<String>,
+    request: Request<Body>,
+) -> Result<Response<Body>, Infallible> {
+    if request.uri().path() != path.as_str() {
+        return Ok(Response::builder()
+            .status(StatusCode::NOT_FOUND)
+            .body(Body::empty())
+            .unwrap());
+    }
+    let body = hyper::body::to_bytes(request.into_body())
+        .await
+        .unwrap_or_default();
+    let response = match serde_json::from_slice::<Value>(&body) {
+        Ok(Value::Array(requests)) => {
+            let mut responses = Vec::with_capacity(requests.len());
+            for request in &requests {
+                responses.push(forward(&*client, request).await);
+            }
+            Value::Array(responses)
+        }
+        Ok(request) => forward(&*client, &request).await,
+        Err(e) => json!({
+            "jsonrpc": "2.0",
+            "id": Value::Null,
+            "error": { "code": -32700, "message": e.to_string() },
+        }),
+    };
+    Ok(Response::builder()
+        .header("content-type", "application/json")
+        .body(Body::from(response.to_string()))
+        .unwrap())
+}
+
+async fn forward<C: JsonRpcClient>(client: &C, request: &Value) -> Value {
+    let id = request.get("id").cloned().unwrap_or(Value::Null);
+    let method = request
+        .get("method")
+        .and_then(Value::as_str)
+        .unwrap_or_default();
+    let params = request
+        .get("params")
+        .cloned()
+        .unwrap_or(Value::Array(vec![]));
+    match client.request::<_, Value>(method, params).await {
+        Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+        Err(e) => {
+            // upstream errors keep their code, so callers still see reverts
+            // and rate limits for what they are
+            let error = match e.as_error_response() {
+                Some(error) => json!({
+                    "code": error.code,
+                    "message": error.message,
+                    "data": error.data,
+                }),
+                None => json!({ "code": -32603, "message": e.to_string() }),
+            };
+            json!({ "jsonrpc": "2.0", "id": id, "error": error })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{bearer_auth, RelayKey, Upstream};
+    use crate::{
+        forks::RpcConfig,
+        testing::{evm_with_code, fixed_relay},
+        ForkerError,
+    };
+    use ethers::providers::{Http, HttpClientError, JsonRpcClient};
+    use hyper::{
+        service::{make_service_fn, service_fn},
+        Body, Request, Response, Server,
+    };
+    use serde_json::{json, Value};
+    use std::{
+        collections::BTreeMap,
+        convert::Infallible,
+        net::SocketAddr,
+        str::FromStr,
+        time::{Duration, Instant},
+    };
+
+    /// Rpc answering `eth_chainId` only to requests carrying `bearer_auth`
+    /// of `token`, served on the test's runtime.
+    fn authorized_rpc(token: &'static str) -> String {
+        let make_service = make_service_fn(move |_| async move {
+            Ok::<_, Infallible>(service_fn(move |request: Request<Body>| async move {
+                let authorized = request
+                    .headers()
+                    .get("authorization")
+                    .is_some_and(|value| *value == bearer_auth(token).1);
+                let body = hyper::body::to_bytes(request.into_body()).await.unwrap();
+                let id = serde_json::from_slice::<Value>(&body).unwrap()["id"].clone();
+                let response = if authorized {
+                    json!({ "jsonrpc": "2.0", "id": id, "result": "0x1" })
+                } else {
+                    json!({
+                        "jsonrpc": "2.0",
+                        "id": id,
+                        "error": { "code": -32001, "message": "unauthorized" },
+                    })
+                };
+                Ok::<_, Infallible>(Response::new(Body::from(response.to_string())))
+            }))
+        });
+        let server = Server::bind(&SocketAddr::from(([127, 0, 0, 1], 0))).serve(make_service);
+        let url = format!("http://{}", server.local_addr());
+        tokio::spawn(server);
+        url
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_ipc_rpcs_are_relayed() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let path = std::env::temp_dir().join(format!("forker-{}.ipc", super::random_token()));
+        let listener = tokio::net::UnixListener::bind(&path).unwrap();
+        tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut buffer = vec![0; 4096];
+            loop {
+                let read = stream.read(&mut buffer).await.unwrap();
+                if read == 0 {
+                    break;
+                }
+                let request: Value = serde_json::from_slice(&buffer[..read]).unwrap();
+                let response = json!({ "jsonrpc": "2.0", "id": request["id"], "result": "0x1" });
+                stream
+                    .write_all(response.to_string().as_bytes())
+                    .await
+                    .unwrap();
+            }
+        });
+
+        let mut forked_evm = evm_with_code(&[]).await;
+        let url = forked_evm
+            .serving_url(path.to_str().unwrap())
+            .await
+            .unwrap();
+        assert!(url.starts_with("http://127.0.0.1:"));
+        let client = Http::from_str(&url).unwrap();
+        let chain_id: String = client.request("eth_chainId", ()).await.unwrap();
+        assert_eq!(chain_id, "0x1");
+        let _ = std::fs::remove_file(path);
+
+        assert_eq!(
+            forked_evm.serving_url("http://rpc.invalid").await.unwrap(),
+            "http://rpc.invalid"
+        );
+        assert!(matches!(
+            forked_evm.serving_url("ftp://rpc.invalid").await,
+            Err(ForkerError::InvalidRpcUrl(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_relays_send_upstream_headers() {
+        let rpc = authorized_rpc("secret");
+        let mut forked_evm = evm_with_code(&[]).await;
+        let mut upstream = Upstream {
+            urls: vec![rpc],
+            ..Default::default()
+        };
+        let unauthorized = forked_evm.relay_for(upstream.clone()).await.unwrap();
+        let (name, value) = bearer_auth("secret");
+        upstream.headers = BTreeMap::from([(name, value)]);
+        let authorized = forked_evm.relay_for(upstream.clone()).await.unwrap();
+        assert_ne!(unauthorized.url, authorized.url);
+        // the same upstream reuses its relay
+        let reused = forked_evm.relay_for(upstream.clone()).await.unwrap();
+        assert_eq!(reused.url, authorized.url);
+
+        let client = Http::from_str(&authorized.url).unwrap();
+        let chain_id: String = client.request("eth_chainId", ()).await.unwrap();
+        assert_eq!(chain_id, "0x1");
+        let client = Http::from_str(&unauthorized.url).unwrap();
+        assert!(client
+            .request::<_, String>("eth_chainId", ())
+            .await
+            .is_err());
+
+        // credentials never end up in keys or debug output
+        assert!(!format!("{:?}", RelayKey::new(&upstream)).contains("secret"));
+        assert!(!format!("{upstream:?}").contains("secret"));
+    }
+
+    #[tokio::test]
+    async fn test_relays_only_answer_their_token() {
+        let relay = fixed_relay(Ok(json!("0x1"))).await;
+        let client = Http::from_str(&relay.url).unwrap();
+        let chain_id: String = client.request("eth_chainId", ()).await.unwrap();
+        assert_eq!(chain_id, "0x1");
+
+        let (root, token) = relay.url.rsplit_once('/').unwrap();
+        assert_eq!(token.len(), 32);
+        let guessed = Http::from_str(&format!("{root}/")).unwrap();
+        assert!(guessed
+            .request::<_, String>("eth_chainId", ())
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_relays_keep_upstream_error_codes() {
+        let relay = fixed_relay(Err(429)).await;
+        let client = Http::from_str(&relay.url).unwrap();
+        match client.request::<_, String>("eth_chainId", ()).await {
+            Err(HttpClientError::JsonRpcError(error)) => assert_eq!(error.code, 429),
+            other => panic!("expected a json-rpc error, got {other:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_rate_limited_relays_share_a_budget() {
+        let rpc = fixed_relay(Ok(json!("0x1"))).await;
+        let mut forked_evm = evm_with_code(&[]).await;
+        forked_evm.set_rpc_config(RpcConfig {
+            requests_per_second: Some(10),
+            ..Default::default()
+        });
+        // relays of the same provider, e.g. with different headers
+        let upstream = forked_evm.configured_upstream(vec![rpc.url.clone()]);
+        let first = forked_evm.relay_for(upstream.clone()).await.unwrap();
+        let (name, value) = bearer_auth("secret");
+        let second = forked_evm
+            .relay_for(Upstream {
+                headers: BTreeMap::from([(name, value)]),
+                ..upstream
+            })
+            .await
+            .unwrap();
+        assert_ne!(first.url, second.url);
+        assert_eq!(forked_evm.rpc_limiters.len(), 1);
+
+        let start = Instant::now();
+        for relay in [&first, &second, &first] {
+            let client = Http::from_str(&relay.url).unwrap();
+            let chain_id: String = client.request("eth_chainId", ()).await.unwrap();
+            assert_eq!(chain_id, "0x1");
+        }
+        // the first request goes out at once, the next two 100ms apart
+        assert!(start.elapsed() >= Duration::from_millis(200));
+    }
+}
@@ -0,0 +1,156 @@
+use crate::{CallOptions, ForkedEvm, ForkerError};
+use alloy_sol_types::SolCall;
+use foundry_evm::executors::RawCallResult;
+use revm::primitives::{Address, Bytes, U256};
+
+/// Fluent alternative to the positional call and write methods, built by
+/// [ForkedEvm::tx].
+pub struct TxBuilder<'a> {
+    forked_evm: &'a mut ForkedEvm,
+    from: Address,
+    to: Option<Address>,
+    calldata: Bytes,
+    opts: CallOptions,
+}
+
+impl ForkedEvm {
+    /// Starts a transaction from the zero address with no calldata.
+    pub fn tx(&mut self) -> TxBuilder<'_> {
+        TxBuilder {
+            forked_evm: self,
+            from: Address::ZERO,
+            to: None,
+            calldata: Bytes::new(),
+            opts: CallOptions::default(),
+        }
+    }
+}
+
+impl TxBuilder<'_> {
+    pub fn from(mut self, from: Address) -> Self {
+        self.from = from;
+        self
+    }
+
+    pub fn to(mut self, to: Address) -> Self {
+        self.to = Some(to);
+        self
+    }
+
+    pub fn calldata(mut self, calldata: impl Into<Bytes>) -> Self {
+        self.calldata = calldata.into();
+        self
+    }
+
+    /// Sets the calldata to the abi encoding of a typed call.
+    pub fn call<C: SolCall>(self, call: &C) -> Self {
+        self.calldata(call.abi_encode())
+    }
+
+    pub fn value(mut self, value: U256) -> Self {
+        self.opts.value = Some(value);
+        self
+    }
+
+    pub fn gas(mut self, gas_limit: u64) -> Self {
+        self.opts.gas_limit = Some(gas_limit);
+        self
+    }
+
+    pub fn gas_price(mut self, gas_price: U256) -> Self {
+        self.opts.gas_price = Some(gas_price);
+        self
+    }
+
+    pub fn nonce(mut self, nonce: u64) -> Self {
+        self.opts.nonce = Some(nonce);
+        self
+    }
+
+    pub fn chain_id(mut self, chain_id: u64) -> Self {
+        self.opts.chain_id = Some(chain_id);
+        self
+    }
+
+    pub fn origin(mut self, origin: Address) -> Self {
+        self.opts.origin = Some(origin);
+        self
+    }
+
+    /// Replaces all options at once, e.g. with shared defaults.
+    pub fn options(mut self, opts: CallOptions) -> Self {
+        self.opts = opts;
+        self
+    }
+
+    /// Executes the transaction without committing it.
+    pub fn simulate(self) -> Result<RawCallResult, ForkerError> {
+        let to = self.target()?;
+        self.forked_evm.call_with_opts(
+            self.from.as_slice(),
+            to.as_slice(),
+            &self.calldata,
+            &self.opts,
+        )
+    }
+
+    /// Executes the transaction and commits its state changes.
+    pub fn commit(self) -> Result<RawCallResult, ForkerError> {
+        let to = self.target()?;
+        self.forked_evm.write_with_opts(
+            self.from.as_slice(),
+            to.as_slice(),
+            &self.calldata,
+            self.opts.value.unwrap_or_default(),
+            &self.opts,
+        )
+    }
+
+    fn target(&self) -> Result<Address, ForkerError> {
+        self.to.ok_or(ForkerError::MissingRecipient)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{testing::evm_with_code, ForkerError};
+    use revm::primitives::{Address, U256};
+
+    #[tokio::test]
+    async fn test_builder_simulates_and_commits() {
+        let sender = Address::repeat_byte(0x11);
+        let target = Address::repeat_byte(0x22);
+        // stores CALLVALUE at slot 0
+        let mut forked_evm = evm_with_code(&[(target, "34600055")]).await;
+        forked_evm
+            .insert_account(sender, U256::from(10), 0, Default::default(), [])
+            .unwrap();
+        assert!(matches!(
+            forked_evm.tx().from(sender).simulate(),
+            Err(ForkerError::MissingRecipient)
+        ));
+
+        let simulated = forked_evm
+            .tx()
+            .from(sender)
+            .to(target)
+            .value(U256::from(3))
+            .simulate()
+            .unwrap();
+        assert!(!simulated.reverted);
+        assert_eq!(forked_evm.balance_of(target).unwrap(), U256::ZERO);
+
+        forked_evm
+            .tx()
+            .from(sender)
+            .to(target)
+            .value(U256::from(3))
+            .commit()
+            .unwrap();
+        assert_eq!(forked_evm.balance_of(target).unwrap(), U256::from(3));
+        assert_eq!(
+            forked_evm.storage_at(target, U256::ZERO).unwrap(),
+            U256::from(3)
+        );
+    }
+}
@@ -0,0 +1,45 @@
+//! wasm-bindgen bindings so browser based tooling can run fork-backed
+//! dry-runs, enabled by the `wasm` feature.
+
+use crate::ForkedEvm;
+use foundry_evm::backend::Backend;
+use wasm_bindgen::prelude::*;
+
+/// A fork of an rpc endpoint exposed to js.
+#[wasm_bindgen]
+pub struct Forker {
+    forked_evm: ForkedEvm,
+}
+
+#[wasm_bindgen]
+impl Forker {
+    /// Forks `fork_url` at `fork_block_number`, or at the latest block when
+    /// omitted.
+    pub async fn fork(fork_url: String, fork_block_number: Option<u64>) -> Result<Forker, JsError> {
+        let fork_opts =
+            ForkedEvm::create_fork_opts(&fork_url, fork_block_number, &Default::default())
+                .await
+                .map_err(|e| JsError::new(&e.to_string()))?;
+        let env = fork_opts.env.clone();
+        let backend = Backend::spawn(Some(fork_opts)).await;
+        Ok(Forker {
+            forked_evm: ForkedEvm::new_with_shared_backend(backend, env, None),
+        })
+    }
+
+    /// Runs a call against the fork without committing it and returns its
+    /// output, rejecting with the revert data if it reverts.
+    pub fn call(&self, from: &[u8], to: &[u8], calldata: &[u8]) -> Result<Vec<u8>, JsError> {
+        let result = self
+            .forked_evm
+            .call(from, to, calldata)
+            .map_err(|e| JsError::new(&e.to_string()))?;
+        if result.reverted {
+            return Err(JsError::new(&format!(
+                "execution reverted: {}",
+                result.result
+            )));
+        }
+        Ok(result.result.to_vec())
+    }
+}
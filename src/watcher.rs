@@ -0,0 +1,139 @@
+use crate::{ForkedEvm, ForkerError};
+use alloy_rpc_types::BlockNumberOrTag;
+use foundry_evm::fork::ForkId;
+use std::{sync::Arc, time::Duration};
+use tokio::{
+    sync::{mpsc, Mutex},
+    task::JoinHandle,
+};
+
+/// Notification sent by a [BlockWatcher].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WatchEvent {
+    /// The watched fork was rolled to a new chain tip.
+    NewHead { block_number: u64 },
+    /// Polling or rolling failed, the watcher keeps polling.
+    Error(String),
+}
+
+/// Background task keeping a fork pinned to its chain's tip, stopped when
+/// dropped or when its event receiver is dropped.
+pub struct BlockWatcher {
+    handle: JoinHandle<()>,
+}
+
+impl BlockWatcher {
+    /// Polls the chain of `fork_id` every `interval` and rolls the fork to
+    /// each new tip, keeping whichever fork was active selected. Local
+    /// writes on the watched fork are handled as in
+    /// [ForkedEvm::refresh_to_latest].
+    pub fn spawn(
+        forked_evm: Arc<Mutex<ForkedEvm>>,
+        fork_id: ForkId,
+        interval: Duration,
+        preserve_local_writes: bool,
+    ) -> (Self, mpsc::UnboundedReceiver<WatchEvent>) {
+        let (events, receiver) = mpsc::unbounded_channel();
+        let handle = tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(interval).await;
+                let forked_evm = forked_evm.clone();
+                let fork_id = fork_id.clone();
+                // fork rpc reads block, so they run off the async workers
+                let refreshed = tokio::task::spawn_blocking(move || {
+                    roll_to_tip(
+                        &mut forked_evm.blocking_lock(),
+                        &fork_id,
+                        preserve_local_writes,
+                    )
+                })
+                .await;
+                let event = match refreshed {
+                    Ok(Ok(Some(block_number))) => WatchEvent::NewHead { block_number },
+                    Ok(Ok(None)) => continue,
+                    Ok(Err(e)) => WatchEvent::Error(e.to_string()),
+                    Err(e) => WatchEvent::Error(e.to_string()),
+                };
+                if events.send(event).is_err() {
+                    break;
+                }
+            }
+        });
+        (Self { handle }, receiver)
+    }
+
+    /// Stops the watcher, same as dropping it.
+    pub fn stop(self) {}
+}
+
+impl Drop for BlockWatcher {
+    fn drop(&mut self) {
+        self.handle.abort();
+    }
+}
+
+/// Rolls the fork `fork_id` to its chain's tip if it moved, returning the
+/// new block.
+fn roll_to_tip(
+    forked_evm: &mut ForkedEvm,
+    fork_id: &ForkId,
+    preserve_local_writes: bool,
+) -> Result<Option<u64>, ForkerError> {
+    let active = forked_evm.executor.backend.active_fork_id();
+    let previous = forked_evm
+        .forks()
+        .find(|(_, info)| Some(info.local_id) == active)
+        .map(|(fork_id, _)| fork_id.clone());
+
+    forked_evm.select(fork_id)?;
+    let rolled = roll_active_to_tip(forked_evm, preserve_local_writes);
+    if let Some(previous) = previous {
+        forked_evm.select(&previous)?;
+    }
+    rolled
+}
+
+fn roll_active_to_tip(
+    forked_evm: &mut ForkedEvm,
+    preserve_local_writes: bool,
+) -> Result<Option<u64>, ForkerError> {
+    let url = forked_evm
+        .active_fork()
+        .map(|info| info.url.clone())
+        .ok_or(ForkerError::NoActiveFork)?;
+    let latest: u64 = forked_evm
+        .get_block(BlockNumberOrTag::Latest)?
+        .header
+        .number
+        .ok_or_else(|| ForkerError::rpc(&url, "latest block has no number"))?
+        .saturating_to();
+    if forked_evm.pinned_block() >= Some(latest) {
+        return Ok(None);
+    }
+    forked_evm.roll_to_block(latest, preserve_local_writes)?;
+    Ok(Some(latest))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{BlockWatcher, WatchEvent};
+    use crate::testing::evm_with_code;
+    use foundry_evm::fork::ForkId;
+    use std::{sync::Arc, time::Duration};
+    use tokio::sync::Mutex;
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_watcher_reports_errors_and_keeps_polling() {
+        let forked_evm = Arc::new(Mutex::new(evm_with_code(&[]).await));
+        let (watcher, mut events) = BlockWatcher::spawn(
+            forked_evm,
+            ForkId("unknown".to_string()),
+            Duration::from_millis(1),
+            false,
+        );
+        for _ in 0..2 {
+            assert!(matches!(events.recv().await, Some(WatchEvent::Error(_))));
+        }
+        watcher.stop();
+    }
+}